@@ -1,64 +1,180 @@
 use std::env;
-use std::fs::OpenOptions;
-use std::io::Write;
+use std::error::Error;
+use std::fs::{self, OpenOptions};
+use std::path::Path;
 
 #[macro_use]
 extern crate bitfield;
 
 pub mod maxhell;
 
+use maxhell::assembler::{self, Module, Section};
+use maxhell::cubin::{self, Kernel};
 use maxhell::definition::*;
+use maxhell::disassembler;
+use maxhell::dump::{self, Scheduling};
+use maxhell::emitter::{Emitter, OutputFormat};
 use maxhell::encoder;
+use maxhell::nvn;
+use maxhell::program::{self, Container, EndOfProgram, Program, ProgramInfo, Terminator};
+use maxhell::sph::{ShaderProgramHeader, ShaderType};
+use maxhell::target::Target;
 
-fn write_shader(file_name: &str, instructions: &[u64]) -> std::io::Result<()> {
+fn write_output(
+    file_name: &str,
+    emitter: &dyn Emitter,
+    binary: &[u8],
+) -> Result<(), Box<dyn Error>> {
     let mut file = OpenOptions::new()
         .write(true)
         .create(true)
         .truncate(true)
         .open(file_name)?;
 
-    let mut position = 0;
+    emitter.emit(&mut file, binary)?;
 
-    // TODO: proper scheduling
-    let stub_sched_control = 0x1f8000fc0007e0u64;
+    Ok(())
+}
 
-    for instruction in instructions {
-        // We already have put 3 instructions, add scheduling control
-        // TODO: handle that in a real way
-        if (position % 0x20) == 0 {
-            file.write_all(&stub_sched_control.to_le_bytes())?;
-            position += 0x8;
-        }
+fn write_module(
+    file_name: &str,
+    emitter: &dyn Emitter,
+    module: &Module,
+    header: Option<&ShaderProgramHeader>,
+) -> Result<(), Box<dyn Error>> {
+    let mut binary = Vec::new();
+
+    // NOTE: the code following the header must be aligned on 0x20 bytes once loaded.
+    if let Some(header) = header {
+        binary.extend_from_slice(&header.to_bytes());
+    }
 
-        file.write_all(&instruction.to_le_bytes())?;
+    let base = binary.len() as u64;
 
-        position += 0x8;
+    for symbol in module.symbols.iter().filter(|symbol| symbol.global) {
+        println!(
+            "Symbol {}: 0x{:x}",
+            symbol.name,
+            base + module.symbol_offset(symbol)
+        );
     }
 
-    while (position % 0x20) != 0 {
-        file.write_all(&encoder::encode_nop(false, 7, false, 0, ControlCode::TRUE).to_le_bytes())?;
-        position += 0x8;
+    binary.extend_from_slice(&module.to_bytes()?);
+
+    write_output(file_name, emitter, &binary)
+}
+
+/// The kernels of a module, one per code section.
+///
+/// The default section is named after --cubin, the other ones after their `.entry`.
+fn module_kernels<'a>(
+    module: &'a Module,
+    default_name: &str,
+) -> Result<Vec<(&'a Program, Kernel)>, Box<dyn Error>> {
+    let mut kernels = Vec::new();
+
+    for (index, section) in module.sections.iter().enumerate() {
+        let program = section
+            .program()
+            .ok_or_else(|| format!("data section {} cannot be written in a cubin", section.name))?;
+
+        let name = if section.name == assembler::DEFAULT_SECTION {
+            default_name
+        } else {
+            module
+                .entries()
+                .find(|symbol| symbol.section == index)
+                .map(|symbol| symbol.name.as_str())
+                .ok_or_else(|| format!("code section {} has no .entry", section.name))?
+        };
+
+        // TODO: allow to declare the parameters and shared memory of the kernels.
+        kernels.push((program, Kernel::new(name)));
+    }
+
+    if kernels.is_empty() {
+        return Err("no kernel to write in the cubin".into());
+    }
+
+    Ok(kernels)
+}
+
+fn import_dump(
+    target: Target,
+    dump_file_name: &str,
+    file_name: Option<&String>,
+    scheduling: Scheduling,
+) -> Result<(), Box<dyn Error>> {
+    let program = dump::import_dump(target, &fs::read_to_string(dump_file_name)?, scheduling)?;
+    let source = dump::to_source(&program)?;
+
+    match file_name {
+        Some(file_name) => fs::write(file_name, source)?,
+        None => print!("{}", source),
     }
 
     Ok(())
 }
 
-fn main() -> std::io::Result<()> {
-    let file_name = env::args().nth(1).expect("Cannot find file argument");
+fn disassemble_cubin(file_name: &str) -> Result<(), Box<dyn Error>> {
+    let cubin = cubin::read_cubin(&fs::read(file_name)?)?;
+
+    for kernel in &cubin.kernels {
+        println!("{} ({}):", kernel.name, cubin.target);
 
-    let mut instructions = Vec::new();
+        for record in cubin.kernel_records(kernel) {
+            println!("    // {:?}", record);
+        }
 
-    instructions.push(encoder::encode_ram());
-    instructions.push(encoder::encode_sam());
+        for (index, instruction) in kernel
+            .program(cubin.target)
+            .instructions()
+            .iter()
+            .enumerate()
+        {
+            let text = disassembler::disassemble(*instruction)
+                .unwrap_or_else(|| String::from("<invalid>"));
+
+            println!(
+                "    /*{:04x}*/ {} /* 0x{:016x} */",
+                program::instruction_address(index),
+                text,
+                instruction
+            );
+        }
+    }
+
+    Ok(())
+}
 
-    instructions.push(encoder::encode_ret(7, false, ControlCode::TRUE));
-    instructions.push(encoder::encode_exit(7, false, ControlCode::TRUE, false));
-    instructions.push(encoder::encode_nop(false, 7, false, 0, ControlCode::TRUE));
-    instructions.push(encoder::encode_get_lmembase(42));
-    instructions.push(encoder::encode_set_lmembase(42));
-    instructions.push(encoder::encode_ide(42, false));
-    instructions.push(encoder::encode_kil(7, false, ControlCode::TRUE));
-    instructions.push(encoder::encode_al2p(
+/// The program written when no source is given.
+fn example_program(target: Target) -> Result<Program, Box<dyn Error>> {
+    let mut program = Program::new(target);
+
+    program.push(encoder::encode_ram(target)?);
+    program.push(encoder::encode_sam(target)?);
+    program.push(encoder::encode_ret(target, 7, false, ControlCode::TRUE)?);
+    program.push(encoder::encode_exit(
+        target,
+        7,
+        false,
+        ControlCode::TRUE,
+        false,
+    )?);
+    program.push(encoder::encode_nop(
+        target,
+        false,
+        7,
+        false,
+        0,
+        ControlCode::TRUE,
+    )?);
+    program.push(encoder::encode_get_lmembase(target, 42)?);
+    program.push(encoder::encode_set_lmembase(target, 42)?);
+    program.push(encoder::encode_ide(target, 42, false)?);
+    program.push(encoder::encode_kil(target, 7, false, ControlCode::TRUE)?);
+    program.push(encoder::encode_al2p(
+        target,
         7,
         false,
         7,
@@ -67,8 +183,9 @@ fn main() -> std::io::Result<()> {
         false,
         AtributeLoadMode::M128,
         0x0,
-    ));
-    instructions.push(encoder::encode_ald(
+    )?);
+    program.push(encoder::encode_ald(
+        target,
         7,
         false,
         1,
@@ -78,8 +195,9 @@ fn main() -> std::io::Result<()> {
         false,
         AtributeLoadMode::M128,
         0x0,
-    ));
-    instructions.push(encoder::encode_ast(
+    )?);
+    program.push(encoder::encode_ast(
+        target,
         7,
         false,
         1,
@@ -88,8 +206,33 @@ fn main() -> std::io::Result<()> {
         true,
         AtributeLoadMode::M128,
         0x0,
-    ));
-    instructions.push(encoder::encode_atoms(
+    )?);
+    program.push(encoder::encode_ipa(
+        target,
+        7,
+        false,
+        3,
+        0xFF,
+        0xFF,
+        0xFF,
+        0x7c,
+        false,
+        IpaInterpolationMode::PASS,
+        IpaSampleMode::DEFAULT,
+        false,
+    )?);
+    program.push(encoder::encode_out(
+        target,
+        7,
+        false,
+        0,
+        0,
+        0,
+        true,
+        OutMode::EMIT,
+    )?);
+    program.push(encoder::encode_atoms(
+        target,
         7,
         false,
         4,
@@ -98,8 +241,9 @@ fn main() -> std::io::Result<()> {
         0x14,
         AtomsPrimitiveType::S32,
         AtomsOperation::EXCH,
-    ));
-    instructions.push(encoder::encode_atoms_cas(
+    )?);
+    program.push(encoder::encode_atoms_cas(
+        target,
         7,
         false,
         4,
@@ -108,8 +252,9 @@ fn main() -> std::io::Result<()> {
         0x14,
         AtomicCasPrimitiveType::U32,
         AtomsCasOperation::CAS,
-    ));
-    instructions.push(encoder::encode_atom(
+    )?);
+    program.push(encoder::encode_atom(
+        target,
         7,
         false,
         4,
@@ -119,8 +264,20 @@ fn main() -> std::io::Result<()> {
         AtomPrimitiveType::U64,
         AtomOperation::SAFE_ADD,
         false,
-    ));
-    instructions.push(encoder::encode_atom_cas(
+    )?);
+    program.push(encoder::encode_red(
+        target,
+        7,
+        false,
+        1,
+        2,
+        0x14,
+        AtomPrimitiveType::U32,
+        AtomOperation::ADD,
+        false,
+    )?);
+    program.push(encoder::encode_atom_cas(
+        target,
         7,
         false,
         4,
@@ -129,13 +286,294 @@ fn main() -> std::io::Result<()> {
         0x14,
         AtomicCasPrimitiveType::U64,
         false,
-    ));
+    )?);
+    program.push(encoder::encode_texs(
+        target,
+        7,
+        false,
+        0,
+        2,
+        4,
+        0xFF,
+        0x8,
+        TextureType::TEXTURE_2D,
+        TextureLodMode::LZ,
+        false,
+        0xF,
+        true,
+        false,
+    )?);
+    program.push(encoder::encode_sust(
+        target,
+        7,
+        false,
+        4,
+        0,
+        0x0,
+        false,
+        SurfaceType::SURFACE_2D,
+        false,
+        SurfaceSize::B32,
+        0xF,
+        SurfaceClampMode::IGN,
+    )?);
+    program.push(encoder::encode_vabsdiff(
+        target,
+        7,
+        false,
+        5,
+        1,
+        VideoSelector::B0,
+        false,
+        2,
+        Some(VideoSelector::B0),
+        false,
+        false,
+        5,
+        VideoSecondaryOperation::ACC,
+        false,
+    )?);
+    program.push(encoder::encode_membar(target, 7, false, MembarLevel::CTA)?);
+    program.push(encoder::encode_bar(
+        target,
+        7,
+        false,
+        BarMode::RED,
+        BarReductionOperation::POPC,
+        0,
+        true,
+        0,
+        true,
+        0,
+        false,
+    )?);
     // NOTE: do not change operation as nvdisasm seems to not support it correctly at all.
-    instructions.push(encoder::encode_b2r(7, false, 0, 7, B2ROperation::BAR, 0));
+    program.push(encoder::encode_b2r(
+        target,
+        7,
+        false,
+        0,
+        7,
+        B2ROperation::BAR,
+        0,
+    )?);
 
-    println!("Instruction: 0x{:x}", instructions.last().unwrap());
+    Ok(program)
+}
 
-    write_shader(file_name.as_str(), &instructions)?;
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut target = Target::default();
+    let mut shader_type = None;
+    let mut kernel_name = None;
+    let mut disassemble = false;
+    let mut nvn = false;
+    let mut print_info = false;
+    let mut import = None;
+    let mut terminator = None;
+    let mut alignment = None;
+    let mut format = OutputFormat::default();
+    let mut file_names = Vec::new();
 
-    Ok(())
+    let mut args = env::args().skip(1);
+
+    while let Some(arg) = args.next() {
+        if arg == "--arch" {
+            target = args.next().expect("Cannot find --arch value").parse()?;
+        } else if let Some(value) = arg.strip_prefix("--arch=") {
+            target = value.parse()?;
+        } else if arg == "--sph" {
+            shader_type = Some(
+                args.next()
+                    .expect("Cannot find --sph value")
+                    .parse::<ShaderType>()?,
+            );
+        } else if let Some(value) = arg.strip_prefix("--sph=") {
+            shader_type = Some(value.parse::<ShaderType>()?);
+        } else if arg == "--format" {
+            format = args
+                .next()
+                .expect("Cannot find --format value")
+                .parse::<OutputFormat>()?;
+        } else if let Some(value) = arg.strip_prefix("--format=") {
+            format = value.parse::<OutputFormat>()?;
+        } else if arg == "--import" {
+            import = Some(Scheduling::Detect);
+        } else if let Some(value) = arg.strip_prefix("--import=") {
+            import = Some(match value {
+                "sched" => Scheduling::Present,
+                "nosched" => Scheduling::Absent,
+                "detect" => Scheduling::Detect,
+                _ => return Err(format!("Unknown --import mode \"{}\"", value).into()),
+            });
+        } else if let Some(value) = arg.strip_prefix("--terminator=") {
+            terminator = Some(match value {
+                "none" => Terminator::Nothing,
+                "bra" => Terminator::SelfBranch,
+                _ => return Err(format!("Unknown terminator \"{}\"", value).into()),
+            });
+        } else if let Some(value) = arg.strip_prefix("--align=") {
+            alignment = Some(match value.strip_prefix("0x") {
+                Some(hex) => u64::from_str_radix(hex, 16)?,
+                None => value.parse::<u64>()?,
+            });
+        } else if arg == "--info" {
+            print_info = true;
+        } else if arg == "--nvn" {
+            nvn = true;
+        } else if arg == "--disassemble" {
+            disassemble = true;
+        } else if arg == "--cubin" {
+            kernel_name = Some(args.next().expect("Cannot find --cubin value"));
+        } else if let Some(value) = arg.strip_prefix("--cubin=") {
+            kernel_name = Some(String::from(value));
+        } else {
+            file_names.push(arg);
+        }
+    }
+
+    if disassemble {
+        let file_name = file_names.first().expect("Cannot find file argument");
+
+        return disassemble_cubin(file_name.as_str());
+    }
+
+    if let Some(scheduling) = import {
+        let dump_file_name = file_names.first().expect("Cannot find file argument");
+
+        return import_dump(target, dump_file_name, file_names.get(1), scheduling);
+    }
+
+    // NOTE: with a single file, the example program is written to it.
+    let (input_file_name, file_name) = match file_names.as_slice() {
+        [file_name] => (None, file_name),
+        [input_file_name, file_name] => (Some(input_file_name), file_name),
+        _ => panic!("Expected an optional source and an output file"),
+    };
+
+    let container = if kernel_name.is_some() {
+        Container::Cubin
+    } else if nvn {
+        Container::Nvn
+    } else {
+        Container::Raw
+    };
+
+    let mut module = match input_file_name {
+        Some(input_file_name) => {
+            // NOTE: the preprocessor gives included paths relative to the directory of the source.
+            let directory = Path::new(input_file_name)
+                .parent()
+                .map(Path::to_path_buf)
+                .unwrap_or_default();
+
+            assembler::assemble_module_with_includes(
+                target,
+                container,
+                &fs::read_to_string(input_file_name)?,
+                &mut |path| fs::read_to_string(directory.join(path)),
+            )?
+        }
+        None => Module::from_program(example_program(target)?),
+    };
+
+    let last_instruction = module
+        .sections
+        .iter()
+        .rev()
+        .filter_map(Section::program)
+        .find_map(|program| program.instructions().last().copied());
+
+    if let Some(last_instruction) = last_instruction {
+        println!("Instruction: 0x{:x}", last_instruction);
+
+        if let Some(text) = disassembler::disassemble(last_instruction) {
+            println!("Disassembly: {}", text);
+        }
+    }
+
+    if print_info {
+        for section in &module.sections {
+            if let Some(program) = section.program() {
+                println!("{}:\n{}", section.name, ProgramInfo::from_program(program));
+            }
+        }
+    }
+
+    let default_end = container.default_end_of_program();
+    let end = EndOfProgram {
+        terminator: terminator.unwrap_or(default_end.terminator),
+        alignment: alignment.unwrap_or(default_end.alignment),
+    };
+
+    container.validate(&end)?;
+
+    for program in module.sections.iter_mut().filter_map(Section::program_mut) {
+        program.finalize(&end)?;
+    }
+
+    let emitter = format.emitter();
+
+    if let Some(kernel_name) = kernel_name {
+        if nvn {
+            return Err("--nvn cannot be used with --cubin".into());
+        }
+
+        if shader_type.is_some() {
+            return Err(
+                "--sph cannot be used with --cubin, kernels have no shader program header".into(),
+            );
+        }
+
+        let kernels = module_kernels(&module, &kernel_name)?;
+        let kernels: Vec<(&Program, &Kernel)> = kernels
+            .iter()
+            .map(|(program, kernel)| (*program, kernel))
+            .collect();
+
+        return write_output(
+            file_name.as_str(),
+            emitter.as_ref(),
+            &cubin::write_cubin(&kernels)?,
+        );
+    }
+
+    if shader_type.is_some() && module.sections.len() > 1 {
+        return Err("--sph cannot be used with more than one section".into());
+    }
+
+    let header = match (
+        shader_type,
+        module.sections.first().and_then(Section::program),
+    ) {
+        (Some(shader_type), Some(program)) => {
+            Some(ShaderProgramHeader::from_program(program, shader_type))
+        }
+        _ => None,
+    };
+
+    if nvn {
+        let programs = module
+            .sections
+            .iter()
+            .map(|section| {
+                section
+                    .program()
+                    .map(|program| (program, header.as_ref()))
+                    .ok_or_else(|| {
+                        format!("data section {} cannot be written with --nvn", section.name)
+                    })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        let binary = nvn::write_nvn(&programs)?;
+
+        return write_output(file_name.as_str(), emitter.as_ref(), &binary);
+    }
+
+    write_module(
+        file_name.as_str(),
+        emitter.as_ref(),
+        &module,
+        header.as_ref(),
+    )
 }
@@ -0,0 +1,522 @@
+use std::error::Error;
+use std::fmt;
+
+use super::definition::*;
+use super::encoder::EncodeError;
+use super::program::{Container, Program};
+use super::target::Target;
+use super::util::{FieldDefinition, FieldKind};
+
+mod module;
+mod preprocessor;
+#[cfg(test)]
+mod tests;
+
+pub use module::*;
+pub use preprocessor::*;
+
+const SOURCE_PREDICATE_REGISTER: &str = "source_predicate_register";
+const INVERT_SOURCE_PREDICATE: &str = "invert_source_predicate";
+
+/// Error returned by the assembler.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum AssembleError {
+    /// The line doesn't contain any instruction.
+    MissingOpcode,
+    UnknownOpcode(String),
+    /// The instruction isn't available on the selected target.
+    UnsupportedInstruction(Opcode, Target),
+    /// The atomic operation cannot be performed on the primitive type.
+    InvalidAtomicOperation(Opcode, AtomOperation, AtomPrimitiveType),
+    /// Atomics on the primitive type aren't available on the selected target.
+    UnsupportedAtomicType(Opcode, AtomPrimitiveType, Target),
+    /// The texture instruction has no encoding for the texture type, LOD mode and flags.
+    UnsupportedTextureMode(Opcode, TextureType, TextureLodMode),
+    /// The texture instruction cannot write the set of components.
+    UnsupportedComponentMask(Opcode, u8),
+    /// The register given as operand B of a video instruction doesn't exist.
+    InvalidVideoRegister(Opcode, u16),
+    /// The register form of a video instruction requires a selector for operand B.
+    MissingVideoSelector(Opcode),
+    /// The immediate form of a video instruction doesn't have any selector for operand B.
+    UnexpectedVideoSelector(Opcode, VideoSelector),
+    /// The half-precision immediate would lose precision once encoded.
+    InexactHalfImmediate(Opcode, HalfPair),
+    UnknownField(Opcode, String),
+    DuplicateField(Opcode, String),
+    /// The value cannot be stored in the field.
+    InvalidValue(Opcode, String, String),
+    /// The instruction doesn't have a predicate guard.
+    UnexpectedPredicate(Opcode),
+    UnknownLabel(String),
+    DuplicateLabel(String),
+    /// The label is defined in another section than the branch using it.
+    UnreachableLabel(String),
+    UnknownDirective(String),
+    /// The operand of the directive is missing or invalid.
+    InvalidOperand(String, String),
+    /// The directive emits data, which cannot be mixed with instructions.
+    DataInCode(String),
+    /// The current section holds data.
+    InstructionInData,
+    /// The data section cannot be written to the container.
+    DataInContainer(String, Container),
+    /// The directive selects a section while assembling a single program.
+    UnexpectedDirective(String),
+    InvalidExpression(String),
+    /// The block opened by the directive isn't closed.
+    UnterminatedBlock(String),
+    /// The directive closes a block that isn't opened.
+    UnmatchedDirective(String),
+    DuplicateMacro(String),
+    /// The argument given to the macro doesn't match any of its parameters.
+    InvalidMacroArgument(String, String),
+    /// The parameter of the macro has no default and isn't given.
+    MissingMacroArgument(String, String),
+    /// The macro or the file is expanded within itself.
+    RecursionLimit(String),
+    /// The source expands to more lines than the given limit.
+    ExpansionLimit(usize),
+    /// The file cannot be included, for the given reason.
+    IncludeFailed(String, String),
+}
+
+impl fmt::Display for AssembleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AssembleError::MissingOpcode => write!(f, "missing opcode"),
+            AssembleError::UnknownOpcode(name) => write!(f, "unknown opcode {}", name),
+            AssembleError::UnsupportedInstruction(opcode, target) => {
+                write!(f, "{:?} is not supported on {}", opcode, target)
+            }
+            AssembleError::InvalidAtomicOperation(opcode, operation, type_size) => write!(
+                f,
+                "{:?} cannot perform {:?} on {:?}",
+                opcode, operation, type_size
+            ),
+            AssembleError::UnsupportedAtomicType(opcode, type_size, target) => write!(
+                f,
+                "{:?} on {:?} is not supported on {}",
+                opcode, type_size, target
+            ),
+            AssembleError::UnsupportedTextureMode(opcode, texture_type, lod_mode) => write!(
+                f,
+                "{:?} cannot encode {:?} with {:?} and the given flags",
+                opcode, texture_type, lod_mode
+            ),
+            AssembleError::UnsupportedComponentMask(opcode, component_mask) => write!(
+                f,
+                "{:?} cannot write the components 0x{:x}",
+                opcode, component_mask
+            ),
+            AssembleError::InvalidVideoRegister(opcode, register) => {
+                write!(f, "{:?} cannot read operand B from R{}", opcode, register)
+            }
+            AssembleError::MissingVideoSelector(opcode) => {
+                write!(f, "{:?} requires a selector for operand B", opcode)
+            }
+            AssembleError::UnexpectedVideoSelector(opcode, selector) => write!(
+                f,
+                "{:?} cannot use the selector {:?} with an immediate",
+                opcode, selector
+            ),
+            AssembleError::InexactHalfImmediate(opcode, immediate) => write!(
+                f,
+                "{:?} cannot encode the immediate ({}, {}) exactly",
+                opcode,
+                immediate.low(),
+                immediate.high()
+            ),
+            AssembleError::UnknownField(opcode, name) => {
+                write!(f, "{:?} has no field {}", opcode, name)
+            }
+            AssembleError::DuplicateField(opcode, name) => {
+                write!(f, "{:?}: {} is set more than once", opcode, name)
+            }
+            AssembleError::InvalidValue(opcode, name, value) => {
+                write!(f, "{:?}: invalid value {} for {}", opcode, value, name)
+            }
+            AssembleError::UnexpectedPredicate(opcode) => {
+                write!(f, "{:?} cannot be predicated", opcode)
+            }
+            AssembleError::UnknownLabel(name) => write!(f, "unknown label {}", name),
+            AssembleError::DuplicateLabel(name) => write!(f, "label {} is already defined", name),
+            AssembleError::UnreachableLabel(name) => {
+                write!(f, "label {} is defined in another section", name)
+            }
+            AssembleError::UnknownDirective(name) => write!(f, "unknown directive {}", name),
+            AssembleError::InvalidOperand(directive, operand) => {
+                write!(f, "{}: invalid operand \"{}\"", directive, operand)
+            }
+            AssembleError::DataInCode(directive) => {
+                write!(f, "{} cannot be used in a code section", directive)
+            }
+            AssembleError::InstructionInData => {
+                write!(f, "instructions cannot be used in a data section")
+            }
+            AssembleError::DataInContainer(section, container) => {
+                write!(
+                    f,
+                    "{:?} cannot hold the data section {}",
+                    container, section
+                )
+            }
+            AssembleError::UnexpectedDirective(directive) => {
+                write!(f, "{} cannot be used in a single program", directive)
+            }
+            AssembleError::InvalidExpression(expression) => {
+                write!(f, "invalid expression \"{}\"", expression)
+            }
+            AssembleError::UnterminatedBlock(directive) => {
+                write!(f, "{} is never closed", directive)
+            }
+            AssembleError::UnmatchedDirective(directive) => {
+                write!(f, "{} doesn't close any block", directive)
+            }
+            AssembleError::DuplicateMacro(name) => write!(f, "macro {} is already defined", name),
+            AssembleError::InvalidMacroArgument(name, argument) => {
+                write!(f, "{}: invalid argument \"{}\"", name, argument)
+            }
+            AssembleError::MissingMacroArgument(name, parameter) => {
+                write!(f, "{}: missing argument {}", name, parameter)
+            }
+            AssembleError::RecursionLimit(name) => {
+                write!(f, "{} is expanded too many times within itself", name)
+            }
+            AssembleError::ExpansionLimit(limit) => {
+                write!(f, "the source expands to more than {} lines", limit)
+            }
+            AssembleError::IncludeFailed(path, reason) => {
+                write!(f, "cannot include {}: {}", path, reason)
+            }
+        }
+    }
+}
+
+impl Error for AssembleError {}
+
+impl From<EncodeError> for AssembleError {
+    fn from(error: EncodeError) -> Self {
+        match error {
+            EncodeError::UnsupportedInstruction(opcode, target) => {
+                AssembleError::UnsupportedInstruction(opcode, target)
+            }
+            EncodeError::InvalidAtomicOperation(opcode, operation, type_size) => {
+                AssembleError::InvalidAtomicOperation(opcode, operation, type_size)
+            }
+            EncodeError::UnsupportedAtomicType(opcode, type_size, target) => {
+                AssembleError::UnsupportedAtomicType(opcode, type_size, target)
+            }
+            EncodeError::UnsupportedTextureMode(opcode, texture_type, lod_mode) => {
+                AssembleError::UnsupportedTextureMode(opcode, texture_type, lod_mode)
+            }
+            EncodeError::UnsupportedComponentMask(opcode, component_mask) => {
+                AssembleError::UnsupportedComponentMask(opcode, component_mask)
+            }
+            EncodeError::InvalidVideoRegister(opcode, register) => {
+                AssembleError::InvalidVideoRegister(opcode, register)
+            }
+            EncodeError::MissingVideoSelector(opcode) => {
+                AssembleError::MissingVideoSelector(opcode)
+            }
+            EncodeError::UnexpectedVideoSelector(opcode, selector) => {
+                AssembleError::UnexpectedVideoSelector(opcode, selector)
+            }
+            EncodeError::InexactHalfImmediate(opcode, immediate) => {
+                AssembleError::InexactHalfImmediate(opcode, immediate)
+            }
+        }
+    }
+}
+
+/// Error returned when assembling a source, with the line where it happened.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SourceError {
+    /// The included file holding the line, None for the source being assembled.
+    pub file: Option<String>,
+    /// The line number, starting at 1.
+    pub line: usize,
+    pub error: AssembleError,
+}
+
+impl SourceError {
+    fn new(source: &SourceLine, error: AssembleError) -> Self {
+        SourceError {
+            file: source.file.clone(),
+            line: source.line,
+            error,
+        }
+    }
+}
+
+impl fmt::Display for SourceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.file {
+            Some(file) => write!(f, "{}:{}: {}", file, self.line, self.error),
+            None => write!(f, "line {}: {}", self.line, self.error),
+        }
+    }
+}
+
+impl Error for SourceError {}
+
+fn parse_number(text: &str) -> Option<i128> {
+    let (negative, text) = match text.strip_prefix('-') {
+        Some(text) => (true, text),
+        None => (false, text),
+    };
+
+    let value = match text.strip_prefix("0x") {
+        Some(hex) => i128::from_str_radix(hex, 16).ok()?,
+        None => text.parse::<i128>().ok()?,
+    };
+
+    Some(if negative { -value } else { value })
+}
+
+/// Parse the value of a field as printed by the disassembler.
+fn parse_field_value(field: &FieldDefinition, text: &str) -> Option<u64> {
+    let bits = field.mask() >> field.lsb;
+
+    let value = match field.kind() {
+        FieldKind::Register if text == "RZ" => 0xFF,
+        FieldKind::Register => text.strip_prefix('R')?.parse::<u64>().ok()?,
+        FieldKind::Predicate if text == "PT" => 7,
+        FieldKind::Predicate => {
+            let value = text.strip_prefix('P')?.parse::<u64>().ok()?;
+
+            if value >= 7 {
+                return None;
+            }
+
+            value
+        }
+        FieldKind::Enum => match field.variant_value(text) {
+            Some(value) => value,
+            None => parse_number(text).filter(|value| *value >= 0)? as u64,
+        },
+        FieldKind::Flag | FieldKind::Immediate => {
+            let value = parse_number(text)?;
+            let (min, max) = if field.signed {
+                let half = 1i128 << (field.width() - 1);
+
+                (-half, half - 1)
+            } else {
+                (0, bits as i128)
+            };
+
+            if value < min || value > max {
+                return None;
+            }
+
+            value as u64 & bits
+        }
+    };
+
+    if value & !bits != 0 {
+        return None;
+    }
+
+    Some(value)
+}
+
+/// Split an instruction in its predicate guard, its mnemonic and its operands.
+fn split_instruction(text: &str) -> (Option<&str>, &str, &str) {
+    let text = text.trim();
+    let text = text.strip_suffix(';').unwrap_or(text).trim_end();
+
+    let (guard, text) = match text.strip_prefix('@') {
+        Some(rest) => {
+            let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+
+            (Some(&rest[..end]), rest[end..].trim_start())
+        }
+        None => (None, text),
+    };
+
+    let end = text.find(char::is_whitespace).unwrap_or(text.len());
+
+    (guard, &text[..end], text[end..].trim())
+}
+
+/// Assemble an instruction written as `@!P0 MNEMONIC field=value, flag, ...`.
+///
+/// This is the syntax printed by the disassembler. Fields that aren't given are
+/// zero, except for the predicate guard which defaults to PT.
+pub fn assemble_instruction(target: Target, text: &str) -> Result<u64, AssembleError> {
+    let (guard, name, operands) = split_instruction(text);
+
+    if name.is_empty() {
+        return Err(AssembleError::MissingOpcode);
+    }
+
+    let opcode =
+        Opcode::from_name(name).ok_or_else(|| AssembleError::UnknownOpcode(name.to_string()))?;
+
+    if !target.supports(opcode) {
+        return Err(AssembleError::UnsupportedInstruction(opcode, target));
+    }
+
+    let fields = opcode.fields();
+    let mut values = vec![0; fields.len()];
+    let mut assigned = vec![false; fields.len()];
+
+    let field_index = |name: &str| -> Result<usize, AssembleError> {
+        fields
+            .iter()
+            .position(|field| field.name == name)
+            .ok_or_else(|| AssembleError::UnknownField(opcode, name.to_string()))
+    };
+
+    if let Ok(index) = field_index(SOURCE_PREDICATE_REGISTER) {
+        values[index] = 7;
+    }
+
+    if let Some(guard) = guard {
+        let predicate_index = field_index(SOURCE_PREDICATE_REGISTER)
+            .map_err(|_| AssembleError::UnexpectedPredicate(opcode))?;
+        let invert_index = field_index(INVERT_SOURCE_PREDICATE)
+            .map_err(|_| AssembleError::UnexpectedPredicate(opcode))?;
+        let (invert, predicate) = match guard.strip_prefix('!') {
+            Some(predicate) => (true, predicate),
+            None => (false, guard),
+        };
+
+        values[predicate_index] = parse_field_value(&fields[predicate_index], predicate)
+            .ok_or_else(|| {
+                AssembleError::InvalidValue(
+                    opcode,
+                    SOURCE_PREDICATE_REGISTER.to_string(),
+                    predicate.to_string(),
+                )
+            })?;
+        values[invert_index] = invert as u64;
+        assigned[predicate_index] = true;
+        assigned[invert_index] = true;
+    }
+
+    for operand in operands
+        .split(',')
+        .map(str::trim)
+        .filter(|operand| !operand.is_empty())
+    {
+        let (name, value) = match operand.split_once('=') {
+            Some((name, value)) => (name.trim(), Some(value.trim())),
+            None => (operand, None),
+        };
+
+        let index = field_index(name)?;
+        let field = &fields[index];
+
+        if assigned[index] {
+            return Err(AssembleError::DuplicateField(opcode, name.to_string()));
+        }
+
+        values[index] = match value {
+            Some(value) => parse_field_value(field, value).ok_or_else(|| {
+                AssembleError::InvalidValue(opcode, name.to_string(), value.to_string())
+            })?,
+            // NOTE: a field given without value is a flag being set.
+            None if field.kind() == FieldKind::Flag => 1,
+            None => {
+                return Err(AssembleError::InvalidValue(
+                    opcode,
+                    name.to_string(),
+                    String::new(),
+                ))
+            }
+        };
+        assigned[index] = true;
+    }
+
+    Ok(opcode.encode(&values))
+}
+
+/// Field holding the branch offsets, relative to the following instruction.
+const OFFSET: &str = "offset";
+
+/// Check if the offset field of an instruction is a branch target, which can be a label.
+fn has_relative_offset(opcode: Opcode) -> bool {
+    matches!(
+        opcode,
+        Opcode::BRA | Opcode::PBK | Opcode::PCNT | Opcode::PRET | Opcode::SSY
+    )
+}
+
+fn is_label_name(name: &str) -> bool {
+    name.chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '.')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+}
+
+/// Split the label defined at the start of a line, written as `name:`.
+fn split_label(text: &str) -> (Option<&str>, &str) {
+    match text.split_once(':') {
+        Some((name, rest)) if is_label_name(name.trim()) => (Some(name.trim()), rest),
+        _ => (None, text),
+    }
+}
+
+/// Replace the label used as branch target of an instruction by its offset.
+///
+/// The lookup returns the address of a label, in the same section as the instruction.
+fn resolve_label(
+    text: &str,
+    address: u64,
+    lookup: impl Fn(&str) -> Result<u64, AssembleError>,
+) -> Result<String, AssembleError> {
+    let (guard, name, operands) = split_instruction(text);
+
+    match Opcode::from_name(name) {
+        Some(opcode) if has_relative_offset(opcode) => {}
+        _ => return Ok(String::from(text)),
+    }
+
+    let operands = operands
+        .split(',')
+        .map(|operand| match operand.split_once('=') {
+            Some((field, value)) if field.trim() == OFFSET && is_label_name(value.trim()) => {
+                let target = lookup(value.trim())?;
+
+                Ok(format!(
+                    "{}={}",
+                    OFFSET,
+                    target as i64 - (address as i64 + 8)
+                ))
+            }
+            _ => Ok(String::from(operand)),
+        })
+        .collect::<Result<Vec<_>, AssembleError>>()?;
+
+    let guard = guard
+        .map(|guard| format!("@{} ", guard))
+        .unwrap_or_default();
+
+    Ok(format!("{}{} {}", guard, name, operands.join(",")))
+}
+
+/// Assemble a source holding one instruction per line, `//` starts a comment.
+///
+/// A line can start with a label definition, written as `name:`. Labels can be used as
+/// the offset of the branch instructions, e.g. `BRA offset=loop`.
+///
+/// The source makes a single program, directives selecting another section than the
+/// default one cannot be used. See assemble_module for sources holding more than that.
+pub fn assemble_source(target: Target, source: &str) -> Result<Program, SourceError> {
+    let lines = preprocess(source, &mut no_include)?;
+    let module = ModuleBuilder::new(true, Container::Raw).assemble(target, &lines)?;
+
+    debug_assert!(module.sections.len() <= 1);
+
+    Ok(module
+        .sections
+        .into_iter()
+        .find_map(|section| match section.data {
+            SectionData::Code(program) => Some(program),
+            SectionData::Data(_) => None,
+        })
+        .unwrap_or_else(|| Program::new(target)))
+}
@@ -0,0 +1,569 @@
+use std::convert::TryFrom;
+
+use super::{
+    assemble_instruction, is_label_name, no_include, parse_number, preprocess, resolve_label,
+    split_label, AssembleError, IncludeReader, SourceError, SourceLine,
+};
+use crate::maxhell::encoder::EncodeError;
+use crate::maxhell::program::{
+    aligned_instruction_count, instruction_address, Container, Program, GROUP_SIZE,
+};
+use crate::maxhell::target::Target;
+
+#[cfg(test)]
+mod tests;
+
+/// The code section used until a directive selects another section.
+pub const DEFAULT_SECTION: &str = ".text";
+
+/// Alignment of a data section that isn't raised by `.align`.
+///
+/// NOTE: constant buffers are read by 32-bit words.
+const DATA_ALIGNMENT: u64 = 4;
+
+/// Check if a section holds code, which is the case of `.text` and `.text.<name>`.
+pub fn is_code_section(name: &str) -> bool {
+    name == DEFAULT_SECTION || name.starts_with(".text.")
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum SectionData {
+    Code(Program),
+    Data(Vec<u8>),
+}
+
+/// A section of a module.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Section {
+    pub name: String,
+    /// Alignment of the start of the section, raised by `.align`.
+    pub alignment: u64,
+    pub data: SectionData,
+}
+
+impl Section {
+    pub fn program(&self) -> Option<&Program> {
+        match &self.data {
+            SectionData::Code(program) => Some(program),
+            SectionData::Data(_) => None,
+        }
+    }
+
+    pub fn program_mut(&mut self) -> Option<&mut Program> {
+        match &mut self.data {
+            SectionData::Code(program) => Some(program),
+            SectionData::Data(_) => None,
+        }
+    }
+
+    /// Size of the section once written, scheduling control words included.
+    pub fn size(&self) -> u64 {
+        match &self.data {
+            SectionData::Code(program) => program.code_size(),
+            SectionData::Data(data) => data.len() as u64,
+        }
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, EncodeError> {
+        match &self.data {
+            SectionData::Code(program) => program.code(),
+            SectionData::Data(data) => Ok(data.clone()),
+        }
+    }
+}
+
+/// A label defined in a module.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Symbol {
+    pub name: String,
+    /// Index of the section defining the symbol.
+    pub section: usize,
+    /// Offset from the start of the section, the address of the instruction for code.
+    pub offset: u64,
+    /// The symbol is exported, with `.global` or `.entry`.
+    pub global: bool,
+    /// The symbol is a kernel entry point, at the start of its own section.
+    pub entry: bool,
+}
+
+/// Sections and symbols assembled from a source.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Module {
+    pub target: Target,
+    pub sections: Vec<Section>,
+    pub symbols: Vec<Symbol>,
+}
+
+impl Module {
+    pub fn new(target: Target) -> Self {
+        Module {
+            target,
+            sections: Vec::new(),
+            symbols: Vec::new(),
+        }
+    }
+
+    /// Create a module holding a program in the default section.
+    pub fn from_program(program: Program) -> Self {
+        let mut module = Module::new(program.target());
+
+        module.sections.push(Section {
+            name: String::from(DEFAULT_SECTION),
+            alignment: GROUP_SIZE,
+            data: SectionData::Code(program),
+        });
+
+        module
+    }
+
+    pub fn section(&self, name: &str) -> Option<&Section> {
+        self.sections.iter().find(|section| section.name == name)
+    }
+
+    pub fn symbol(&self, name: &str) -> Option<&Symbol> {
+        self.symbols.iter().find(|symbol| symbol.name == name)
+    }
+
+    /// The kernel entry points, declared with `.entry`.
+    pub fn entries(&self) -> impl Iterator<Item = &Symbol> {
+        self.symbols.iter().filter(|symbol| symbol.entry)
+    }
+
+    /// Offsets of the sections written one after the other, each on its alignment.
+    pub fn section_offsets(&self) -> Vec<u64> {
+        let mut offset = 0u64;
+
+        self.sections
+            .iter()
+            .map(|section| {
+                let start = offset.next_multiple_of(section.alignment);
+
+                offset = start + section.size();
+                start
+            })
+            .collect()
+    }
+
+    /// Offset of a symbol from the start of the module.
+    pub fn symbol_offset(&self, symbol: &Symbol) -> u64 {
+        self.section_offsets()[symbol.section] + symbol.offset
+    }
+
+    /// The binary of the module, made of its sections written one after the other.
+    ///
+    /// NOTE: the padding between sections is made of zeros.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, EncodeError> {
+        let mut binary = Vec::new();
+
+        for (section, offset) in self.sections.iter().zip(self.section_offsets()) {
+            binary.resize(offset as usize, 0);
+            binary.extend_from_slice(&section.to_bytes()?);
+        }
+
+        Ok(binary)
+    }
+}
+
+/// An instruction of a code section, before the labels are known.
+enum Slot<'a> {
+    Source(&'a SourceLine, &'a str),
+    /// A raw instruction given with `.dword`.
+    Word(u64),
+    /// NOPs padding the code to an alignment, given with the line of the `.align`.
+    Align(&'a SourceLine, u64),
+}
+
+enum PendingData<'a> {
+    /// The slots of the code and the number of instructions they make once padded.
+    Code(Vec<Slot<'a>>, usize),
+    Data(Vec<u8>),
+}
+
+struct PendingSection<'a> {
+    name: String,
+    alignment: u64,
+    data: PendingData<'a>,
+}
+
+/// Builds a module in two passes: the first one lays out the sections and defines the
+/// labels, the second one assembles the instructions once every label is known.
+pub(super) struct ModuleBuilder<'a> {
+    /// Only the default section can be used, as in a source making a single program.
+    single_section: bool,
+    /// The container the module is written to.
+    container: Container,
+    sections: Vec<PendingSection<'a>>,
+    current: Option<usize>,
+    symbols: Vec<Symbol>,
+    /// Names given to `.global`, with their line.
+    globals: Vec<(&'a SourceLine, &'a str)>,
+}
+
+impl<'a> ModuleBuilder<'a> {
+    pub(super) fn new(single_section: bool, container: Container) -> Self {
+        ModuleBuilder {
+            single_section,
+            container,
+            sections: Vec::new(),
+            current: None,
+            symbols: Vec::new(),
+            globals: Vec::new(),
+        }
+    }
+
+    /// Select a section, creating it the first time.
+    fn select(&mut self, name: &str) -> usize {
+        let index = match self
+            .sections
+            .iter()
+            .position(|section| section.name == name)
+        {
+            Some(index) => index,
+            None => {
+                let (alignment, data) = if is_code_section(name) {
+                    (GROUP_SIZE, PendingData::Code(Vec::new(), 0))
+                } else {
+                    (DATA_ALIGNMENT, PendingData::Data(Vec::new()))
+                };
+
+                self.sections.push(PendingSection {
+                    name: String::from(name),
+                    alignment,
+                    data,
+                });
+
+                self.sections.len() - 1
+            }
+        };
+
+        self.current = Some(index);
+
+        index
+    }
+
+    /// The current section, the default one is selected if none is.
+    fn current(&mut self) -> &mut PendingSection<'a> {
+        let index = match self.current {
+            Some(index) => index,
+            None => self.select(DEFAULT_SECTION),
+        };
+
+        &mut self.sections[index]
+    }
+
+    fn define(
+        &mut self,
+        name: &str,
+        offset: Option<u64>,
+        entry: bool,
+    ) -> Result<(), AssembleError> {
+        if self.symbols.iter().any(|symbol| symbol.name == name) {
+            return Err(AssembleError::DuplicateLabel(String::from(name)));
+        }
+
+        let position = match &self.current().data {
+            PendingData::Code(_, count) => instruction_address(*count),
+            PendingData::Data(data) => data.len() as u64,
+        };
+
+        self.symbols.push(Symbol {
+            name: String::from(name),
+            section: self.current.unwrap(),
+            offset: offset.unwrap_or(position),
+            global: entry,
+            entry,
+        });
+
+        Ok(())
+    }
+
+    fn directive(
+        &mut self,
+        line: &'a SourceLine,
+        name: &str,
+        operand: &'a str,
+    ) -> Result<(), AssembleError> {
+        let invalid_operand = |operand: &str| {
+            AssembleError::InvalidOperand(String::from(name), String::from(operand))
+        };
+
+        match name {
+            ".section" => {
+                if operand.is_empty() || operand.contains(char::is_whitespace) {
+                    return Err(invalid_operand(operand));
+                }
+
+                if self.single_section && operand != DEFAULT_SECTION {
+                    return Err(AssembleError::UnexpectedDirective(String::from(name)));
+                }
+
+                // NOTE: cubins and NVN containers are only made of programs, data sections
+                // can only be written raw.
+                if self.container != Container::Raw && !is_code_section(operand) {
+                    return Err(AssembleError::DataInContainer(
+                        String::from(operand),
+                        self.container,
+                    ));
+                }
+
+                self.select(operand);
+            }
+            ".entry" => {
+                if !is_label_name(operand) {
+                    return Err(invalid_operand(operand));
+                }
+
+                if self.single_section {
+                    return Err(AssembleError::UnexpectedDirective(String::from(name)));
+                }
+
+                // NOTE: the entry point is the start of the section, not its first instruction.
+                self.select(&format!(".text.{}", operand));
+                self.define(operand, Some(0), true)?;
+            }
+            ".global" => {
+                for label in operand.split(',').map(str::trim) {
+                    if !is_label_name(label) {
+                        return Err(invalid_operand(label));
+                    }
+
+                    self.globals.push((line, label));
+                }
+            }
+            ".align" => {
+                let alignment = parse_number(operand)
+                    .and_then(|value| u64::try_from(value).ok())
+                    .filter(|value| value.is_power_of_two())
+                    .ok_or_else(|| invalid_operand(operand))?;
+
+                let section = self.current();
+
+                match &mut section.data {
+                    // NOTE: the code is made of groups preceded by a scheduling control word,
+                    // aligning code aligns the start of a group.
+                    PendingData::Code(slots, count) => {
+                        if !alignment.is_multiple_of(GROUP_SIZE) {
+                            return Err(invalid_operand(operand));
+                        }
+
+                        slots.push(Slot::Align(line, alignment));
+                        *count = aligned_instruction_count(*count, alignment);
+                    }
+                    PendingData::Data(data) => {
+                        data.resize((data.len() as u64).next_multiple_of(alignment) as usize, 0);
+                    }
+                }
+
+                section.alignment = section.alignment.max(alignment);
+            }
+            ".word" | ".dword" => {
+                let is_word = name == ".word";
+                let values = operand
+                    .split(',')
+                    .map(str::trim)
+                    .map(|value| {
+                        let number = parse_number(value).ok_or_else(|| invalid_operand(value))?;
+
+                        // NOTE: negative values are stored in two's complement.
+                        if is_word {
+                            u32::try_from(number)
+                                .or_else(|_| i32::try_from(number).map(|number| number as u32))
+                                .map(u64::from)
+                        } else {
+                            u64::try_from(number)
+                                .or_else(|_| i64::try_from(number).map(|number| number as u64))
+                        }
+                        .map_err(|_| invalid_operand(value))
+                    })
+                    .collect::<Result<Vec<_>, AssembleError>>()?;
+
+                match &mut self.current().data {
+                    // A .dword in code is an instruction given as is.
+                    PendingData::Code(slots, count) if !is_word => {
+                        *count += values.len();
+                        slots.extend(values.into_iter().map(Slot::Word));
+                    }
+                    PendingData::Code(_, _) => {
+                        return Err(AssembleError::DataInCode(String::from(name)))
+                    }
+                    PendingData::Data(data) => {
+                        for value in values {
+                            if is_word {
+                                data.extend_from_slice(&(value as u32).to_le_bytes());
+                            } else {
+                                data.extend_from_slice(&value.to_le_bytes());
+                            }
+                        }
+                    }
+                }
+            }
+            ".zero" => {
+                let size = parse_number(operand)
+                    .and_then(|value| usize::try_from(value).ok())
+                    .ok_or_else(|| invalid_operand(operand))?;
+
+                match &mut self.current().data {
+                    PendingData::Code(_, _) => {
+                        return Err(AssembleError::DataInCode(String::from(name)))
+                    }
+                    PendingData::Data(data) => data.resize(data.len() + size, 0),
+                }
+            }
+            _ => return Err(AssembleError::UnknownDirective(String::from(name))),
+        }
+
+        Ok(())
+    }
+
+    fn line(&mut self, line: &'a SourceLine) -> Result<(), AssembleError> {
+        let (label, text) = split_label(&line.text);
+        let text = text.trim();
+
+        if let Some(label) = label {
+            self.define(label, None, false)?;
+        }
+
+        if text.is_empty() {
+            return Ok(());
+        }
+
+        if text.starts_with('.') {
+            let (name, operand) = text.split_once(char::is_whitespace).unwrap_or((text, ""));
+
+            return self.directive(line, name, operand.trim());
+        }
+
+        match &mut self.current().data {
+            PendingData::Code(slots, count) => {
+                slots.push(Slot::Source(line, text));
+                *count += 1;
+
+                Ok(())
+            }
+            PendingData::Data(_) => Err(AssembleError::InstructionInData),
+        }
+    }
+
+    pub(super) fn assemble(
+        mut self,
+        target: Target,
+        lines: &'a [SourceLine],
+    ) -> Result<Module, SourceError> {
+        for line in lines {
+            self.line(line)
+                .map_err(|error| SourceError::new(line, error))?;
+        }
+
+        for (line, name) in &self.globals {
+            let symbol = self
+                .symbols
+                .iter_mut()
+                .find(|symbol| symbol.name == *name)
+                .ok_or_else(|| {
+                    SourceError::new(line, AssembleError::UnknownLabel(String::from(*name)))
+                })?;
+
+            symbol.global = true;
+        }
+
+        let symbols = &self.symbols;
+        let mut module = Module::new(target);
+
+        for (index, section) in self.sections.into_iter().enumerate() {
+            let lookup = |name: &str| {
+                let symbol = symbols
+                    .iter()
+                    .find(|symbol| symbol.name == name)
+                    .ok_or_else(|| AssembleError::UnknownLabel(String::from(name)))?;
+
+                if symbol.section != index {
+                    return Err(AssembleError::UnreachableLabel(String::from(name)));
+                }
+
+                // NOTE: branching to an entry point branches to its first instruction.
+                Ok(if symbol.entry {
+                    instruction_address(0)
+                } else {
+                    symbol.offset
+                })
+            };
+
+            let data = match section.data {
+                PendingData::Code(slots, count) => {
+                    let mut program = Program::new(target);
+
+                    for slot in slots {
+                        match slot {
+                            Slot::Source(line, text) => {
+                                let address = instruction_address(program.instructions().len());
+                                let instruction = resolve_label(text, address, lookup)
+                                    .and_then(|text| assemble_instruction(target, &text))
+                                    .map_err(|error| SourceError::new(line, error))?;
+
+                                program.push(instruction);
+                            }
+                            Slot::Word(word) => program.push(word),
+                            Slot::Align(line, alignment) => {
+                                program
+                                    .align(alignment)
+                                    .map_err(|error| SourceError::new(line, error.into()))?;
+                            }
+                        }
+                    }
+
+                    debug_assert_eq!(program.instructions().len(), count);
+
+                    SectionData::Code(program)
+                }
+                PendingData::Data(data) => SectionData::Data(data),
+            };
+
+            module.sections.push(Section {
+                name: section.name,
+                alignment: section.alignment,
+                data,
+            });
+        }
+
+        module.symbols = self.symbols;
+
+        Ok(module)
+    }
+}
+
+/// Assemble a source made of sections, see assemble_source for the syntax of the code.
+///
+/// The source is expanded by preprocess first, it cannot include files.
+///
+/// Directives start with a dot:
+/// - `.section name` selects a section, creating it the first time. `.text` and
+///   `.text.<name>` hold code, other sections hold data. Code goes to `.text` until a
+///   section is selected.
+/// - `.entry name` selects the code section `.text.<name>` and declares the kernel `name`
+///   starting there.
+/// - `.global name, ...` exports labels.
+/// - `.align n` pads the section to a multiple of n bytes, a power of two. In code, n must
+///   be a multiple of the 0x20 bytes of a group and the padding is made of NOPs.
+/// - `.word value, ...` and `.dword value, ...` emit 32-bit and 64-bit values. In code,
+///   `.dword` emits instructions given as is and `.word` cannot be used.
+/// - `.zero size` emits zeros, it cannot be used in code.
+///
+/// The module is assembled to be written raw, see assemble_module_with_includes for the
+/// other containers.
+pub fn assemble_module(target: Target, source: &str) -> Result<Module, SourceError> {
+    assemble_module_with_includes(target, Container::Raw, source, &mut no_include)
+}
+
+/// Assemble a source made of sections, reading the files it includes with a reader.
+///
+/// Data sections are rejected unless the module is written to Container::Raw.
+pub fn assemble_module_with_includes(
+    target: Target,
+    container: Container,
+    source: &str,
+    include: &mut IncludeReader,
+) -> Result<Module, SourceError> {
+    let lines = preprocess(source, include)?;
+
+    ModuleBuilder::new(false, container).assemble(target, &lines)
+}
@@ -0,0 +1,158 @@
+use super::*;
+use crate::maxhell::assembler::assemble_source;
+
+const TARGET: Target = Target::SM50;
+
+const RAM: u64 = 0xe380_0000_0000_0000;
+const SAM: u64 = 0xe370_0000_0000_0000;
+const EXIT: u64 = 0xe300_0000_0007_000f;
+const BRA_SELF: u64 = 0xe240_0fff_ff87_000f;
+
+fn error(source: &str) -> SourceError {
+    assemble_module(TARGET, source).unwrap_err()
+}
+
+#[test]
+fn lays_out_data_sections() {
+    let source = "RAM\n\
+                  .section .nv.constant2\n\
+                  table: .word 1, -1\n\
+                  .align 8\n\
+                  values: .dword 0x1122334455667788\n\
+                  .zero 4\n\
+                  .global table\n";
+
+    let module = assemble_module(TARGET, source).unwrap();
+
+    assert_eq!(module.sections.len(), 2);
+    assert_eq!(module.sections[0].name, DEFAULT_SECTION);
+    assert_eq!(module.sections[0].program().unwrap().instructions(), [RAM]);
+
+    let data = &module.sections[1];
+
+    assert_eq!(data.name, ".nv.constant2");
+    assert_eq!(data.alignment, 8);
+    assert_eq!(
+        data.data,
+        SectionData::Data(vec![
+            1, 0, 0, 0, 0xff, 0xff, 0xff, 0xff, 0x88, 0x77, 0x66, 0x55, 0x44, 0x33, 0x22, 0x11, 0,
+            0, 0, 0
+        ])
+    );
+
+    let table = module.symbol("table").unwrap();
+    let values = module.symbol("values").unwrap();
+
+    assert!(table.global);
+    assert!(!values.global);
+    assert_eq!(values.offset, 8);
+
+    // The data follows the single group of code.
+    assert_eq!(module.section_offsets(), [0, 0x20]);
+    assert_eq!(module.symbol_offset(table), 0x20);
+
+    let binary = module.to_bytes().unwrap();
+
+    assert_eq!(binary.len(), 0x20 + 20);
+    assert_eq!(binary[0x28..0x30], 0x1122_3344_5566_7788u64.to_le_bytes());
+}
+
+#[test]
+fn aligns_code_on_groups() {
+    let source = "RAM\n\
+                  .align 0x40\n\
+                  aligned: SAM\n\
+                  .dword 0xe30000000007000f\n";
+
+    let module = assemble_module(TARGET, source).unwrap();
+    let section = module.section(DEFAULT_SECTION).unwrap();
+    let program = section.program().unwrap();
+
+    // SAM starts the group at 0x40, preceded by NOPs.
+    assert_eq!(program.instructions().len(), 8);
+    assert_eq!(program.instructions()[0], RAM);
+    assert_eq!(program.instructions()[6..], [SAM, EXIT]);
+    assert_eq!(module.symbol("aligned").unwrap().offset, 0x48);
+    assert_eq!(section.alignment, 0x40);
+
+    // Aligning code on less than a group would split it.
+    assert_eq!(
+        error("RAM\n.align 0x10").error,
+        AssembleError::InvalidOperand(String::from(".align"), String::from("0x10"))
+    );
+}
+
+#[test]
+fn declares_entries() {
+    let source = ".entry first\n\
+                  BRA offset=first, cc_flags=TRUE\n\
+                  .entry second\n\
+                  .align 0x80\n\
+                  EXIT cc_flags=TRUE\n";
+
+    let module = assemble_module(TARGET, source).unwrap();
+    let names: Vec<&str> = module
+        .sections
+        .iter()
+        .map(|section| section.name.as_str())
+        .collect();
+
+    assert_eq!(names, [".text.first", ".text.second"]);
+
+    let entries: Vec<&Symbol> = module.entries().collect();
+
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[1].name, "second");
+    assert_eq!(entries[1].section, 1);
+    assert_eq!(entries[1].offset, 0);
+    assert!(entries.iter().all(|entry| entry.global));
+
+    // Branching to the entry branches to its first instruction.
+    assert_eq!(
+        module.sections[0].program().unwrap().instructions(),
+        [BRA_SELF]
+    );
+    assert_eq!(module.section_offsets(), [0, 0x80]);
+}
+
+#[test]
+fn rejects_invalid_directives() {
+    assert_eq!(
+        error("RAM\n.byte 1").error,
+        AssembleError::UnknownDirective(String::from(".byte"))
+    );
+    assert_eq!(
+        error(".word 1").error,
+        AssembleError::DataInCode(String::from(".word"))
+    );
+    assert_eq!(
+        error(".section .data\nRAM").error,
+        AssembleError::InstructionInData
+    );
+    assert_eq!(
+        error(".section .data\n.word 0x100000000").error,
+        AssembleError::InvalidOperand(String::from(".word"), String::from("0x100000000"))
+    );
+    assert_eq!(
+        error("loop: RAM\n.entry kernel\nBRA offset=loop").error,
+        AssembleError::UnreachableLabel(String::from("loop"))
+    );
+    assert_eq!(
+        error("RAM\n.global nowhere\n"),
+        SourceError {
+            file: None,
+            line: 2,
+            error: AssembleError::UnknownLabel(String::from("nowhere")),
+        }
+    );
+
+    // A single program cannot hold more than one section.
+    assert_eq!(
+        assemble_source(TARGET, "RAM\n.entry kernel\nSAM").unwrap_err(),
+        SourceError {
+            file: None,
+            line: 2,
+            error: AssembleError::UnexpectedDirective(String::from(".entry")),
+        }
+    );
+}
@@ -0,0 +1,798 @@
+use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
+use std::io;
+use std::path::Path;
+
+use super::{
+    is_label_name, parse_number, split_instruction, split_label, AssembleError, SourceError,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Maximum nesting of macro expansions and includes, to stop recursive definitions.
+const MAX_DEPTH: usize = 64;
+
+/// Maximum number of lines a source can expand to, to stop runaway repetitions.
+const MAX_LINES: usize = 1 << 20;
+
+/// Directives handled by the preprocessor, they aren't seen by the assembler.
+const PREPROCESSOR_DIRECTIVES: &[&str] = &[
+    ".macro", ".endm", ".rept", ".irp", ".endr", ".if", ".else", ".endif", ".include", ".set",
+];
+
+/// Directives taking integer expressions, the constants of their operands are replaced.
+const EXPRESSION_DIRECTIVES: &[&str] = &[".word", ".dword", ".zero", ".align"];
+
+/// A line of a source once expanded.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SourceLine {
+    /// The included file it comes from, None for the source given to the preprocessor.
+    pub file: Option<String>,
+    /// The line of the file it comes from, starting at 1.
+    ///
+    /// NOTE: lines expanded from a macro or a loop get the line of the directive
+    /// expanding them.
+    pub line: usize,
+    pub text: String,
+}
+
+/// Reads the file given to `.include`.
+///
+/// The path of a file included by another one is relative to the directory of the
+/// including file, the reader gets it relative to the directory of the source.
+pub type IncludeReader<'a> = dyn FnMut(&str) -> io::Result<String> + 'a;
+
+struct Macro {
+    /// Names of the parameters and their default values.
+    parameters: Vec<(String, Option<String>)>,
+    body: Vec<String>,
+}
+
+/// Remove the comment of a line.
+fn strip_comment(line: &str) -> &str {
+    match line.find("//") {
+        Some(start) => &line[..start],
+        None => line,
+    }
+}
+
+/// The directive starting a line, if any.
+fn directive_name(text: &str) -> Option<&str> {
+    let text = text.trim_start();
+
+    if text.starts_with('.') {
+        text.split_whitespace().next()
+    } else {
+        None
+    }
+}
+
+/// Split a line in its first word and the rest of it.
+fn split_first_word(text: &str) -> (&str, &str) {
+    let text = text.trim();
+
+    match text.split_once(char::is_whitespace) {
+        Some((word, rest)) => (word, rest.trim()),
+        None => (text, ""),
+    }
+}
+
+fn is_identifier_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_'
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+/// Replace the `\name` arguments of a body, `\@` is replaced by the expansion count if any.
+fn substitute_arguments(
+    text: &str,
+    arguments: &HashMap<&str, &str>,
+    count: Option<usize>,
+) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find('\\') {
+        result.push_str(&rest[..start]);
+        rest = &rest[start + 1..];
+
+        if let (Some(after), Some(count)) = (rest.strip_prefix('@'), count) {
+            result.push_str(&count.to_string());
+            rest = after;
+            continue;
+        }
+
+        let end = rest.find(|c| !is_identifier_char(c)).unwrap_or(rest.len());
+
+        // NOTE: unknown names are kept, they can be the variable of an inner .irp.
+        match arguments.get(&rest[..end]) {
+            Some(value) => result.push_str(value),
+            None => {
+                result.push('\\');
+                result.push_str(&rest[..end]);
+            }
+        }
+
+        rest = &rest[end..];
+    }
+
+    result.push_str(rest);
+    result
+}
+
+fn is_register_name(word: &str) -> bool {
+    let index = word.strip_prefix('R').or_else(|| word.strip_prefix('P'));
+
+    word == "RZ"
+        || word == "PT"
+        || index.is_some_and(|index| !index.is_empty() && index.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// Replace the identifiers naming a constant by its value, registers are kept.
+fn substitute_expression(text: &str, constants: &HashMap<String, i128>) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.char_indices().peekable();
+
+    while let Some((start, c)) = chars.next() {
+        if !is_identifier_char(c) {
+            result.push(c);
+            continue;
+        }
+
+        let mut end = start + c.len_utf8();
+
+        while let Some((index, c)) = chars.peek().copied() {
+            if !is_identifier_char(c) {
+                break;
+            }
+
+            end = index + c.len_utf8();
+            chars.next();
+        }
+
+        let word = &text[start..end];
+
+        // NOTE: numbers such as 0x10 are made of identifier characters too.
+        match constants.get(word) {
+            Some(value) if is_identifier_start(c) && !is_register_name(word) => {
+                result.push_str(&value.to_string())
+            }
+            _ => result.push_str(word),
+        }
+    }
+
+    result
+}
+
+/// Replace the constants used by the operands of a line.
+///
+/// Only the values of the fields of an instruction and the operands of the directives
+/// taking expressions are replaced. The label, the mnemonic and its suffixes, the field
+/// names and the flags are kept.
+fn substitute_constants(text: &str, constants: &HashMap<String, i128>) -> String {
+    if constants.is_empty() {
+        return String::from(text);
+    }
+
+    let (label, rest) = split_label(text);
+    let mut result = label
+        .map(|label| format!("{}: ", label))
+        .unwrap_or_default();
+
+    match directive_name(rest) {
+        Some(name) if EXPRESSION_DIRECTIVES.contains(&name) => {
+            let (_, operand) = split_first_word(rest);
+
+            result.push_str(name);
+            result.push(' ');
+            result.push_str(&substitute_expression(operand, constants));
+        }
+        Some(_) => result.push_str(rest.trim()),
+        None => {
+            let (guard, name, operands) = split_instruction(rest);
+            let operands: Vec<String> = operands
+                .split(',')
+                .map(str::trim)
+                .filter(|operand| !operand.is_empty())
+                .map(|operand| match operand.split_once('=') {
+                    Some((field, value)) => format!(
+                        "{}={}",
+                        field.trim(),
+                        substitute_expression(value.trim(), constants)
+                    ),
+                    None => String::from(operand),
+                })
+                .collect();
+
+            if let Some(guard) = guard {
+                result.push('@');
+                result.push_str(guard);
+                result.push(' ');
+            }
+
+            result.push_str(name);
+
+            if !operands.is_empty() {
+                result.push(' ');
+                result.push_str(&operands.join(", "));
+            }
+        }
+    }
+
+    result
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Token {
+    Number(i128),
+    Operator(&'static str),
+    Open,
+    Close,
+}
+
+/// Binary operators from the lowest to the highest precedence.
+const BINARY_OPERATORS: &[&[&str]] = &[
+    &["||"],
+    &["&&"],
+    &["|"],
+    &["^"],
+    &["&"],
+    &["==", "!="],
+    &["<", "<=", ">", ">="],
+    &["<<", ">>"],
+    &["+", "-"],
+    &["*", "/", "%"],
+];
+
+/// Every operator, the longest ones first.
+const OPERATORS: &[&str] = &[
+    "||", "&&", "==", "!=", "<=", ">=", "<<", ">>", "|", "^", "&", "<", ">", "+", "-", "*", "/",
+    "%", "~", "!",
+];
+
+fn tokenize(
+    expression: &str,
+    constants: &HashMap<String, i128>,
+) -> Result<Vec<Token>, AssembleError> {
+    let invalid = || AssembleError::InvalidExpression(String::from(expression.trim()));
+    let mut tokens = Vec::new();
+    let mut rest = expression.trim_start();
+
+    while let Some(c) = rest.chars().next() {
+        if is_identifier_char(c) {
+            let end = rest.find(|c| !is_identifier_char(c)).unwrap_or(rest.len());
+            let word = &rest[..end];
+
+            let value = if is_identifier_start(c) {
+                *constants.get(word).ok_or_else(invalid)?
+            } else {
+                parse_number(word).ok_or_else(invalid)?
+            };
+
+            tokens.push(Token::Number(value));
+            rest = &rest[end..];
+        } else if c == '(' || c == ')' {
+            tokens.push(if c == '(' { Token::Open } else { Token::Close });
+            rest = &rest[1..];
+        } else {
+            let operator = OPERATORS
+                .iter()
+                .find(|operator| rest.starts_with(*operator))
+                .ok_or_else(invalid)?;
+
+            tokens.push(Token::Operator(operator));
+            rest = &rest[operator.len()..];
+        }
+
+        rest = rest.trim_start();
+    }
+
+    Ok(tokens)
+}
+
+/// Evaluates the integer expressions of `.set`, `.rept` and `.if`, with the operators
+/// and precedence of C.
+struct Evaluator<'a> {
+    tokens: &'a [Token],
+    position: usize,
+}
+
+impl<'a> Evaluator<'a> {
+    fn next_token(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).copied();
+
+        self.position += 1;
+        token
+    }
+
+    fn unary(&mut self) -> Option<i128> {
+        match self.next_token()? {
+            Token::Number(value) => Some(value),
+            Token::Operator("-") => self.unary()?.checked_neg(),
+            Token::Operator("+") => self.unary(),
+            Token::Operator("~") => Some(!self.unary()?),
+            Token::Operator("!") => Some((self.unary()? == 0) as i128),
+            Token::Open => {
+                let value = self.binary(0)?;
+
+                match self.next_token()? {
+                    Token::Close => Some(value),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    fn binary(&mut self, level: usize) -> Option<i128> {
+        if level == BINARY_OPERATORS.len() {
+            return self.unary();
+        }
+
+        let mut left = self.binary(level + 1)?;
+
+        while let Some(Token::Operator(operator)) = self.tokens.get(self.position).copied() {
+            if !BINARY_OPERATORS[level].contains(&operator) {
+                break;
+            }
+
+            self.position += 1;
+
+            let right = self.binary(level + 1)?;
+
+            left = match operator {
+                "||" => (left != 0 || right != 0) as i128,
+                "&&" => (left != 0 && right != 0) as i128,
+                "|" => left | right,
+                "^" => left ^ right,
+                "&" => left & right,
+                "==" => (left == right) as i128,
+                "!=" => (left != right) as i128,
+                "<" => (left < right) as i128,
+                "<=" => (left <= right) as i128,
+                ">" => (left > right) as i128,
+                ">=" => (left >= right) as i128,
+                "<<" => left.checked_shl(u32::try_from(right).ok()?)?,
+                ">>" => left.checked_shr(u32::try_from(right).ok()?)?,
+                "+" => left.checked_add(right)?,
+                "-" => left.checked_sub(right)?,
+                "*" => left.checked_mul(right)?,
+                "/" => left.checked_div(right)?,
+                "%" => left.checked_rem(right)?,
+                _ => unreachable!(),
+            };
+        }
+
+        Some(left)
+    }
+}
+
+/// Evaluate an integer expression using the constants defined with `.set`.
+fn evaluate(expression: &str, constants: &HashMap<String, i128>) -> Result<i128, AssembleError> {
+    let tokens = tokenize(expression, constants)?;
+    let mut evaluator = Evaluator {
+        tokens: &tokens,
+        position: 0,
+    };
+
+    match evaluator.binary(0) {
+        Some(value) if evaluator.position == tokens.len() => Ok(value),
+        _ => Err(AssembleError::InvalidExpression(String::from(
+            expression.trim(),
+        ))),
+    }
+}
+
+/// A line of the source being expanded, with the line it comes from.
+type Line = (usize, String);
+
+struct Preprocessor<'a, 'b> {
+    include: &'a mut IncludeReader<'b>,
+    constants: HashMap<String, i128>,
+    macros: HashMap<String, Macro>,
+    /// Labels and entries defined so far, they share their names with the constants.
+    labels: HashSet<String>,
+    /// Number of macros expanded so far, used by `\@`.
+    expansion_count: usize,
+    output: Vec<SourceLine>,
+}
+
+/// Find the end of the block starting at the given line.
+///
+/// Returns the index of the closing directive and the index of the `.else` of the block.
+fn find_block_end(
+    lines: &[Line],
+    start: usize,
+    opening: &[&str],
+    closing: &str,
+) -> Result<(usize, Option<usize>), AssembleError> {
+    let mut depth = 0;
+    let mut alternative = None;
+
+    for (index, (_, text)) in lines.iter().enumerate().skip(start + 1) {
+        match directive_name(text) {
+            Some(name) if opening.contains(&name) => depth += 1,
+            Some(name) if name == closing && depth == 0 => return Ok((index, alternative)),
+            Some(name) if name == closing => depth -= 1,
+            Some(".else") if depth == 0 && closing == ".endif" && alternative.is_some() => {
+                return Err(AssembleError::UnmatchedDirective(String::from(".else")));
+            }
+            Some(".else") if depth == 0 && closing == ".endif" => alternative = Some(index),
+            _ => {}
+        }
+    }
+
+    let name = directive_name(&lines[start].1).unwrap_or_default();
+
+    Err(AssembleError::UnterminatedBlock(String::from(name)))
+}
+
+impl<'a, 'b> Preprocessor<'a, 'b> {
+    fn define_macro(&mut self, operand: &str, body: &[Line]) -> Result<(), AssembleError> {
+        let mut words = operand
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|word| !word.is_empty());
+
+        let name = words
+            .next()
+            .filter(|name| is_label_name(name))
+            .ok_or_else(|| {
+                AssembleError::InvalidOperand(String::from(".macro"), String::from(operand))
+            })?;
+
+        let parameters = words
+            .map(|word| {
+                let (parameter, default) = match word.split_once('=') {
+                    Some((parameter, default)) => (parameter, Some(String::from(default))),
+                    None => (word, None),
+                };
+
+                if parameter.is_empty() || !parameter.chars().all(is_identifier_char) {
+                    return Err(AssembleError::InvalidOperand(
+                        String::from(".macro"),
+                        String::from(word),
+                    ));
+                }
+
+                Ok((String::from(parameter), default))
+            })
+            .collect::<Result<Vec<_>, AssembleError>>()?;
+
+        if self.macros.contains_key(name) {
+            return Err(AssembleError::DuplicateMacro(String::from(name)));
+        }
+
+        self.macros.insert(
+            String::from(name),
+            Macro {
+                parameters,
+                body: body.iter().map(|(_, text)| text.clone()).collect(),
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Expand the body of a macro with the arguments of an invocation.
+    fn macro_body(&mut self, name: &str, operands: &str) -> Result<Vec<String>, AssembleError> {
+        let definition = &self.macros[name];
+        let mut values: Vec<Option<&str>> = definition
+            .parameters
+            .iter()
+            .map(|(_, default)| default.as_deref())
+            .collect();
+
+        let operands: Vec<&str> = if operands.is_empty() {
+            Vec::new()
+        } else {
+            operands.split(',').map(str::trim).collect()
+        };
+
+        for (position, operand) in operands.into_iter().enumerate() {
+            let named = operand.split_once('=').and_then(|(parameter, value)| {
+                definition
+                    .parameters
+                    .iter()
+                    .position(|(name, _)| name == parameter.trim())
+                    .map(|index| (index, value.trim()))
+            });
+
+            let (index, value) = match named {
+                Some(named) => named,
+                None if position < values.len() => (position, operand),
+                None => {
+                    return Err(AssembleError::InvalidMacroArgument(
+                        String::from(name),
+                        String::from(operand),
+                    ))
+                }
+            };
+
+            values[index] = Some(value);
+        }
+
+        let mut arguments = HashMap::new();
+
+        for ((parameter, _), value) in definition.parameters.iter().zip(values) {
+            let value = value.ok_or_else(|| {
+                AssembleError::MissingMacroArgument(String::from(name), parameter.clone())
+            })?;
+
+            arguments.insert(parameter.as_str(), value);
+        }
+
+        self.expansion_count += 1;
+
+        Ok(definition
+            .body
+            .iter()
+            .map(|text| substitute_arguments(text, &arguments, Some(self.expansion_count)))
+            .collect())
+    }
+
+    fn emit(&mut self, file: Option<&str>, line: usize, text: &str) -> Result<(), AssembleError> {
+        let label = match split_label(text) {
+            (Some(label), _) => Some(label),
+            (None, rest) => match split_first_word(rest) {
+                (".entry", name) => Some(name),
+                _ => None,
+            },
+        };
+
+        if let Some(label) = label {
+            if self.constants.contains_key(label) {
+                return Err(AssembleError::DuplicateLabel(String::from(label)));
+            }
+
+            self.labels.insert(String::from(label));
+        }
+
+        if self.output.len() == MAX_LINES {
+            return Err(AssembleError::ExpansionLimit(MAX_LINES));
+        }
+
+        self.output.push(SourceLine {
+            file: file.map(String::from),
+            line,
+            text: substitute_constants(text, &self.constants),
+        });
+
+        Ok(())
+    }
+
+    fn expand(
+        &mut self,
+        file: Option<&str>,
+        lines: &[Line],
+        depth: usize,
+    ) -> Result<(), SourceError> {
+        let mut index = 0;
+
+        while index < lines.len() {
+            let (line, text) = &lines[index];
+            let line = *line;
+            let error = |error| SourceError {
+                file: file.map(String::from),
+                line,
+                error,
+            };
+
+            let (label, rest) = match directive_name(text) {
+                Some(_) => (None, text.as_str()),
+                None => split_label(text),
+            };
+            let (word, operand) = split_first_word(rest);
+            let is_macro = self.macros.contains_key(word);
+
+            if let Some(label) = label {
+                if is_macro || PREPROCESSOR_DIRECTIVES.contains(&word) {
+                    self.emit(file, line, &format!("{}:", label))
+                        .map_err(error)?;
+                }
+            }
+
+            match word {
+                ".macro" => {
+                    let (end, _) =
+                        find_block_end(lines, index, &[".macro"], ".endm").map_err(error)?;
+
+                    self.define_macro(operand, &lines[index + 1..end])
+                        .map_err(error)?;
+                    index = end;
+                }
+                ".rept" | ".irp" => {
+                    let (end, _) =
+                        find_block_end(lines, index, &[".rept", ".irp"], ".endr").map_err(error)?;
+                    let body = &lines[index + 1..end];
+
+                    if word == ".rept" {
+                        let count = evaluate(operand, &self.constants)
+                            .and_then(|count| {
+                                usize::try_from(count).map_err(|_| {
+                                    AssembleError::InvalidOperand(
+                                        String::from(word),
+                                        String::from(operand),
+                                    )
+                                })
+                            })
+                            .map_err(error)?;
+
+                        // NOTE: Checked here as well so an empty body cannot spin for ever.
+                        if count > MAX_LINES {
+                            return Err(error(AssembleError::ExpansionLimit(MAX_LINES)));
+                        }
+
+                        for _ in 0..count {
+                            self.expand(file, body, depth)?;
+                        }
+                    } else {
+                        let mut values = operand.split(',').map(str::trim);
+                        let variable = values
+                            .next()
+                            .filter(|variable| {
+                                !variable.is_empty() && variable.chars().all(is_identifier_char)
+                            })
+                            .ok_or_else(|| {
+                                error(AssembleError::InvalidOperand(
+                                    String::from(word),
+                                    String::from(operand),
+                                ))
+                            })?;
+
+                        for value in values {
+                            let arguments = std::iter::once((variable, value)).collect();
+                            let body: Vec<Line> = body
+                                .iter()
+                                .map(|(line, text)| {
+                                    (*line, substitute_arguments(text, &arguments, None))
+                                })
+                                .collect();
+
+                            self.expand(file, &body, depth)?;
+                        }
+                    }
+
+                    index = end;
+                }
+                ".if" => {
+                    let (end, alternative) =
+                        find_block_end(lines, index, &[".if"], ".endif").map_err(error)?;
+                    let condition = evaluate(operand, &self.constants).map_err(error)?;
+
+                    let body = match (condition != 0, alternative) {
+                        (true, Some(alternative)) => &lines[index + 1..alternative],
+                        (true, None) => &lines[index + 1..end],
+                        (false, Some(alternative)) => &lines[alternative + 1..end],
+                        (false, None) => &[],
+                    };
+
+                    self.expand(file, body, depth)?;
+                    index = end;
+                }
+                ".set" => {
+                    let (name, expression) = operand
+                        .split_once(',')
+                        .map(|(name, expression)| (name.trim(), expression))
+                        .filter(|(name, _)| {
+                            name.starts_with(is_identifier_start)
+                                && name.chars().all(is_identifier_char)
+                        })
+                        .ok_or_else(|| {
+                            error(AssembleError::InvalidOperand(
+                                String::from(word),
+                                String::from(operand),
+                            ))
+                        })?;
+
+                    if self.labels.contains(name) {
+                        return Err(error(AssembleError::DuplicateLabel(String::from(name))));
+                    }
+
+                    let value = evaluate(expression, &self.constants).map_err(error)?;
+
+                    self.constants.insert(String::from(name), value);
+                }
+                ".include" => {
+                    let path = operand.trim_matches('"');
+                    let path = match file.and_then(|file| Path::new(file).parent()) {
+                        Some(directory) => directory.join(path).to_string_lossy().into_owned(),
+                        None => String::from(path),
+                    };
+
+                    if depth == MAX_DEPTH {
+                        return Err(error(AssembleError::RecursionLimit(path)));
+                    }
+
+                    let source = (self.include)(&path).map_err(|reason| {
+                        error(AssembleError::IncludeFailed(
+                            path.clone(),
+                            reason.to_string(),
+                        ))
+                    })?;
+
+                    let included: Vec<Line> = source
+                        .lines()
+                        .enumerate()
+                        .map(|(index, text)| (index + 1, String::from(strip_comment(text))))
+                        .collect();
+
+                    self.expand(Some(&path), &included, depth + 1)?;
+                }
+                ".endm" | ".endr" | ".endif" | ".else" => {
+                    return Err(error(AssembleError::UnmatchedDirective(String::from(word))));
+                }
+                _ => {
+                    if is_macro {
+                        if depth == MAX_DEPTH {
+                            return Err(error(AssembleError::RecursionLimit(String::from(word))));
+                        }
+
+                        let body: Vec<Line> = self
+                            .macro_body(word, operand)
+                            .map_err(error)?
+                            .into_iter()
+                            .map(|text| (line, text))
+                            .collect();
+
+                        self.expand(file, &body, depth + 1)?;
+                    } else {
+                        self.emit(file, line, text).map_err(error)?;
+                    }
+                }
+            }
+
+            index += 1;
+        }
+
+        Ok(())
+    }
+}
+
+/// Expand the macros, loops, conditionals, includes and constants of a source.
+///
+/// - `.macro name param, param=default` ... `.endm` defines a macro, invoked as
+///   `name value, param=value`. `\param` is replaced by the value of a parameter and
+///   `\@` by a number unique to the expansion, to make local labels.
+/// - `.rept count` ... `.endr` repeats lines.
+/// - `.irp name, value, ...` ... `.endr` repeats lines for every value of `\name`.
+/// - `.if expression` ... `.else` ... `.endif` keeps lines on a condition.
+/// - `.include "path"` expands a file, read with the include reader.
+/// - `.set name, expression` defines a constant, its name is replaced by its value in
+///   the field values and the expressions of the following lines. Constants and labels
+///   share their names.
+///
+/// Expressions are made of integers, constants and the operators of C.
+pub fn preprocess(
+    source: &str,
+    include: &mut IncludeReader,
+) -> Result<Vec<SourceLine>, SourceError> {
+    let lines: Vec<Line> = source
+        .lines()
+        .enumerate()
+        .map(|(index, text)| (index + 1, String::from(strip_comment(text))))
+        .collect();
+
+    let mut preprocessor = Preprocessor {
+        include,
+        constants: HashMap::new(),
+        macros: HashMap::new(),
+        labels: HashSet::new(),
+        expansion_count: 0,
+        output: Vec::new(),
+    };
+
+    preprocessor.expand(None, &lines, 0)?;
+
+    Ok(preprocessor.output)
+}
+
+/// An include reader for sources that cannot include files.
+pub fn no_include(path: &str) -> io::Result<String> {
+    Err(io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("cannot include {}", path),
+    ))
+}
@@ -0,0 +1,288 @@
+use super::*;
+use crate::maxhell::assembler::{assemble_module, assemble_source};
+use crate::maxhell::target::Target;
+
+const TARGET: Target = Target::SM50;
+
+const BRA_SELF: u64 = 0xe240_0fff_ff87_000f;
+
+/// The text of the expanded lines, trimmed.
+fn texts(source: &str) -> Vec<String> {
+    preprocess(source, &mut no_include)
+        .unwrap()
+        .into_iter()
+        .map(|line| String::from(line.text.trim()))
+        .collect()
+}
+
+fn error(source: &str) -> SourceError {
+    preprocess(source, &mut no_include).unwrap_err()
+}
+
+#[test]
+fn expands_macros() {
+    let source = ".macro EXCHANGE dst, address, value, offset=0x5\n\
+                  ATOMS destination_register=\\dst, source_register_a=\\address, \
+                  source_register_b=\\value, type_size=S32, register_a_offset_shr_2=\\offset, \
+                  operation=EXCH\n\
+                  B2R destination_register=\\dst, operation=BAR, destination_predicate_register=PT\n\
+                  .endm\n\
+                  EXCHANGE R4, R1, R2\n\
+                  EXCHANGE value=R3, dst=R5, address=R1, offset=1\n";
+
+    let expected = "ATOMS destination_register=R4, source_register_a=R1, source_register_b=R2, \
+                    type_size=S32, register_a_offset_shr_2=0x5, operation=EXCH\n\
+                    B2R destination_register=R4, operation=BAR, destination_predicate_register=PT\n\
+                    ATOMS destination_register=R5, source_register_a=R1, source_register_b=R3, \
+                    type_size=S32, register_a_offset_shr_2=1, operation=EXCH\n\
+                    B2R destination_register=R5, operation=BAR, destination_predicate_register=PT\n";
+
+    assert_eq!(
+        assemble_source(TARGET, source).unwrap(),
+        assemble_source(TARGET, expected).unwrap()
+    );
+
+    // The expanded lines keep the line of the invocation.
+    let lines: Vec<usize> = preprocess(source, &mut no_include)
+        .unwrap()
+        .iter()
+        .map(|line| line.line)
+        .collect();
+
+    assert_eq!(lines, [5, 5, 6, 6]);
+}
+
+#[test]
+fn makes_local_labels() {
+    let source = ".macro SPIN\n\
+                  spin\\@: BRA offset=spin\\@, cc_flags=TRUE\n\
+                  .endm\n\
+                  SPIN\n\
+                  SPIN\n";
+
+    let module = assemble_module(TARGET, source).unwrap();
+
+    assert_eq!(
+        module.sections[0].program().unwrap().instructions(),
+        [BRA_SELF, BRA_SELF]
+    );
+    assert_eq!(module.symbols.len(), 2);
+}
+
+#[test]
+fn repeats_lines() {
+    let source = ".rept 2\n\
+                  RAM\n\
+                  .endr\n\
+                  .irp register, R1, R2\n\
+                  .rept 1\n\
+                  B2R destination_register=\\register\n\
+                  .endr\n\
+                  .endr\n";
+
+    assert_eq!(
+        texts(source),
+        [
+            "RAM",
+            "RAM",
+            "B2R destination_register=R1",
+            "B2R destination_register=R2"
+        ]
+    );
+}
+
+#[test]
+fn evaluates_conditions_and_constants() {
+    let source = ".set COUNT, 2\n\
+                  .set OFFSET, COUNT * 4 + 1\n\
+                  .if OFFSET == 9 && !(COUNT > 2)\n\
+                  .if COUNT == 3\n\
+                  SAM\n\
+                  .else\n\
+                  RAM\n\
+                  .endif\n\
+                  .else\n\
+                  SAM\n\
+                  .endif\n\
+                  B2R imm8=OFFSET, destination_register=R0x\n";
+
+    assert_eq!(
+        texts(source),
+        ["RAM", "B2R imm8=9, destination_register=R0x"]
+    );
+
+    let constants = std::iter::once((String::from("A"), 3)).collect();
+
+    assert_eq!(evaluate("1 + 2 * A << 1", &constants), Ok(14));
+    assert_eq!(evaluate("-(4 - 6) % 3", &constants), Ok(2));
+    assert_eq!(evaluate("0x10 >> 2 | ~0 & 1", &constants), Ok(5));
+    assert_eq!(
+        evaluate("1 / 0", &constants),
+        Err(AssembleError::InvalidExpression(String::from("1 / 0")))
+    );
+}
+
+#[test]
+fn substitutes_constants_in_operands() {
+    let source = ".set X, 1\n\
+                  .set R1, 2\n\
+                  .set loop, 3\n\
+                  @P0 IADD.X R0, R1, X;\n\
+                  ATOMS destination_register=R1, register_a_offset_shr_2=X + loop, operation=EXCH\n\
+                  .section .X\n\
+                  .word X, R1";
+
+    assert_eq!(
+        texts(source),
+        [
+            "@P0 IADD.X R0, R1, X",
+            "ATOMS destination_register=R1, register_a_offset_shr_2=1 + 3, operation=EXCH",
+            ".section .X",
+            ".word 1, R1"
+        ]
+    );
+
+    // Labels cannot be replaced as they cannot be named after a constant.
+    assert_eq!(
+        error(".set loop, 1\nloop: RAM"),
+        SourceError {
+            file: None,
+            line: 2,
+            error: AssembleError::DuplicateLabel(String::from("loop")),
+        }
+    );
+    assert_eq!(
+        error(".entry main\n.set main, 1").error,
+        AssembleError::DuplicateLabel(String::from("main"))
+    );
+}
+
+#[test]
+fn includes_files() {
+    let mut include = |path: &str| match path {
+        "common.s" => Ok(String::from(".set VALUE, 4 // comment\n.word VALUE")),
+        "lib/outer.s" => Ok(String::from(".word 1\n.include \"inner.s\"")),
+        "lib/inner.s" => Ok(String::from("// comment\n.word 2\n.include \"missing.s\"")),
+        _ => Err(io::Error::new(io::ErrorKind::NotFound, "not found")),
+    };
+
+    let lines = preprocess(
+        ".section .data\n.include \"common.s\"\n.word VALUE + 1",
+        &mut include,
+    )
+    .unwrap();
+
+    assert_eq!(
+        lines[1..],
+        [
+            SourceLine {
+                file: Some(String::from("common.s")),
+                line: 2,
+                text: String::from(".word 4"),
+            },
+            SourceLine {
+                file: None,
+                line: 3,
+                text: String::from(".word 4 + 1"),
+            },
+        ]
+    );
+
+    assert_eq!(
+        preprocess("RAM\n.include \"missing.s\"", &mut include).unwrap_err(),
+        SourceError {
+            file: None,
+            line: 2,
+            error: AssembleError::IncludeFailed(
+                String::from("missing.s"),
+                String::from("not found")
+            ),
+        }
+    );
+
+    // Nested includes are relative to the including file, errors point to their line.
+    let error = preprocess(".include \"lib/outer.s\"", &mut include).unwrap_err();
+
+    assert_eq!(
+        error,
+        SourceError {
+            file: Some(String::from("lib/inner.s")),
+            line: 3,
+            error: AssembleError::IncludeFailed(
+                String::from("lib/missing.s"),
+                String::from("not found")
+            ),
+        }
+    );
+    assert_eq!(
+        error.to_string(),
+        "lib/inner.s:3: cannot include lib/missing.s: not found"
+    );
+}
+
+#[test]
+fn rejects_invalid_blocks() {
+    assert_eq!(
+        error("RAM\n.macro EMPTY\nRAM"),
+        SourceError {
+            file: None,
+            line: 2,
+            error: AssembleError::UnterminatedBlock(String::from(".macro")),
+        }
+    );
+    assert_eq!(
+        error(".endr").error,
+        AssembleError::UnmatchedDirective(String::from(".endr"))
+    );
+    assert_eq!(
+        error(".if 1\n.else\n.else\n.endif").error,
+        AssembleError::UnmatchedDirective(String::from(".else"))
+    );
+    assert_eq!(
+        error(".if UNDEFINED\n.endif").error,
+        AssembleError::InvalidExpression(String::from("UNDEFINED"))
+    );
+    assert_eq!(
+        error(".macro M a\n.endm\n.macro M\n.endm").error,
+        AssembleError::DuplicateMacro(String::from("M"))
+    );
+    assert_eq!(
+        error(".macro M a\n.endm\nM").error,
+        AssembleError::MissingMacroArgument(String::from("M"), String::from("a"))
+    );
+    assert_eq!(
+        error(".macro M a\n.endm\nM R1, R2").error,
+        AssembleError::InvalidMacroArgument(String::from("M"), String::from("R2"))
+    );
+    assert_eq!(
+        error(".macro LOOP\nLOOP\n.endm\nLOOP").error,
+        AssembleError::RecursionLimit(String::from("LOOP"))
+    );
+}
+
+#[test]
+fn limits_repetitions() {
+    assert_eq!(
+        error(
+            ".rept 0x100000000
+.endr"
+        ),
+        SourceError {
+            file: None,
+            line: 1,
+            error: AssembleError::ExpansionLimit(MAX_LINES),
+        }
+    );
+    assert_eq!(
+        error(
+            ".rept 0x400
+.rept 0x400
+RAM
+RAM
+.endr
+.endr"
+        )
+        .error,
+        AssembleError::ExpansionLimit(MAX_LINES)
+    );
+}
@@ -0,0 +1,865 @@
+use half::f16;
+
+use crate::maxhell::definition::*;
+use crate::maxhell::util::FieldDefinition;
+
+const RZ: u64 = 0xFF;
+const PT: u64 = 7;
+
+/// The fields of an instruction, looked up by the names of its layout.
+struct Fields {
+    opcode: Opcode,
+    raw: u64,
+}
+
+impl Fields {
+    fn field(&self, name: &str) -> &'static FieldDefinition {
+        self.opcode
+            .fields()
+            .iter()
+            .find(|field| field.name == name)
+            .unwrap_or_else(|| panic!("{:?} has no field {}", self.opcode, name))
+    }
+
+    fn value(&self, name: &str) -> u64 {
+        self.field(name).extract(self.raw)
+    }
+
+    fn signed(&self, name: &str) -> i64 {
+        let field = self.field(name);
+
+        field.sign_extend(field.extract(self.raw))
+    }
+
+    fn flag(&self, name: &str) -> bool {
+        self.value(name) != 0
+    }
+
+    fn variant(&self, name: &str) -> &'static str {
+        let field = self.field(name);
+
+        field
+            .variant_name(field.extract(self.raw))
+            .unwrap_or("INVALID")
+    }
+
+    fn register(&self, name: &str) -> String {
+        register(self.value(name))
+    }
+
+    fn predicate(&self, name: &str) -> String {
+        predicate(self.value(name), false)
+    }
+}
+
+fn register(value: u64) -> String {
+    if value == RZ {
+        String::from("RZ")
+    } else {
+        format!("R{}", value)
+    }
+}
+
+fn predicate(value: u64, invert: bool) -> String {
+    let invert = if invert { "!" } else { "" };
+
+    if value == PT {
+        format!("{}PT", invert)
+    } else {
+        format!("{}P{}", invert, value)
+    }
+}
+
+fn hex(value: i64) -> String {
+    if value < 0 {
+        format!("-0x{:x}", value.unsigned_abs())
+    } else {
+        format!("0x{:x}", value)
+    }
+}
+
+/// A memory operand, the register is omitted when it is RZ and the offset when it is 0.
+fn address(space: &str, base: u64, offset: i64) -> String {
+    if base == RZ {
+        format!("{}[{}]", space, hex(offset))
+    } else if offset == 0 {
+        format!("{}[{}]", space, register(base))
+    } else if offset < 0 {
+        format!("{}[{}-{}]", space, register(base), hex(-offset))
+    } else {
+        format!("{}[{}+{}]", space, register(base), hex(offset))
+    }
+}
+
+fn variant_name(variants: &[(&'static str, u64)], value: u8) -> &'static str {
+    variants
+        .iter()
+        .find(|(_, variant)| *variant == u64::from(value))
+        .map_or("INVALID", |(name, _)| name)
+}
+
+/// Letters of the components selected by a mask.
+fn components(mask: u8) -> String {
+    "RGBA"
+        .chars()
+        .enumerate()
+        .filter(|(index, _)| mask & (1 << index) != 0)
+        .map(|(_, component)| component)
+        .collect()
+}
+
+/// `TEXTURE_2D_ARRAY` is printed as `ARRAY_2D`.
+fn texture_type(name: &str) -> String {
+    let name = name.trim_start_matches("TEXTURE_");
+
+    match name.strip_suffix("_ARRAY") {
+        Some(name) => format!("ARRAY_{}", name),
+        None => String::from(name),
+    }
+}
+
+/// The 32-bit unsigned type is the default of the atomic instructions.
+fn atomic_type(name: &str) -> Option<String> {
+    match name {
+        "U32" => None,
+        "U64" => Some(String::from("64")),
+        _ => Some(String::from(name)),
+    }
+}
+
+fn video_type(signed: bool, selector: &str) -> String {
+    let width = match selector {
+        "W" => 32,
+        "H0" | "H1" => 16,
+        _ => 8,
+    };
+
+    format!("{}{}", if signed { "S" } else { "U" }, width)
+}
+
+fn video_operand(register: String, selector: &str) -> String {
+    if selector == "W" {
+        register
+    } else {
+        format!("{}.{}", register, selector)
+    }
+}
+
+fn half_operand(register: String, negate: bool, absolute: bool, swizzle: &str) -> String {
+    let mut operand = register;
+
+    if absolute {
+        operand = format!("|{}|", operand);
+    }
+
+    if negate {
+        operand = format!("-{}", operand);
+    }
+
+    if swizzle != "H1_H0" {
+        operand = format!("{}.{}", operand, swizzle);
+    }
+
+    operand
+}
+
+/// The two halves of an immediate, only their upper 10 bits are encoded.
+fn half_immediate(fields: &Fields) -> [String; 2] {
+    let half = |value: u64, negate: bool| {
+        f16::from_bits(((value << 6) | (u64::from(negate) << 15)) as u16).to_string()
+    };
+
+    [
+        half(
+            fields.value("immediate_low"),
+            fields.flag("negate_immediate_low"),
+        ),
+        half(
+            fields.value("immediate_high"),
+            fields.flag("negate_immediate_high"),
+        ),
+    ]
+}
+
+fn modifier(modifiers: &mut Vec<String>, condition: bool, modifier: &str) {
+    if condition {
+        modifiers.push(String::from(modifier));
+    }
+}
+
+/// Print an instruction as nvdisasm does, without its address and encoding.
+pub fn listing(instruction: u64) -> String {
+    let opcode = Opcode::decode(instruction).expect("unknown opcode");
+    let fields = Fields {
+        opcode,
+        raw: instruction,
+    };
+    let name = opcode.name();
+    let bindless = name.ends_with("_B");
+    let immediate = name.ends_with("_IMM");
+
+    let mut modifiers: Vec<String> = Vec::new();
+    let mut operands: Vec<String> = Vec::new();
+
+    match opcode {
+        Opcode::RAM | Opcode::SAM => {}
+        Opcode::RET
+        | Opcode::KIL
+        | Opcode::SYNC
+        | Opcode::BRK
+        | Opcode::CONT
+        | Opcode::EXIT
+        | Opcode::NOP => {
+            if opcode == Opcode::EXIT {
+                modifier(&mut modifiers, fields.flag("keep_refcount"), "KEEPREFCOUNT");
+            } else if opcode == Opcode::NOP {
+                modifier(&mut modifiers, fields.flag("trigger"), "TRIG");
+            }
+
+            let cc = fields.variant("cc_flags");
+
+            if cc != "TRUE" {
+                operands.push(format!("CC.{}", cc));
+            }
+
+            if opcode == Opcode::NOP && fields.value("imm16") != 0 {
+                operands.push(hex(fields.signed("imm16")));
+            }
+        }
+        Opcode::IDE => {
+            modifier(
+                &mut modifiers,
+                true,
+                if fields.flag("disable") { "DI" } else { "EN" },
+            );
+            operands.push(hex(fields.signed("imm16")));
+        }
+        Opcode::GETLMEMBASE => operands.push(fields.register("source_register")),
+        Opcode::SETLMEMBASE => operands.push(fields.register("destination_register")),
+        Opcode::AL2P | Opcode::ALD | Opcode::AST => {
+            let mode = fields.variant("mode");
+
+            modifier(&mut modifiers, mode != "M32", mode.trim_start_matches('M'));
+
+            if opcode != Opcode::AST {
+                modifier(&mut modifiers, fields.flag("o_flag"), "O");
+            }
+
+            if opcode == Opcode::AL2P {
+                if fields.value("destination_predicate_register") != PT {
+                    operands.push(fields.predicate("destination_predicate_register"));
+                }
+
+                operands.push(fields.register("destination_register"));
+                operands.push(fields.register("source_register"));
+                operands.push(hex(fields.signed("load_offset")));
+            } else {
+                modifier(&mut modifiers, fields.flag("no_physical_flag"), "P");
+
+                let attribute = address(
+                    "a",
+                    fields.value("source_offset_register"),
+                    fields.signed("load_offset"),
+                );
+
+                if opcode == Opcode::ALD {
+                    operands.push(fields.register("destination_register"));
+                    operands.push(attribute);
+                    operands.push(fields.register("source_register"));
+                } else {
+                    operands.push(attribute);
+                    operands.push(fields.register("destination_register"));
+                    operands.push(fields.register("source_register_b"));
+                }
+            }
+        }
+        Opcode::IPA => {
+            let sample_mode = fields.variant("sample_mode");
+
+            modifier(&mut modifiers, true, fields.variant("interpolation_mode"));
+            modifier(&mut modifiers, sample_mode != "DEFAULT", sample_mode);
+            modifier(&mut modifiers, fields.flag("idx_flag"), "IDX");
+            modifier(&mut modifiers, fields.flag("sat_flag"), "SAT");
+
+            operands.push(fields.register("destination_register"));
+            operands.push(address(
+                "a",
+                fields.value("source_register_a"),
+                fields.signed("attribute_shr_2") * 4,
+            ));
+            operands.push(fields.register("source_register_b"));
+            operands.push(fields.register("source_register_c"));
+        }
+        Opcode::OUT | Opcode::OUT_IMM => {
+            modifier(&mut modifiers, true, fields.variant("mode"));
+
+            operands.push(fields.register("destination_register"));
+            operands.push(fields.register("source_register_a"));
+            operands.push(if immediate {
+                hex(fields.signed("stream"))
+            } else {
+                fields.register("source_register_b")
+            });
+        }
+        Opcode::ATOMS | Opcode::ATOMS_CAS => {
+            modifiers.extend(
+                fields
+                    .variant("operation")
+                    .split('_')
+                    .map(String::from)
+                    .chain(atomic_type(fields.variant("type_size"))),
+            );
+
+            operands.push(fields.register("destination_register"));
+            operands.push(address(
+                "",
+                fields.value("source_register_a"),
+                fields.signed("register_a_offset_shr_2") * 4,
+            ));
+
+            if opcode == Opcode::ATOMS_CAS {
+                // NOTE: the comparison value is held by the register preceding the stored value.
+                operands.push(register(fields.value("source_register_b") - 1));
+            }
+
+            operands.push(fields.register("source_register_b"));
+        }
+        Opcode::ATOM | Opcode::ATOM_CAS | Opcode::RED => {
+            modifier(&mut modifiers, fields.flag("e_flag"), "E");
+
+            if opcode == Opcode::ATOM_CAS {
+                modifier(&mut modifiers, true, "CAS");
+            } else {
+                modifier(
+                    &mut modifiers,
+                    true,
+                    &fields.variant("operation").replace('_', ""),
+                );
+            }
+
+            modifiers.extend(atomic_type(fields.variant("type_size")));
+
+            let address = address(
+                "",
+                fields.value("source_register_a"),
+                fields.signed("register_a_offset"),
+            );
+
+            if opcode == Opcode::RED {
+                operands.push(address);
+                operands.push(fields.register("source_register_b"));
+            } else {
+                operands.push(fields.register("destination_register"));
+                operands.push(address);
+                operands.push(fields.register("source_register_b"));
+            }
+
+            // NOTE: the stored value follows the comparison value.
+            if opcode == Opcode::ATOM_CAS {
+                let width = if fields.variant("type_size") == "U64" {
+                    2
+                } else {
+                    1
+                };
+
+                operands.push(register(fields.value("source_register_b") + width));
+            }
+        }
+        Opcode::B2R => {
+            modifier(&mut modifiers, true, fields.variant("operation"));
+
+            if fields.value("destination_predicate_register") != PT {
+                operands.push(fields.predicate("destination_predicate_register"));
+            }
+
+            operands.push(fields.register("destination_register"));
+            operands.push(hex(fields.signed("imm8")));
+        }
+        Opcode::BAR => {
+            let mode = fields.variant("mode");
+
+            modifier(&mut modifiers, true, mode);
+            modifier(&mut modifiers, mode == "RED", fields.variant("reduction"));
+
+            operands.push(if fields.flag("barrier_immediate_flag") {
+                hex(fields.signed("barrier"))
+            } else {
+                register(fields.value("barrier"))
+            });
+            operands.push(if fields.flag("thread_count_immediate_flag") {
+                hex(fields.signed("thread_count"))
+            } else {
+                register(fields.value("thread_count"))
+            });
+
+            if mode == "RED" {
+                operands.push(predicate(
+                    fields.value("reduction_predicate_register"),
+                    fields.flag("invert_reduction_predicate"),
+                ));
+            }
+        }
+        Opcode::MEMBAR => modifier(&mut modifiers, true, fields.variant("level")),
+        Opcode::DEPBAR => {
+            modifier(&mut modifiers, fields.flag("le_flag"), "LE");
+
+            operands.push(format!("SB{}", fields.value("scoreboard")));
+            operands.push(hex(fields.signed("count")));
+
+            let mask = fields.value("dependency_mask");
+
+            if mask != 0 {
+                let scoreboards: Vec<String> = (0..6)
+                    .filter(|scoreboard| mask & (1 << scoreboard) != 0)
+                    .map(|scoreboard| scoreboard.to_string())
+                    .collect();
+
+                operands.push(format!("{{{}}}", scoreboards.join(",")));
+            }
+        }
+        Opcode::TEX
+        | Opcode::TEX_B
+        | Opcode::TLD
+        | Opcode::TLD_B
+        | Opcode::TLD4
+        | Opcode::TLD4_B
+        | Opcode::TMML
+        | Opcode::TMML_B
+        | Opcode::TXD
+        | Opcode::TXD_B => {
+            modifier(&mut modifiers, bindless, "B");
+
+            match opcode {
+                Opcode::TEX | Opcode::TEX_B => {
+                    let lod_mode = fields.variant("lod_mode");
+
+                    modifier(&mut modifiers, lod_mode != "NONE", lod_mode);
+                    modifier(&mut modifiers, fields.flag("aoffi_flag"), "AOFFI");
+                    modifier(&mut modifiers, fields.flag("dc_flag"), "DC");
+                    modifier(&mut modifiers, fields.flag("lc_flag"), "LC");
+                    modifier(&mut modifiers, fields.flag("ndv_flag"), "NDV");
+                }
+                Opcode::TLD | Opcode::TLD_B => {
+                    modifier(
+                        &mut modifiers,
+                        true,
+                        if fields.flag("ll_flag") { "LL" } else { "LZ" },
+                    );
+                    modifier(&mut modifiers, fields.flag("aoffi_flag"), "AOFFI");
+                    modifier(&mut modifiers, fields.flag("ms_flag"), "MS");
+                }
+                Opcode::TLD4 | Opcode::TLD4_B => {
+                    let offset_mode = fields.variant("offset_mode");
+
+                    modifier(&mut modifiers, true, fields.variant("component"));
+                    modifier(&mut modifiers, offset_mode != "NONE", offset_mode);
+                    modifier(&mut modifiers, fields.flag("dc_flag"), "DC");
+                    modifier(&mut modifiers, fields.flag("ndv_flag"), "NDV");
+                }
+                Opcode::TMML | Opcode::TMML_B => {
+                    modifier(&mut modifiers, fields.flag("ndv_flag"), "NDV")
+                }
+                _ => modifier(&mut modifiers, fields.flag("aoffi_flag"), "AOFFI"),
+            }
+
+            modifier(&mut modifiers, fields.flag("nodep_flag"), "NODEP");
+
+            if (opcode == Opcode::TEX || opcode == Opcode::TEX_B)
+                && fields.value("sparse_predicate_register") != PT
+            {
+                operands.push(fields.predicate("sparse_predicate_register"));
+            }
+
+            operands.push(fields.register("destination_register"));
+            operands.push(fields.register("source_register_a"));
+            operands.push(fields.register("source_register_b"));
+
+            if !bindless {
+                operands.push(hex(fields.signed("texture_index")));
+            }
+
+            operands.push(texture_type(fields.variant("texture_type")));
+            operands.push(hex(fields.signed("component_mask")));
+        }
+        Opcode::TXQ | Opcode::TXQ_B => {
+            modifier(&mut modifiers, bindless, "B");
+            modifier(&mut modifiers, fields.flag("nodep_flag"), "NODEP");
+
+            operands.push(fields.register("destination_register"));
+            operands.push(fields.register("source_register"));
+
+            if !bindless {
+                operands.push(hex(fields.signed("texture_index")));
+            }
+
+            operands.push(String::from(fields.variant("query")));
+            operands.push(hex(fields.signed("component_mask")));
+        }
+        Opcode::TEXS | Opcode::TLDS | Opcode::TLD4S => {
+            // NOTE: the texture type, LOD mode, DC, AOFFI and MS flags of the encoding.
+            let texture = match opcode {
+                Opcode::TEXS => TEXS_ENCODINGS.get(fields.value("encoding") as usize).map(
+                    |(texture_type, lod_mode, dc)| (*texture_type, *lod_mode, *dc, false, false),
+                ),
+                Opcode::TLDS => TLDS_ENCODINGS
+                    .iter()
+                    .find(|(encoding, ..)| u64::from(*encoding) == fields.value("encoding"))
+                    .map(|(_, texture_type, lod_mode, aoffi, ms)| {
+                        (*texture_type, *lod_mode, false, *aoffi, *ms)
+                    }),
+                _ => None,
+            };
+
+            if opcode == Opcode::TLD4S {
+                modifier(&mut modifiers, true, fields.variant("component"));
+                modifier(&mut modifiers, fields.flag("aoffi_flag"), "AOFFI");
+                modifier(&mut modifiers, fields.flag("dc_flag"), "DC");
+            } else if let Some((_, lod_mode, dc, aoffi, ms)) = texture {
+                modifier(
+                    &mut modifiers,
+                    lod_mode != TextureLodMode::NONE,
+                    variant_name(TextureLodMode::VARIANTS, u8::from(lod_mode)),
+                );
+                modifier(&mut modifiers, dc, "DC");
+                modifier(&mut modifiers, aoffi, "AOFFI");
+                modifier(&mut modifiers, ms, "MS");
+            }
+
+            modifier(&mut modifiers, fields.flag("nodep_flag"), "NODEP");
+            modifier(&mut modifiers, fields.flag("fp16_flag"), "F16");
+
+            operands.push(fields.register("destination_register_a"));
+            operands.push(fields.register("destination_register_b"));
+            operands.push(fields.register("source_register_a"));
+            operands.push(fields.register("source_register_b"));
+            operands.push(hex(fields.signed("texture_index")));
+
+            if opcode != Opcode::TLD4S {
+                // NOTE: the second destination selects the masks writing more than two components.
+                let masks: &[u8] = if fields.value("destination_register_b") == RZ {
+                    &TEXTURE_SINGLE_DESTINATION_MASKS
+                } else {
+                    &TEXTURE_DUAL_DESTINATION_MASKS
+                };
+                let mask = masks
+                    .get(fields.value("component_swizzle") as usize)
+                    .copied()
+                    .unwrap_or(0);
+
+                operands.push(match texture {
+                    Some((texture_type, ..)) => self::texture_type(variant_name(
+                        TextureType::VARIANTS,
+                        u8::from(texture_type),
+                    )),
+                    None => String::from("INVALID"),
+                });
+                operands.push(components(mask));
+            }
+        }
+        Opcode::SULD | Opcode::SULD_B | Opcode::SUST | Opcode::SUST_B => {
+            let format = fields.value("format");
+            let clamp_mode = fields.variant("clamp_mode");
+
+            let d_flag = fields.flag("d_flag");
+
+            modifier(&mut modifiers, true, if d_flag { "D" } else { "P" });
+            modifier(
+                &mut modifiers,
+                true,
+                fields
+                    .variant("surface_type")
+                    .trim_start_matches("SURFACE_"),
+            );
+
+            if d_flag {
+                modifier(
+                    &mut modifiers,
+                    true,
+                    variant_name(SurfaceSize::VARIANTS, format as u8),
+                );
+            } else {
+                modifier(&mut modifiers, true, &components(format as u8));
+            }
+
+            modifier(&mut modifiers, clamp_mode != "IGN", clamp_mode);
+
+            let surface = if bindless {
+                fields.register("surface_register")
+            } else {
+                hex(fields.signed("surface_index"))
+            };
+            let coordinates = address("", fields.value("source_register"), 0);
+
+            if opcode == Opcode::SULD || opcode == Opcode::SULD_B {
+                operands.push(fields.register("destination_register"));
+                operands.push(coordinates);
+            } else {
+                operands.push(coordinates);
+                operands.push(fields.register("destination_register"));
+            }
+
+            operands.push(surface);
+        }
+        Opcode::SUATOM | Opcode::SURED => {
+            let clamp_mode = fields.variant("clamp_mode");
+
+            modifier(&mut modifiers, true, "D");
+            modifier(
+                &mut modifiers,
+                true,
+                fields
+                    .variant("surface_type")
+                    .trim_start_matches("SURFACE_"),
+            );
+            modifier(
+                &mut modifiers,
+                true,
+                &fields.variant("operation").replace('_', ""),
+            );
+            modifiers.extend(atomic_type(fields.variant("type_size")));
+            modifier(&mut modifiers, clamp_mode != "IGN", clamp_mode);
+
+            if opcode == Opcode::SUATOM {
+                operands.push(fields.register("destination_register"));
+            }
+
+            operands.push(address("", fields.value("source_register_a"), 0));
+            operands.push(fields.register("source_register_b"));
+            operands.push(fields.register("surface_register"));
+        }
+        Opcode::VADD
+        | Opcode::VADD_IMM
+        | Opcode::VABSDIFF
+        | Opcode::VABSDIFF_IMM
+        | Opcode::VSHL
+        | Opcode::VSHL_IMM
+        | Opcode::VSHR
+        | Opcode::VSHR_IMM
+        | Opcode::VMNMX
+        | Opcode::VMNMX_IMM
+        | Opcode::VMAD
+        | Opcode::VMAD_IMM
+        | Opcode::VSET
+        | Opcode::VSET_IMM
+        | Opcode::VSETP
+        | Opcode::VSETP_IMM => {
+            let a_selector = fields.variant("a_selector");
+            let (b_type, b_operand) = if immediate {
+                (
+                    format!(
+                        "{}16",
+                        if fields.flag("b_signed_flag") {
+                            "S"
+                        } else {
+                            "U"
+                        }
+                    ),
+                    hex(fields.signed("imm16")),
+                )
+            } else {
+                let b_selector = fields.variant("b_selector");
+
+                (
+                    video_type(fields.flag("b_signed_flag"), b_selector),
+                    video_operand(fields.register("source_register_b"), b_selector),
+                )
+            };
+
+            match opcode {
+                Opcode::VSET | Opcode::VSET_IMM => {
+                    modifier(&mut modifiers, true, fields.variant("compare_operation"))
+                }
+                Opcode::VSETP | Opcode::VSETP_IMM => {
+                    modifier(&mut modifiers, true, fields.variant("compare_operation"));
+                    modifier(&mut modifiers, true, fields.variant("predicate_operation"));
+                }
+                _ => {}
+            }
+
+            modifier(
+                &mut modifiers,
+                true,
+                &video_type(fields.flag("a_signed_flag"), a_selector),
+            );
+            modifier(&mut modifiers, true, &b_type);
+
+            match opcode {
+                Opcode::VMAD | Opcode::VMAD_IMM => {
+                    let scale = fields.variant("scale");
+
+                    modifier(&mut modifiers, scale != "NONE", scale);
+                }
+                Opcode::VSETP | Opcode::VSETP_IMM => {}
+                _ => {
+                    let secondary_operation = fields.variant("secondary_operation");
+
+                    modifier(
+                        &mut modifiers,
+                        secondary_operation != "NONE",
+                        secondary_operation,
+                    );
+                }
+            }
+
+            if opcode == Opcode::VMNMX || opcode == Opcode::VMNMX_IMM {
+                modifier(&mut modifiers, fields.flag("mx_flag"), "MX");
+            }
+
+            if opcode != Opcode::VSET
+                && opcode != Opcode::VSET_IMM
+                && opcode != Opcode::VSETP
+                && opcode != Opcode::VSETP_IMM
+            {
+                modifier(&mut modifiers, fields.flag("sat_flag"), "SAT");
+            }
+
+            if opcode == Opcode::VSETP || opcode == Opcode::VSETP_IMM {
+                operands.push(fields.predicate("destination_predicate_register_a"));
+                operands.push(fields.predicate("destination_predicate_register_b"));
+            } else {
+                operands.push(fields.register("destination_register"));
+            }
+
+            operands.push(video_operand(
+                fields.register("source_register_a"),
+                a_selector,
+            ));
+            operands.push(b_operand);
+
+            if opcode == Opcode::VSETP || opcode == Opcode::VSETP_IMM {
+                operands.push(predicate(
+                    fields.value("combine_predicate_register"),
+                    fields.flag("invert_combine_predicate"),
+                ));
+            } else {
+                operands.push(fields.register("source_register_c"));
+            }
+        }
+        Opcode::HADD2
+        | Opcode::HADD2_IMM
+        | Opcode::HMUL2
+        | Opcode::HMUL2_IMM
+        | Opcode::HFMA2
+        | Opcode::HFMA2_IMM => {
+            if opcode == Opcode::HADD2 || opcode == Opcode::HADD2_IMM {
+                modifier(&mut modifiers, fields.flag("ftz_flag"), "FTZ");
+            } else {
+                let multiply_mode = fields.variant("multiply_mode");
+
+                modifier(&mut modifiers, multiply_mode != "NONE", multiply_mode);
+            }
+
+            let merge = fields.variant("merge");
+
+            modifier(&mut modifiers, merge != "H1_H0", merge);
+            modifier(&mut modifiers, fields.flag("sat_flag"), "SAT");
+
+            let fma = opcode == Opcode::HFMA2 || opcode == Opcode::HFMA2_IMM;
+
+            operands.push(fields.register("destination_register"));
+            operands.push(half_operand(
+                fields.register("source_register_a"),
+                !fma && fields.flag("negate_a"),
+                !fma && fields.flag("absolute_a"),
+                fields.variant("a_swizzle"),
+            ));
+
+            if immediate {
+                operands.extend(half_immediate(&fields));
+            } else {
+                operands.push(half_operand(
+                    fields.register("source_register_b"),
+                    fields.flag("negate_b"),
+                    !fma && fields.flag("absolute_b"),
+                    fields.variant("b_swizzle"),
+                ));
+            }
+
+            if fma {
+                operands.push(half_operand(
+                    fields.register("source_register_c"),
+                    fields.flag("negate_c"),
+                    false,
+                    fields.variant("c_swizzle"),
+                ));
+            }
+        }
+        Opcode::HSET2 | Opcode::HSET2_IMM | Opcode::HSETP2 | Opcode::HSETP2_IMM => {
+            let setp = opcode == Opcode::HSETP2 || opcode == Opcode::HSETP2_IMM;
+
+            if !setp {
+                modifier(&mut modifiers, fields.flag("bf_flag"), "BF");
+            }
+
+            modifier(&mut modifiers, true, fields.variant("compare_operation"));
+
+            if setp {
+                modifier(&mut modifiers, fields.flag("h_and_flag"), "H_AND");
+            }
+
+            modifier(&mut modifiers, true, fields.variant("predicate_operation"));
+            modifier(&mut modifiers, fields.flag("ftz_flag"), "FTZ");
+
+            if setp {
+                operands.push(fields.predicate("destination_predicate_register_a"));
+                operands.push(fields.predicate("destination_predicate_register_b"));
+            } else {
+                operands.push(fields.register("destination_register"));
+            }
+
+            operands.push(half_operand(
+                fields.register("source_register_a"),
+                fields.flag("negate_a"),
+                fields.flag("absolute_a"),
+                fields.variant("a_swizzle"),
+            ));
+
+            if immediate {
+                operands.extend(half_immediate(&fields));
+            } else {
+                operands.push(half_operand(
+                    fields.register("source_register_b"),
+                    fields.flag("negate_b"),
+                    fields.flag("absolute_b"),
+                    fields.variant("b_swizzle"),
+                ));
+            }
+
+            operands.push(predicate(
+                fields.value("combine_predicate_register"),
+                fields.flag("invert_combine_predicate"),
+            ));
+        }
+        _ => panic!("no listing syntax for {:?}", opcode),
+    }
+
+    let mut text = String::new();
+
+    if opcode
+        .fields()
+        .iter()
+        .any(|field| field.name == "source_predicate_register")
+    {
+        let guard = fields.value("source_predicate_register");
+        let invert = fields.flag("invert_source_predicate");
+
+        if guard != PT || invert {
+            text.push_str(&format!("@{} ", predicate(guard, invert)));
+        }
+    }
+
+    text.push_str(name.split('_').next().unwrap());
+
+    for modifier in modifiers {
+        text.push('.');
+        text.push_str(&modifier);
+    }
+
+    if !operands.is_empty() {
+        text.push(' ');
+        text.push_str(&operands.join(", "));
+    }
+
+    text.push_str(" ;");
+    text
+}
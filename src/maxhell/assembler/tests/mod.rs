@@ -0,0 +1,209 @@
+use std::fs;
+use std::path::Path;
+
+use super::*;
+use crate::maxhell::disassembler::disassemble;
+
+mod listing;
+
+use listing::listing;
+
+struct GoldenEntry {
+    location: String,
+    assembly: String,
+    encoding: u64,
+    listing: String,
+}
+
+fn load_golden_file(path: &Path) -> Vec<GoldenEntry> {
+    let content = fs::read_to_string(path).unwrap();
+    let mut entries = Vec::new();
+
+    for (line_number, line) in content.lines().enumerate() {
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let location = format!("{}:{}", path.display(), line_number + 1);
+        let columns: Vec<&str> = line.splitn(3, '|').map(str::trim).collect();
+
+        assert_eq!(columns.len(), 3, "{}: expected three columns", location);
+
+        let encoding = columns[1]
+            .strip_prefix("0x")
+            .and_then(|hex| u64::from_str_radix(hex, 16).ok())
+            .unwrap_or_else(|| panic!("{}: invalid encoding {}", location, columns[1]));
+
+        entries.push(GoldenEntry {
+            location,
+            assembly: columns[0].to_string(),
+            encoding,
+            listing: columns[2].to_string(),
+        });
+    }
+
+    entries
+}
+
+/// Split a line printed by `nvdisasm -hex` into the instruction and its encoding.
+fn split_listing<'a>(location: &str, listing: &'a str) -> (&'a str, u64) {
+    let (text, encoding) = listing
+        .strip_suffix("*/")
+        .and_then(|listing| listing.rsplit_once("/*"))
+        .unwrap_or_else(|| panic!("{}: the listing has no encoding", location));
+
+    let encoding = encoding
+        .trim()
+        .strip_prefix("0x")
+        .and_then(|hex| u64::from_str_radix(hex, 16).ok())
+        .unwrap_or_else(|| panic!("{}: invalid listing encoding {}", location, encoding));
+
+    (text.trim(), encoding)
+}
+
+#[test]
+fn golden_corpus() {
+    let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden");
+    let mut checked = 0;
+
+    for file in fs::read_dir(&directory).unwrap() {
+        let path = file.unwrap().path();
+
+        if path.extension().and_then(|extension| extension.to_str()) != Some("golden") {
+            continue;
+        }
+
+        let target: Target = path.file_stem().unwrap().to_str().unwrap().parse().unwrap();
+
+        for entry in load_golden_file(&path) {
+            let encoding = assemble_instruction(target, &entry.assembly)
+                .unwrap_or_else(|error| panic!("{}: {}", entry.location, error));
+
+            assert_eq!(
+                encoding, entry.encoding,
+                "{}: encoded 0x{:016x}",
+                entry.location, encoding
+            );
+
+            let disassembly = disassemble(entry.encoding)
+                .unwrap_or_else(|| panic!("{}: cannot disassemble", entry.location));
+
+            assert_eq!(disassembly, entry.assembly, "{}", entry.location);
+
+            let (text, listing_encoding) = split_listing(&entry.location, &entry.listing);
+
+            assert_eq!(
+                listing_encoding, entry.encoding,
+                "{}: listing encoding",
+                entry.location
+            );
+            assert_eq!(listing(entry.encoding), text, "{}: listing", entry.location);
+
+            checked += 1;
+        }
+    }
+
+    assert!(checked > 0, "no golden entries found");
+}
+
+#[test]
+fn rejects_invalid_instructions() {
+    assert_eq!(
+        assemble_instruction(Target::SM50, "FOO"),
+        Err(AssembleError::UnknownOpcode("FOO".to_string()))
+    );
+    assert_eq!(
+        assemble_instruction(Target::SM50, "HADD2"),
+        Err(AssembleError::UnsupportedInstruction(
+            Opcode::HADD2,
+            Target::SM50
+        ))
+    );
+    assert_eq!(
+        assemble_instruction(Target::SM50, "@P0 RAM"),
+        Err(AssembleError::UnexpectedPredicate(Opcode::RAM))
+    );
+    assert_eq!(
+        assemble_instruction(Target::SM50, "MEMBAR level=CTA, level=GL"),
+        Err(AssembleError::DuplicateField(
+            Opcode::MEMBAR,
+            "level".to_string()
+        ))
+    );
+    assert_eq!(
+        assemble_instruction(Target::SM50, "GETLMEMBASE source_register=R256"),
+        Err(AssembleError::InvalidValue(
+            Opcode::GETLMEMBASE,
+            "source_register".to_string(),
+            "R256".to_string()
+        ))
+    );
+}
+
+#[test]
+fn assembles_sources() {
+    let source = "// Header comment\n\
+                  \n\
+                  RAM\n\
+                  @!P1 EXIT cc_flags=TRUE // Trailing comment\n";
+
+    let program = assemble_source(Target::SM50, source).unwrap();
+
+    assert_eq!(
+        program.instructions(),
+        [
+            0xe380_0000_0000_0000,
+            assemble_instruction(Target::SM50, "@!P1 EXIT cc_flags=TRUE").unwrap(),
+        ]
+    );
+    assert_eq!(
+        assemble_source(Target::SM50, "RAM\nFOO\n").unwrap_err(),
+        SourceError {
+            file: None,
+            line: 2,
+            error: AssembleError::UnknownOpcode(String::from("FOO")),
+        }
+    );
+}
+
+#[test]
+fn resolves_labels() {
+    let source = "start: SSY offset=end\n\
+                  @P0 BRA offset=start\n\
+                  loop:\n\
+                  BRA cc_flags=TRUE, offset=loop // Branch to itself\n\
+                  SYNC cc_flags=TRUE\n\
+                  end:\n";
+
+    let program = assemble_source(Target::SM50, source).unwrap();
+    let instructions = program.instructions();
+
+    assert_eq!(
+        instructions[0],
+        assemble_instruction(Target::SM50, "SSY offset=0x20").unwrap()
+    );
+    assert_eq!(
+        instructions[1],
+        assemble_instruction(Target::SM50, "@P0 BRA offset=-0x10").unwrap()
+    );
+    assert_eq!(instructions[2], 0xe240_0fff_ff87_000f);
+
+    assert_eq!(
+        assemble_source(Target::SM50, "BRA offset=nowhere").unwrap_err(),
+        SourceError {
+            file: None,
+            line: 1,
+            error: AssembleError::UnknownLabel(String::from("nowhere")),
+        }
+    );
+    assert_eq!(
+        assemble_source(Target::SM50, "a: RAM\na: SAM").unwrap_err(),
+        SourceError {
+            file: None,
+            line: 2,
+            error: AssembleError::DuplicateLabel(String::from("a")),
+        }
+    );
+}
@@ -0,0 +1,859 @@
+use std::convert::{TryFrom, TryInto};
+use std::error::Error;
+use std::fmt;
+
+use super::definition::*;
+use super::encoder::EncodeError;
+use super::program::{instruction_address, Program, ProgramInfo};
+use super::target::Target;
+use crate::enum_with_val;
+
+mod reader;
+#[cfg(test)]
+mod tests;
+
+pub use reader::*;
+
+/// Offset of the kernel parameters inside of the constant buffer 0.
+///
+/// NOTE: the driver uses the bytes before the parameters for the launch configuration.
+pub const PARAMETER_BASE: u32 = 0x140;
+
+const ELF_HEADER_SIZE: usize = 0x40;
+const PROGRAM_HEADER_SIZE: usize = 0x38;
+const SECTION_HEADER_SIZE: usize = 0x40;
+const SYMBOL_SIZE: usize = 0x18;
+
+const ELFCLASS64: u8 = 2;
+const ELFDATA2LSB: u8 = 1;
+const ELFOSABI_CUDA: u8 = 0x33;
+const ELFABIVERSION_CUDA: u8 = 7;
+const EV_CURRENT: u8 = 1;
+const ET_EXEC: u16 = 2;
+const EM_CUDA: u16 = 190;
+
+const EF_CUDA_TEXMODE_UNIFIED: u32 = 0x100;
+const EF_CUDA_64BIT_ADDRESS: u32 = 0x400;
+const EF_CUDA_VIRTUAL_SM_SHIFT: u32 = 16;
+
+const SHT_PROGBITS: u32 = 1;
+const SHT_SYMTAB: u32 = 2;
+const SHT_STRTAB: u32 = 3;
+const SHT_NOBITS: u32 = 8;
+const SHT_CUDA_INFO: u32 = 0x7000_0000;
+
+const SHF_WRITE: u64 = 0x1;
+const SHF_ALLOC: u64 = 0x2;
+const SHF_EXECINSTR: u64 = 0x4;
+const SHF_INFO_LINK: u64 = 0x40;
+
+/// Position of the barrier count in the flags of a .text section.
+const SHF_BARRIERS_SHIFT: u32 = 20;
+
+/// Position of the register count in the info of a .text section.
+const SHI_REGISTERS_SHIFT: u32 = 24;
+
+const STB_LOCAL: u8 = 0;
+const STB_GLOBAL: u8 = 1;
+const STT_FUNC: u8 = 2;
+const STT_SECTION: u8 = 3;
+const STO_CUDA_ENTRY: u8 = 0x10;
+
+const PT_LOAD: u32 = 1;
+const PT_PHDR: u32 = 6;
+const PF_X: u32 = 0x1;
+const PF_W: u32 = 0x2;
+const PF_R: u32 = 0x4;
+
+// NOTE: the layout of the cubin only depends on the number of kernels, so do the indices of
+// the sections and symbols.
+const SHSTRTAB_INDEX: u16 = 1;
+const STRTAB_INDEX: u32 = 2;
+const SYMTAB_INDEX: u32 = 3;
+const FIRST_KERNEL_INDEX: u32 = 5;
+
+/// Indices of the sections and symbols of a cubin holding a number of kernels.
+///
+/// The sections of the kernels are grouped by kind: every .nv.info.<kernel> section, then
+/// every .nv.constant0.<kernel>, .text.<kernel> and .nv.shared.<kernel> section.
+/// The symbols of these sections come first, followed by the global kernel symbols.
+#[derive(Debug, Clone, Copy)]
+struct Layout {
+    kernel_count: u32,
+}
+
+impl Layout {
+    fn constant_index(self, kernel: u32) -> u32 {
+        FIRST_KERNEL_INDEX + self.kernel_count + kernel
+    }
+
+    fn text_index(self, kernel: u32) -> u32 {
+        FIRST_KERNEL_INDEX + 2 * self.kernel_count + kernel
+    }
+
+    fn shared_index(self, kernel: u32) -> u32 {
+        FIRST_KERNEL_INDEX + 3 * self.kernel_count + kernel
+    }
+
+    fn text_symbol(self, kernel: u32) -> u32 {
+        1 + 3 * kernel
+    }
+
+    fn constant_symbol(self, kernel: u32) -> u32 {
+        2 + 3 * kernel
+    }
+
+    fn shared_symbol(self, kernel: u32) -> u32 {
+        3 + 3 * kernel
+    }
+
+    fn kernel_symbol(self, kernel: u32) -> u32 {
+        1 + 3 * self.kernel_count + kernel
+    }
+}
+
+/// Error returned when writing a cubin.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum CubinWriteError {
+    Encode(EncodeError),
+    /// A cubin must hold at least one kernel.
+    NoKernel,
+    /// The value of the .nv.info attribute doesn't fit in its 16-bit size, the size is given.
+    AttributeTooLarge(InfoAttribute, usize),
+}
+
+impl fmt::Display for CubinWriteError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CubinWriteError::Encode(error) => write!(f, "{}", error),
+            CubinWriteError::NoKernel => write!(f, "a cubin requires at least one kernel"),
+            CubinWriteError::AttributeTooLarge(attribute, size) => write!(
+                f,
+                ".nv.info attribute {:?} is too large ({} bytes)",
+                attribute, size
+            ),
+        }
+    }
+}
+
+impl Error for CubinWriteError {}
+
+impl From<EncodeError> for CubinWriteError {
+    fn from(error: EncodeError) -> Self {
+        CubinWriteError::Encode(error)
+    }
+}
+
+enum_with_val! {
+    /// How the value of a .nv.info attribute is stored.
+    #[derive(PartialEq, Eq, Clone, Copy)]
+    pub struct InfoFormat(u8) {
+        NVAL = 1,
+        BVAL = 2,
+        HVAL = 3,
+        SVAL = 4,
+    }
+}
+
+enum_with_val! {
+    /// Attributes of the .nv.info sections, known as EIATTR_* by the CUDA tools.
+    #[derive(PartialEq, Eq, Clone, Copy)]
+    pub struct InfoAttribute(u8) {
+        CTAIDZ_USED = 0x04,
+        MAX_THREADS = 0x05,
+        PARAM_CBANK = 0x0a,
+        SYNC_STACK = 0x0d,
+        REQNTID = 0x10,
+        FRAME_SIZE = 0x11,
+        MIN_STACK_SIZE = 0x12,
+        KPARAM_INFO = 0x17,
+        CBANK_PARAM_SIZE = 0x19,
+        MAXREG_COUNT = 0x1b,
+        EXIT_INSTR_OFFSETS = 0x1c,
+        S2RCTAID_INSTR_OFFSETS = 0x1d,
+        CRS_STACK_SIZE = 0x1e,
+        MAX_STACK_SIZE = 0x23,
+        REGCOUNT = 0x2f,
+    }
+}
+
+/// The value of a .nv.info attribute.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum InfoValue {
+    None,
+    Byte(u8),
+    Half(u16),
+    Data(Vec<u8>),
+}
+
+/// An entry of a .nv.info section.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct InfoEntry {
+    pub attribute: InfoAttribute,
+    pub value: InfoValue,
+}
+
+impl InfoEntry {
+    pub fn new(attribute: InfoAttribute, value: InfoValue) -> Self {
+        InfoEntry { attribute, value }
+    }
+
+    /// Create an entry holding a list of words.
+    pub fn words(attribute: InfoAttribute, words: &[u32]) -> Self {
+        InfoEntry::new(
+            attribute,
+            InfoValue::Data(words.iter().flat_map(|word| word.to_le_bytes()).collect()),
+        )
+    }
+
+    /// The words of the value, if its size is a multiple of 4 bytes.
+    pub fn as_words(&self) -> Option<Vec<u32>> {
+        match &self.value {
+            InfoValue::Data(data) if data.len().is_multiple_of(4) => Some(
+                data.chunks_exact(4)
+                    .map(|word| u32::from_le_bytes(word.try_into().unwrap()))
+                    .collect(),
+            ),
+            _ => None,
+        }
+    }
+
+    /// Decode the entry to a readable structure.
+    pub fn record(&self) -> InfoRecord {
+        let words = self.as_words().unwrap_or_default();
+
+        let record = match (self.attribute, &self.value, words.as_slice()) {
+            (InfoAttribute::REGCOUNT, _, &[function, registers]) => {
+                Some(InfoRecord::RegisterCount {
+                    function,
+                    registers,
+                })
+            }
+            (InfoAttribute::FRAME_SIZE, _, &[function, size]) => {
+                Some(InfoRecord::FrameSize { function, size })
+            }
+            (InfoAttribute::MIN_STACK_SIZE, _, &[function, size]) => {
+                Some(InfoRecord::MinStackSize { function, size })
+            }
+            (InfoAttribute::MAX_STACK_SIZE, _, &[function, size]) => {
+                Some(InfoRecord::MaxStackSize { function, size })
+            }
+            (InfoAttribute::PARAM_CBANK, _, &[section, location]) => {
+                Some(InfoRecord::ParameterBank {
+                    section,
+                    offset: location as u16,
+                    size: (location >> 16) as u16,
+                })
+            }
+            (InfoAttribute::CBANK_PARAM_SIZE, InfoValue::Half(size), _) => {
+                Some(InfoRecord::ParameterBankSize(*size))
+            }
+            (InfoAttribute::KPARAM_INFO, _, &[index, location, flags]) => {
+                Some(InfoRecord::KernelParameter {
+                    index,
+                    ordinal: location as u16,
+                    offset: (location >> 16) as u16,
+                    size: flags >> 18,
+                })
+            }
+            (InfoAttribute::MAXREG_COUNT, InfoValue::Half(count), _) => {
+                Some(InfoRecord::MaxRegisterCount(*count))
+            }
+            (InfoAttribute::EXIT_INSTR_OFFSETS, InfoValue::Data(_), offsets) => {
+                Some(InfoRecord::ExitOffsets(offsets.to_vec()))
+            }
+            (InfoAttribute::S2RCTAID_INSTR_OFFSETS, InfoValue::Data(_), offsets) => {
+                Some(InfoRecord::S2RCtaidOffsets(offsets.to_vec()))
+            }
+            (InfoAttribute::CTAIDZ_USED, InfoValue::None, _) => Some(InfoRecord::CtaidzUsed),
+            (InfoAttribute::MAX_THREADS, _, &[x, y, z]) => Some(InfoRecord::MaxThreads([x, y, z])),
+            (InfoAttribute::REQNTID, _, &[x, y, z]) => Some(InfoRecord::RequiredThreads([x, y, z])),
+            (InfoAttribute::CRS_STACK_SIZE, _, &[size]) => Some(InfoRecord::CrsStackSize(size)),
+            _ => None,
+        };
+
+        record.unwrap_or_else(|| InfoRecord::Unknown(self.clone()))
+    }
+
+    pub fn format(&self) -> InfoFormat {
+        match self.value {
+            InfoValue::None => InfoFormat::NVAL,
+            InfoValue::Byte(_) => InfoFormat::BVAL,
+            InfoValue::Half(_) => InfoFormat::HVAL,
+            InfoValue::Data(_) => InfoFormat::SVAL,
+        }
+    }
+
+    fn write(&self, output: &mut Vec<u8>) -> Result<(), CubinWriteError> {
+        output.push(u8::from(self.format()));
+        output.push(u8::from(self.attribute));
+
+        match &self.value {
+            InfoValue::None => output.extend_from_slice(&[0, 0]),
+            InfoValue::Byte(value) => output.extend_from_slice(&[*value, 0]),
+            InfoValue::Half(value) => output.extend_from_slice(&value.to_le_bytes()),
+            InfoValue::Data(data) => {
+                let size = u16::try_from(data.len())
+                    .map_err(|_| CubinWriteError::AttributeTooLarge(self.attribute, data.len()))?;
+
+                output.extend_from_slice(&size.to_le_bytes());
+                output.extend_from_slice(data);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A .nv.info attribute decoded to a readable structure.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum InfoRecord {
+    /// Registers used by the function with the given symbol index.
+    RegisterCount {
+        function: u32,
+        registers: u32,
+    },
+    FrameSize {
+        function: u32,
+        size: u32,
+    },
+    MinStackSize {
+        function: u32,
+        size: u32,
+    },
+    MaxStackSize {
+        function: u32,
+        size: u32,
+    },
+    /// Location of the parameters, section is the symbol index of the constant buffer section.
+    ParameterBank {
+        section: u32,
+        offset: u16,
+        size: u16,
+    },
+    ParameterBankSize(u16),
+    KernelParameter {
+        index: u32,
+        ordinal: u16,
+        offset: u16,
+        size: u32,
+    },
+    MaxRegisterCount(u16),
+    ExitOffsets(Vec<u32>),
+    S2RCtaidOffsets(Vec<u32>),
+    CtaidzUsed,
+    MaxThreads([u32; 3]),
+    RequiredThreads([u32; 3]),
+    CrsStackSize(u32),
+    /// An attribute not known by maxhell, or with an unexpected format.
+    Unknown(InfoEntry),
+}
+
+/// A parameter of a kernel, stored in the constant buffer 0.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct KernelParameter {
+    /// Offset of the parameter from PARAMETER_BASE.
+    pub offset: u32,
+    pub size: u32,
+}
+
+/// Describes how a program is exposed as a CUDA kernel.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Kernel {
+    pub name: String,
+    pub parameters: Vec<KernelParameter>,
+    /// Size of the shared memory used by the kernel, in bytes.
+    pub shared_memory_size: u32,
+}
+
+impl Kernel {
+    pub fn new(name: &str) -> Self {
+        debug_assert!(!name.is_empty() && !name.contains('\0'));
+
+        Kernel {
+            name: String::from(name),
+            parameters: Vec::new(),
+            shared_memory_size: 0,
+        }
+    }
+
+    /// Append a parameter and return its offset from PARAMETER_BASE.
+    pub fn push_parameter(&mut self, size: u32, alignment: u32) -> u32 {
+        debug_assert!(size != 0 && alignment.is_power_of_two());
+
+        let offset = self.parameters_size().next_multiple_of(alignment);
+
+        self.parameters.push(KernelParameter { offset, size });
+
+        offset
+    }
+
+    /// Size of the parameters in the constant buffer 0, in bytes.
+    pub fn parameters_size(&self) -> u32 {
+        self.parameters
+            .last()
+            .map_or(0, |parameter| parameter.offset + parameter.size)
+    }
+}
+
+/// The ELF flags of a cubin for a target, known as EF_CUDA_SM50 and friends by the CUDA tools.
+pub fn elf_flags(target: Target) -> u32 {
+    let sm_version = target.sm_version();
+
+    sm_version
+        | (sm_version << EF_CUDA_VIRTUAL_SM_SHIFT)
+        | EF_CUDA_TEXMODE_UNIFIED
+        | EF_CUDA_64BIT_ADDRESS
+}
+
+/// Offsets of the EXIT instructions of a program, from the start of its code.
+pub fn exit_offsets(program: &Program) -> Vec<u32> {
+    program
+        .instructions()
+        .iter()
+        .enumerate()
+        .filter(|(_, instruction)| Opcode::decode(**instruction) == Some(Opcode::EXIT))
+        .map(|(index, _)| instruction_address(index) as u32)
+        .collect()
+}
+
+/// A section of the cubin, before layout.
+struct Section {
+    name: String,
+    kind: u32,
+    flags: u64,
+    link: u32,
+    info: u32,
+    alignment: u64,
+    entry_size: u64,
+    data: Vec<u8>,
+    /// Size of a SHT_NOBITS section, the size of data is used otherwise.
+    size: u64,
+}
+
+impl Section {
+    fn new(name: &str, kind: u32, flags: u64, alignment: u64, data: Vec<u8>) -> Self {
+        Section {
+            name: String::from(name),
+            kind,
+            flags,
+            link: 0,
+            info: 0,
+            alignment,
+            entry_size: 0,
+            size: data.len() as u64,
+            data,
+        }
+    }
+}
+
+/// A string table being built.
+struct StringTable(Vec<u8>);
+
+impl StringTable {
+    fn new() -> Self {
+        StringTable(vec![0])
+    }
+
+    fn add(&mut self, name: &str) -> u32 {
+        let offset = self.0.len() as u32;
+
+        self.0.extend_from_slice(name.as_bytes());
+        self.0.push(0);
+
+        offset
+    }
+}
+
+fn push_symbol(
+    symbols: &mut Vec<u8>,
+    name: u32,
+    info: u8,
+    other: u8,
+    section: u32,
+    value: u64,
+    size: u64,
+) {
+    symbols.extend_from_slice(&name.to_le_bytes());
+    symbols.push(info);
+    symbols.push(other);
+    symbols.extend_from_slice(&(section as u16).to_le_bytes());
+    symbols.extend_from_slice(&value.to_le_bytes());
+    symbols.extend_from_slice(&size.to_le_bytes());
+}
+
+fn push_program_header(
+    output: &mut Vec<u8>,
+    kind: u32,
+    flags: u32,
+    offset: u64,
+    file_size: u64,
+    memory_size: u64,
+) {
+    output.extend_from_slice(&kind.to_le_bytes());
+    output.extend_from_slice(&flags.to_le_bytes());
+    output.extend_from_slice(&offset.to_le_bytes());
+    // Virtual and physical addresses.
+    output.extend_from_slice(&0u64.to_le_bytes());
+    output.extend_from_slice(&0u64.to_le_bytes());
+    output.extend_from_slice(&file_size.to_le_bytes());
+    output.extend_from_slice(&memory_size.to_le_bytes());
+    output.extend_from_slice(&8u64.to_le_bytes());
+}
+
+/// Entries of the .nv.info section describing a kernel, the section is shared by every kernel.
+fn global_info(kernel_symbol: u32, program_info: &ProgramInfo) -> Vec<InfoEntry> {
+    vec![
+        InfoEntry::words(
+            InfoAttribute::REGCOUNT,
+            &[kernel_symbol, program_info.register_count],
+        ),
+        InfoEntry::words(InfoAttribute::MAX_STACK_SIZE, &[kernel_symbol, 0]),
+        InfoEntry::words(InfoAttribute::MIN_STACK_SIZE, &[kernel_symbol, 0]),
+        InfoEntry::words(InfoAttribute::FRAME_SIZE, &[kernel_symbol, 0]),
+    ]
+}
+
+/// Entries of the .nv.info.<kernel> section.
+fn kernel_info(program: &Program, kernel: &Kernel, constant_symbol: u32) -> Vec<InfoEntry> {
+    /// The constant buffer holding the parameters, as encoded in KPARAM_INFO.
+    const PARAMETER_CBANK: u32 = 0x1f;
+
+    let mut entries = Vec::new();
+
+    // NOTE: nvcc lists the parameters from the last to the first one.
+    for (ordinal, parameter) in kernel.parameters.iter().enumerate().rev() {
+        entries.push(InfoEntry::words(
+            InfoAttribute::KPARAM_INFO,
+            &[
+                0,
+                ordinal as u32 | (parameter.offset << 16),
+                (parameter.size << 18) | (PARAMETER_CBANK << 12),
+            ],
+        ));
+    }
+
+    let parameters_size = kernel.parameters_size();
+
+    entries.push(InfoEntry::words(
+        InfoAttribute::PARAM_CBANK,
+        &[constant_symbol, PARAMETER_BASE | (parameters_size << 16)],
+    ));
+    entries.push(InfoEntry::new(
+        InfoAttribute::CBANK_PARAM_SIZE,
+        InfoValue::Half(parameters_size as u16),
+    ));
+    entries.push(InfoEntry::new(
+        InfoAttribute::MAXREG_COUNT,
+        InfoValue::Half(0xff),
+    ));
+
+    let exit_offsets = exit_offsets(program);
+
+    if !exit_offsets.is_empty() {
+        entries.push(InfoEntry::words(
+            InfoAttribute::EXIT_INSTR_OFFSETS,
+            &exit_offsets,
+        ));
+    }
+
+    entries
+}
+
+fn info_data(entries: &[InfoEntry]) -> Result<Vec<u8>, CubinWriteError> {
+    let mut data = Vec::new();
+
+    for entry in entries {
+        entry.write(&mut data)?;
+    }
+
+    Ok(data)
+}
+
+/// Write a cubin exposing every program as a kernel.
+///
+/// The programs must all be built for the same target.
+pub fn write_cubin(kernels: &[(&Program, &Kernel)]) -> Result<Vec<u8>, CubinWriteError> {
+    let target = match kernels.first() {
+        Some((program, _)) => program.target(),
+        None => return Err(CubinWriteError::NoKernel),
+    };
+    let layout = Layout {
+        kernel_count: kernels.len() as u32,
+    };
+
+    let mut strings = StringTable::new();
+    let mut symbols = vec![0; SYMBOL_SIZE];
+    let mut global_entries = Vec::new();
+    let mut info_sections = Vec::with_capacity(kernels.len());
+    let mut constant_sections = Vec::with_capacity(kernels.len());
+    let mut text_sections = Vec::with_capacity(kernels.len());
+    let mut shared_sections = Vec::with_capacity(kernels.len());
+    let mut kernel_symbols = Vec::with_capacity(kernels.len());
+
+    for (index, (program, kernel)) in kernels.iter().enumerate() {
+        debug_assert_eq!(program.target(), target);
+        debug_assert!(kernel.parameters_size() <= 0x1000);
+
+        let index = index as u32;
+        let code = program.code()?;
+        let program_info = ProgramInfo::from_program(program);
+        let kernel_symbol = layout.kernel_symbol(index);
+
+        let text_name = format!(".text.{}", kernel.name);
+        let constant_name = format!(".nv.constant0.{}", kernel.name);
+        let shared_name = format!(".nv.shared.{}", kernel.name);
+
+        for (symbol, name, section) in [
+            (
+                layout.text_symbol(index),
+                &text_name,
+                layout.text_index(index),
+            ),
+            (
+                layout.constant_symbol(index),
+                &constant_name,
+                layout.constant_index(index),
+            ),
+            (
+                layout.shared_symbol(index),
+                &shared_name,
+                layout.shared_index(index),
+            ),
+        ] {
+            debug_assert_eq!(symbols.len(), symbol as usize * SYMBOL_SIZE);
+
+            let name = strings.add(name);
+
+            push_symbol(
+                &mut symbols,
+                name,
+                STT_SECTION | (STB_LOCAL << 4),
+                0,
+                section,
+                0,
+                0,
+            );
+        }
+
+        // NOTE: the kernel symbols are global, they are written once every local symbol is.
+        kernel_symbols.push((strings.add(&kernel.name), code.len() as u64));
+        global_entries.extend(global_info(kernel_symbol, &program_info));
+
+        let mut info_kernel = Section::new(
+            &format!(".nv.info.{}", kernel.name),
+            SHT_CUDA_INFO,
+            SHF_INFO_LINK,
+            4,
+            info_data(&kernel_info(program, kernel, layout.constant_symbol(index)))?,
+        );
+        info_kernel.link = SYMTAB_INDEX;
+        info_kernel.info = layout.text_index(index);
+
+        let mut constant = Section::new(
+            &constant_name,
+            SHT_PROGBITS,
+            SHF_ALLOC,
+            4,
+            vec![0; (PARAMETER_BASE + kernel.parameters_size()) as usize],
+        );
+        constant.info = layout.text_index(index);
+
+        let mut text = Section::new(
+            &text_name,
+            SHT_PROGBITS,
+            SHF_ALLOC
+                | SHF_EXECINSTR
+                | (u64::from(program_info.barrier_count()) << SHF_BARRIERS_SHIFT),
+            0x80,
+            code,
+        );
+        text.link = SYMTAB_INDEX;
+        text.info = kernel_symbol | (program_info.register_count << SHI_REGISTERS_SHIFT);
+
+        let mut shared = Section::new(
+            &shared_name,
+            SHT_NOBITS,
+            SHF_WRITE | SHF_ALLOC,
+            0x10,
+            Vec::new(),
+        );
+        shared.info = layout.text_index(index);
+        shared.size = u64::from(kernel.shared_memory_size);
+
+        info_sections.push(info_kernel);
+        constant_sections.push(constant);
+        text_sections.push(text);
+        shared_sections.push(shared);
+    }
+
+    for (index, (name, size)) in kernel_symbols.into_iter().enumerate() {
+        push_symbol(
+            &mut symbols,
+            name,
+            STT_FUNC | (STB_GLOBAL << 4),
+            STO_CUDA_ENTRY,
+            layout.text_index(index as u32),
+            0,
+            size,
+        );
+    }
+
+    debug_assert_eq!(
+        symbols.len(),
+        layout.kernel_symbol(layout.kernel_count) as usize * SYMBOL_SIZE
+    );
+
+    let mut symtab = Section::new(".symtab", SHT_SYMTAB, 0, 8, symbols);
+    symtab.link = STRTAB_INDEX;
+    symtab.info = layout.kernel_symbol(0);
+    symtab.entry_size = SYMBOL_SIZE as u64;
+
+    let mut info = Section::new(".nv.info", SHT_CUDA_INFO, 0, 4, info_data(&global_entries)?);
+    info.link = SYMTAB_INDEX;
+
+    let strtab = Section::new(".strtab", SHT_STRTAB, 0, 1, strings.0);
+
+    let mut sections = vec![strtab, symtab, info];
+    sections.extend(info_sections);
+    sections.extend(constant_sections);
+    sections.extend(text_sections);
+    sections.extend(shared_sections);
+
+    let mut section_names = StringTable::new();
+    let shstrtab_name = section_names.add(".shstrtab");
+    let name_offsets: Vec<u32> = sections
+        .iter()
+        .map(|section| section_names.add(&section.name))
+        .collect();
+
+    sections.insert(
+        0,
+        Section::new(".shstrtab", SHT_STRTAB, 0, 1, section_names.0),
+    );
+
+    let name_offsets: Vec<u32> = std::iter::once(shstrtab_name).chain(name_offsets).collect();
+
+    // NOTE: sections are indexed from 1, the null section is only written in the header table.
+    let section_offset_index = |index: u32| index as usize - 1;
+
+    debug_assert_eq!(
+        sections[section_offset_index(u32::from(SHSTRTAB_INDEX))].name,
+        ".shstrtab"
+    );
+    debug_assert_eq!(
+        sections.len(),
+        section_offset_index(layout.shared_index(layout.kernel_count))
+    );
+
+    // Layout the file: headers, section data then the section header table.
+    const PROGRAM_HEADER_COUNT: usize = 3;
+
+    let mut output = vec![0; ELF_HEADER_SIZE + PROGRAM_HEADER_COUNT * PROGRAM_HEADER_SIZE];
+    let mut offsets = Vec::with_capacity(sections.len());
+
+    for section in &sections {
+        output.resize(output.len().next_multiple_of(section.alignment as usize), 0);
+        offsets.push(output.len() as u64);
+
+        if section.kind != SHT_NOBITS {
+            output.extend_from_slice(&section.data);
+        }
+    }
+
+    output.resize(output.len().next_multiple_of(8), 0);
+
+    let section_header_offset = output.len() as u64;
+
+    // The null section.
+    output.extend_from_slice(&[0; SECTION_HEADER_SIZE]);
+
+    for ((section, offset), name) in sections.iter().zip(&offsets).zip(&name_offsets) {
+        output.extend_from_slice(&name.to_le_bytes());
+        output.extend_from_slice(&section.kind.to_le_bytes());
+        output.extend_from_slice(&section.flags.to_le_bytes());
+        // Address.
+        output.extend_from_slice(&0u64.to_le_bytes());
+        output.extend_from_slice(&offset.to_le_bytes());
+        output.extend_from_slice(&section.size.to_le_bytes());
+        output.extend_from_slice(&section.link.to_le_bytes());
+        output.extend_from_slice(&section.info.to_le_bytes());
+        output.extend_from_slice(&section.alignment.to_le_bytes());
+        output.extend_from_slice(&section.entry_size.to_le_bytes());
+    }
+
+    // NOTE: the constant buffers directly precede the code, all of them are loaded by the same segment.
+    let last_kernel = layout.kernel_count - 1;
+    let last_text = section_offset_index(layout.text_index(last_kernel));
+    let constant_offset = offsets[section_offset_index(layout.constant_index(0))];
+    let code_end = offsets[last_text] + sections[last_text].size;
+    let shared_offset = offsets[section_offset_index(layout.shared_index(0))];
+    let shared_size: u64 = kernels
+        .iter()
+        .map(|(_, kernel)| u64::from(kernel.shared_memory_size).next_multiple_of(0x10))
+        .sum();
+
+    let mut program_headers = Vec::with_capacity(PROGRAM_HEADER_COUNT * PROGRAM_HEADER_SIZE);
+    let program_headers_size = (PROGRAM_HEADER_COUNT * PROGRAM_HEADER_SIZE) as u64;
+    let loaded_size = code_end - constant_offset;
+
+    push_program_header(
+        &mut program_headers,
+        PT_PHDR,
+        PF_R,
+        ELF_HEADER_SIZE as u64,
+        program_headers_size,
+        program_headers_size,
+    );
+    push_program_header(
+        &mut program_headers,
+        PT_LOAD,
+        PF_R | PF_X,
+        constant_offset,
+        loaded_size,
+        loaded_size,
+    );
+    push_program_header(
+        &mut program_headers,
+        PT_LOAD,
+        PF_R | PF_W,
+        shared_offset,
+        0,
+        shared_size,
+    );
+
+    output[ELF_HEADER_SIZE..ELF_HEADER_SIZE + program_headers.len()]
+        .copy_from_slice(&program_headers);
+
+    let mut header = Vec::with_capacity(ELF_HEADER_SIZE);
+
+    header.extend_from_slice(b"\x7fELF");
+    header.extend_from_slice(&[
+        ELFCLASS64,
+        ELFDATA2LSB,
+        EV_CURRENT,
+        ELFOSABI_CUDA,
+        ELFABIVERSION_CUDA,
+    ]);
+    header.resize(0x10, 0);
+    header.extend_from_slice(&ET_EXEC.to_le_bytes());
+    header.extend_from_slice(&EM_CUDA.to_le_bytes());
+    header.extend_from_slice(&u32::from(EV_CURRENT).to_le_bytes());
+    // Entry point.
+    header.extend_from_slice(&0u64.to_le_bytes());
+    header.extend_from_slice(&(ELF_HEADER_SIZE as u64).to_le_bytes());
+    header.extend_from_slice(&section_header_offset.to_le_bytes());
+    header.extend_from_slice(&elf_flags(target).to_le_bytes());
+    header.extend_from_slice(&(ELF_HEADER_SIZE as u16).to_le_bytes());
+    header.extend_from_slice(&(PROGRAM_HEADER_SIZE as u16).to_le_bytes());
+    header.extend_from_slice(&(PROGRAM_HEADER_COUNT as u16).to_le_bytes());
+    header.extend_from_slice(&(SECTION_HEADER_SIZE as u16).to_le_bytes());
+    header.extend_from_slice(&(sections.len() as u16 + 1).to_le_bytes());
+    header.extend_from_slice(&SHSTRTAB_INDEX.to_le_bytes());
+
+    debug_assert_eq!(header.len(), ELF_HEADER_SIZE);
+
+    output[..ELF_HEADER_SIZE].copy_from_slice(&header);
+
+    Ok(output)
+}
@@ -0,0 +1,291 @@
+use std::convert::TryInto;
+use std::error::Error;
+use std::fmt;
+
+use super::*;
+
+/// Error returned when reading a cubin.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum CubinError {
+    /// The data doesn't start with the ELF magic.
+    NotElf,
+    /// Only little-endian ELF64 files made for CUDA are supported.
+    UnsupportedFormat,
+    /// The SM version of the cubin isn't supported by maxhell.
+    UnsupportedTarget(u32),
+    /// A structure at the given offset goes past the end of the file.
+    Truncated(u64),
+    /// The name at the given offset of a string table isn't valid.
+    InvalidName(u64),
+    /// The .nv.info section is malformed at the given offset of the section.
+    InvalidInfo(u64),
+}
+
+impl fmt::Display for CubinError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CubinError::NotElf => write!(f, "not an ELF file"),
+            CubinError::UnsupportedFormat => {
+                write!(f, "not a little-endian 64-bit CUDA ELF file")
+            }
+            CubinError::UnsupportedTarget(sm_version) => {
+                write!(f, "sm_{} is not supported", sm_version)
+            }
+            CubinError::Truncated(offset) => write!(f, "truncated file at 0x{:x}", offset),
+            CubinError::InvalidName(offset) => write!(f, "invalid name at 0x{:x}", offset),
+            CubinError::InvalidInfo(offset) => {
+                write!(f, "invalid .nv.info attribute at 0x{:x}", offset)
+            }
+        }
+    }
+}
+
+impl Error for CubinError {}
+
+/// A kernel found in a cubin.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct CubinKernel {
+    pub name: String,
+    /// Index of the symbol of the kernel, used by the .nv.info attributes.
+    pub symbol: u32,
+    /// The content of the .text section, scheduling control words included.
+    pub code: Vec<u8>,
+    pub register_count: u32,
+    pub barrier_count: u32,
+    pub shared_memory_size: u64,
+    /// The entries of the .nv.info.<kernel> section.
+    pub info: Vec<InfoEntry>,
+}
+
+impl CubinKernel {
+    /// The code of the kernel as a program that can be disassembled or emulated.
+    pub fn program(&self, target: Target) -> Program {
+        Program::from_code(target, &self.code)
+    }
+}
+
+/// A cubin read from an ELF file.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Cubin {
+    pub target: Target,
+    /// The entries of the .nv.info section.
+    pub info: Vec<InfoEntry>,
+    pub kernels: Vec<CubinKernel>,
+}
+
+impl Cubin {
+    pub fn kernel(&self, name: &str) -> Option<&CubinKernel> {
+        self.kernels.iter().find(|kernel| kernel.name == name)
+    }
+
+    /// The records describing a kernel, from both .nv.info and .nv.info.<kernel>.
+    pub fn kernel_records(&self, kernel: &CubinKernel) -> Vec<InfoRecord> {
+        let global = self.info.iter().map(InfoEntry::record).filter(|record| {
+            let function = match record {
+                InfoRecord::RegisterCount { function, .. }
+                | InfoRecord::FrameSize { function, .. }
+                | InfoRecord::MinStackSize { function, .. }
+                | InfoRecord::MaxStackSize { function, .. } => *function,
+                _ => return false,
+            };
+
+            function == kernel.symbol
+        });
+
+        global
+            .chain(kernel.info.iter().map(InfoEntry::record))
+            .collect()
+    }
+}
+
+/// A section header of an ELF file.
+struct SectionHeader {
+    name: u32,
+    kind: u32,
+    flags: u64,
+    offset: u64,
+    size: u64,
+    info: u32,
+}
+
+/// Get size bytes at an offset of the data.
+fn slice(data: &[u8], offset: u64, size: u64) -> Result<&[u8], CubinError> {
+    offset
+        .checked_add(size)
+        .filter(|end| *end <= data.len() as u64)
+        .map(|end| &data[offset as usize..end as usize])
+        .ok_or(CubinError::Truncated(offset))
+}
+
+fn read_u16(data: &[u8], offset: u64) -> Result<u16, CubinError> {
+    Ok(u16::from_le_bytes(
+        slice(data, offset, 2)?.try_into().unwrap(),
+    ))
+}
+
+fn read_u32(data: &[u8], offset: u64) -> Result<u32, CubinError> {
+    Ok(u32::from_le_bytes(
+        slice(data, offset, 4)?.try_into().unwrap(),
+    ))
+}
+
+fn read_u64(data: &[u8], offset: u64) -> Result<u64, CubinError> {
+    Ok(u64::from_le_bytes(
+        slice(data, offset, 8)?.try_into().unwrap(),
+    ))
+}
+
+/// Read a NUL terminated string from a string table.
+fn read_name(table: &[u8], offset: u32) -> Result<&str, CubinError> {
+    let error = CubinError::InvalidName(u64::from(offset));
+    let start = table.get(offset as usize..).ok_or_else(|| error.clone())?;
+    let end = start
+        .iter()
+        .position(|c| *c == 0)
+        .ok_or_else(|| error.clone())?;
+
+    std::str::from_utf8(&start[..end]).map_err(|_| error)
+}
+
+/// Read the entries of a .nv.info section.
+pub fn read_info(data: &[u8]) -> Result<Vec<InfoEntry>, CubinError> {
+    let mut entries = Vec::new();
+    let mut offset = 0;
+
+    while offset < data.len() as u64 {
+        let header = slice(data, offset, 4).map_err(|_| CubinError::InvalidInfo(offset))?;
+        let format = InfoFormat::from(header[0]);
+        let attribute = InfoAttribute::from(header[1]);
+
+        let value = match format {
+            InfoFormat::NVAL => InfoValue::None,
+            InfoFormat::BVAL => InfoValue::Byte(header[2]),
+            InfoFormat::HVAL => InfoValue::Half(u16::from_le_bytes([header[2], header[3]])),
+            InfoFormat::SVAL => {
+                let size = u64::from(u16::from_le_bytes([header[2], header[3]]));
+                let value =
+                    slice(data, offset + 4, size).map_err(|_| CubinError::InvalidInfo(offset))?;
+
+                offset += size;
+
+                InfoValue::Data(value.to_vec())
+            }
+            _ => return Err(CubinError::InvalidInfo(offset)),
+        };
+
+        entries.push(InfoEntry::new(attribute, value));
+        offset += 4;
+    }
+
+    Ok(entries)
+}
+
+/// Read a cubin and find its kernels.
+pub fn read_cubin(data: &[u8]) -> Result<Cubin, CubinError> {
+    if !data.starts_with(b"\x7fELF") {
+        return Err(CubinError::NotElf);
+    }
+
+    let identification = slice(data, 0, 0x10)?;
+
+    if identification[4] != ELFCLASS64
+        || identification[5] != ELFDATA2LSB
+        || read_u16(data, 0x12)? != EM_CUDA
+    {
+        return Err(CubinError::UnsupportedFormat);
+    }
+
+    let sm_version = read_u32(data, 0x30)? & 0xff;
+    let target =
+        Target::from_sm_version(sm_version).ok_or(CubinError::UnsupportedTarget(sm_version))?;
+
+    let section_header_offset = read_u64(data, 0x28)?;
+    let section_header_size = u64::from(read_u16(data, 0x3a)?);
+    let section_count = u64::from(read_u16(data, 0x3c)?);
+    let names_index = u64::from(read_u16(data, 0x3e)?);
+
+    let headers = (0..section_count)
+        .map(|index| {
+            let offset = section_header_offset + index * section_header_size;
+
+            Ok(SectionHeader {
+                name: read_u32(data, offset)?,
+                kind: read_u32(data, offset + 0x4)?,
+                flags: read_u64(data, offset + 0x8)?,
+                offset: read_u64(data, offset + 0x18)?,
+                size: read_u64(data, offset + 0x20)?,
+                info: read_u32(data, offset + 0x2c)?,
+            })
+        })
+        .collect::<Result<Vec<_>, CubinError>>()?;
+
+    let content = |header: &SectionHeader| {
+        if header.kind == SHT_NOBITS {
+            Ok(&data[0..0])
+        } else {
+            slice(data, header.offset, header.size)
+        }
+    };
+
+    let names = match headers.get(names_index as usize) {
+        Some(header) => content(header)?,
+        None => return Err(CubinError::UnsupportedFormat),
+    };
+
+    let section_names = headers
+        .iter()
+        .map(|header| read_name(names, header.name))
+        .collect::<Result<Vec<_>, CubinError>>()?;
+
+    let find_section = |name: &str| {
+        section_names
+            .iter()
+            .position(|section_name| *section_name == name)
+            .map(|index| &headers[index])
+    };
+
+    let info = match find_section(".nv.info") {
+        Some(header) => read_info(content(header)?)?,
+        None => Vec::new(),
+    };
+
+    let mut kernels = Vec::new();
+
+    for (index, (header, section_name)) in headers.iter().zip(&section_names).enumerate() {
+        let name = match section_name.strip_prefix(".text.") {
+            Some(name) if header.flags & SHF_EXECINSTR != 0 => name,
+            _ => continue,
+        };
+
+        // NOTE: the info section is found by link, nvcc always names it after the kernel.
+        let info_header = headers.iter().find(|info_header| {
+            info_header.kind == SHT_CUDA_INFO
+                && info_header.flags & SHF_INFO_LINK != 0
+                && info_header.info as usize == index
+        });
+
+        let info = match info_header {
+            Some(info_header) => read_info(content(info_header)?)?,
+            None => Vec::new(),
+        };
+
+        let shared_memory_size =
+            find_section(&format!(".nv.shared.{}", name)).map_or(0, |header| header.size);
+
+        kernels.push(CubinKernel {
+            name: String::from(name),
+            symbol: header.info & ((1 << SHI_REGISTERS_SHIFT) - 1),
+            code: content(header)?.to_vec(),
+            register_count: header.info >> SHI_REGISTERS_SHIFT,
+            barrier_count: ((header.flags >> SHF_BARRIERS_SHIFT) & 0x1f) as u32,
+            shared_memory_size,
+            info,
+        });
+    }
+
+    Ok(Cubin {
+        target,
+        info,
+        kernels,
+    })
+}
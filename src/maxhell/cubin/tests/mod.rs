@@ -0,0 +1,278 @@
+use std::convert::TryInto;
+
+use super::*;
+use crate::maxhell::encoder;
+
+const TARGET: Target = Target::SM52;
+
+const RZ: u8 = 0xFF;
+
+fn program(instructions: &[u64]) -> Program {
+    let mut program = Program::new(TARGET);
+
+    for instruction in instructions {
+        program.push(*instruction);
+    }
+
+    program
+}
+
+fn exit() -> u64 {
+    encoder::encode_exit(TARGET, 7, false, ControlCode::TRUE, false).unwrap()
+}
+
+fn read_u16(data: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes(data[offset..offset + 2].try_into().unwrap())
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
+
+/// Name, header offset and content of every section of a cubin.
+fn sections(cubin: &[u8]) -> Vec<(String, usize, &[u8])> {
+    let section_headers = read_u64(cubin, 0x28) as usize;
+    let count = read_u16(cubin, 0x3c) as usize;
+    let names_index = read_u16(cubin, 0x3e) as usize;
+
+    let header = |index: usize| section_headers + index * SECTION_HEADER_SIZE;
+    let content = |index: usize| {
+        let offset = read_u64(cubin, header(index) + 0x18) as usize;
+        let size = read_u64(cubin, header(index) + 0x20) as usize;
+
+        if read_u32(cubin, header(index) + 4) == SHT_NOBITS {
+            &cubin[offset..offset]
+        } else {
+            &cubin[offset..offset + size]
+        }
+    };
+    let names = content(names_index);
+
+    (1..count)
+        .map(|index| {
+            let name_offset = read_u32(cubin, header(index)) as usize;
+            let name_end = names[name_offset..].iter().position(|c| *c == 0).unwrap();
+            let name = String::from_utf8(names[name_offset..name_offset + name_end].to_vec());
+
+            (name.unwrap(), header(index), content(index))
+        })
+        .collect()
+}
+
+#[test]
+fn writes_elf_header() {
+    let cubin = write_cubin(&[(&program(&[exit()]), &Kernel::new("test"))]).unwrap();
+
+    assert_eq!(&cubin[..4], b"\x7fELF");
+    assert_eq!(cubin[7], ELFOSABI_CUDA);
+    assert_eq!(read_u16(&cubin, 0x12), EM_CUDA);
+    assert_eq!(read_u32(&cubin, 0x30), 0x0034_0534);
+    assert_eq!(elf_flags(Target::SM50), 0x0032_0532);
+}
+
+#[test]
+fn writes_kernel_sections() {
+    let program = program(&[
+        encoder::encode_ald(
+            TARGET,
+            7,
+            false,
+            4,
+            RZ,
+            RZ,
+            false,
+            false,
+            AtributeLoadMode::M128,
+            0x80,
+        )
+        .unwrap(),
+        exit(),
+        encoder::encode_nop(TARGET, false, 7, false, 0, ControlCode::TRUE).unwrap(),
+        exit(),
+    ]);
+
+    let mut kernel = Kernel::new("kernel");
+    kernel.push_parameter(8, 8);
+    kernel.shared_memory_size = 0x100;
+
+    let cubin = write_cubin(&[(&program, &kernel)]).unwrap();
+    let sections = sections(&cubin);
+    let section = |name: &str| {
+        sections
+            .iter()
+            .find(|(section_name, _, _)| section_name == name)
+            .unwrap_or_else(|| panic!("Cannot find section {}", name))
+    };
+
+    let (_, text_header, text) = section(".text.kernel");
+    assert_eq!(*text, program.code().unwrap().as_slice());
+    assert_eq!(read_u32(&cubin, text_header + 0x2c) >> 24, 8);
+
+    let (_, _, constant) = section(".nv.constant0.kernel");
+    assert_eq!(constant.len(), 0x148);
+
+    let (_, shared_header, _) = section(".nv.shared.kernel");
+    assert_eq!(read_u64(&cubin, shared_header + 0x20), 0x100);
+
+    let (_, _, info) = section(".nv.info");
+    // REGCOUNT of the kernel symbol.
+    assert_eq!(info[..12], [4, 0x2f, 8, 0, 4, 0, 0, 0, 8, 0, 0, 0]);
+
+    let (_, _, kernel_info) = section(".nv.info.kernel");
+    // EXIT_INSTR_OFFSETS comes last.
+    assert_eq!(
+        kernel_info[kernel_info.len() - 12..],
+        [4, 0x1c, 8, 0, 0x10, 0, 0, 0, 0x28, 0, 0, 0]
+    );
+}
+
+#[test]
+fn lays_out_parameters() {
+    let mut kernel = Kernel::new("kernel");
+
+    assert_eq!(kernel.push_parameter(4, 4), 0);
+    assert_eq!(kernel.push_parameter(8, 8), 8);
+    assert_eq!(kernel.push_parameter(2, 2), 16);
+    assert_eq!(kernel.parameters_size(), 18);
+}
+
+#[test]
+fn reads_written_cubin() {
+    let program = program(&[
+        encoder::encode_nop(TARGET, false, 7, false, 0, ControlCode::TRUE).unwrap(),
+        exit(),
+    ]);
+
+    let mut kernel = Kernel::new("kernel");
+    kernel.push_parameter(8, 8);
+    kernel.push_parameter(4, 4);
+    kernel.shared_memory_size = 0x40;
+
+    let cubin = read_cubin(&write_cubin(&[(&program, &kernel)]).unwrap()).unwrap();
+
+    assert_eq!(cubin.target, TARGET);
+    assert_eq!(cubin.kernels.len(), 1);
+
+    let read_kernel = cubin.kernel("kernel").unwrap();
+
+    assert_eq!(read_kernel.shared_memory_size, 0x40);
+    assert_eq!(
+        read_kernel.register_count,
+        ProgramInfo::from_program(&program).register_count
+    );
+    // The code is padded with a NOP.
+    assert_eq!(
+        read_kernel.program(TARGET).instructions()[..2],
+        *program.instructions()
+    );
+
+    let records = cubin.kernel_records(read_kernel);
+
+    assert!(records.contains(&InfoRecord::RegisterCount {
+        function: read_kernel.symbol,
+        registers: 0,
+    }));
+    assert!(records.contains(&InfoRecord::KernelParameter {
+        index: 0,
+        ordinal: 1,
+        offset: 8,
+        size: 4,
+    }));
+    assert!(records.contains(&InfoRecord::ParameterBank {
+        section: Layout { kernel_count: 1 }.constant_symbol(0),
+        offset: PARAMETER_BASE as u16,
+        size: 12,
+    }));
+    assert!(records.contains(&InfoRecord::ExitOffsets(vec![0x10])));
+    assert!(!records
+        .iter()
+        .any(|record| matches!(record, InfoRecord::Unknown(_))));
+}
+
+#[test]
+fn writes_multiple_kernels() {
+    let first = program(&[exit()]);
+    let second = program(&[encoder::encode_get_lmembase(TARGET, 9).unwrap(), exit()]);
+
+    let mut second_kernel = Kernel::new("second");
+    second_kernel.shared_memory_size = 0x20;
+
+    let cubin = write_cubin(&[(&first, &Kernel::new("first")), (&second, &second_kernel)]).unwrap();
+    let names: Vec<String> = sections(&cubin)
+        .into_iter()
+        .map(|(name, _, _)| name)
+        .collect();
+
+    assert_eq!(
+        names[4..],
+        [
+            ".nv.info.first",
+            ".nv.info.second",
+            ".nv.constant0.first",
+            ".nv.constant0.second",
+            ".text.first",
+            ".text.second",
+            ".nv.shared.first",
+            ".nv.shared.second",
+        ]
+    );
+
+    let cubin = read_cubin(&cubin).unwrap();
+
+    assert_eq!(cubin.kernels.len(), 2);
+
+    for (name, program, registers) in [("first", &first, 0), ("second", &second, 10)] {
+        let kernel = cubin.kernel(name).unwrap();
+
+        assert_eq!(kernel.code, program.code().unwrap());
+        assert_eq!(kernel.register_count, registers);
+        assert!(cubin
+            .kernel_records(kernel)
+            .contains(&InfoRecord::RegisterCount {
+                function: kernel.symbol,
+                registers,
+            }));
+    }
+
+    assert_eq!(cubin.kernel("second").unwrap().shared_memory_size, 0x20);
+}
+
+#[test]
+fn rejects_invalid_cubins() {
+    let cubin = write_cubin(&[(&program(&[exit()]), &Kernel::new("kernel"))]).unwrap();
+
+    assert_eq!(read_cubin(b"not an elf"), Err(CubinError::NotElf));
+    assert!(matches!(
+        read_cubin(&cubin[..0x100]),
+        Err(CubinError::Truncated(_))
+    ));
+
+    let mut sm_70 = cubin.clone();
+    sm_70[0x30] = 70;
+    assert_eq!(read_cubin(&sm_70), Err(CubinError::UnsupportedTarget(70)));
+
+    assert_eq!(
+        read_info(&[4, 0x2f, 8, 0, 1, 0]),
+        Err(CubinError::InvalidInfo(0))
+    );
+}
+
+#[test]
+fn rejects_unwritable_cubins() {
+    assert_eq!(write_cubin(&[]), Err(CubinWriteError::NoKernel));
+
+    // NOTE: every EXIT adds 4 bytes to EXIT_INSTR_OFFSETS, which has a 16-bit size.
+    let exits = program(&[exit(); 0x4000]);
+
+    assert_eq!(
+        write_cubin(&[(&exits, &Kernel::new("exits"))]),
+        Err(CubinWriteError::AttributeTooLarge(
+            InfoAttribute::EXIT_INSTR_OFFSETS,
+            0x10000
+        ))
+    );
+}
@@ -0,0 +1,111 @@
+use std::fmt;
+
+use super::*;
+use crate::maxhell::util::{FieldDefinition, FieldKind};
+
+/// Layouts of the operands shared by the encoders.
+pub const SHARED_LAYOUTS: &[(&str, &[FieldDefinition])] = &[
+    ("Imm16Data", Imm16Data::FIELDS),
+    ("Imm8Data", Imm8Data::FIELDS),
+    ("Imm32Data", Imm32Data::FIELDS),
+    ("Operand0Data", Operand0Data::FIELDS),
+    ("Operand1Data", Operand1Data::FIELDS),
+    ("Operand2Data", Operand2Data::FIELDS),
+    ("Operand3Data", Operand3Data::FIELDS),
+    ("SourcePredicateData", SourcePredicateData::FIELDS),
+    ("DestinationPredicateData", DestinationPredicateData::FIELDS),
+    ("HalfOperandAData", HalfOperandAData::FIELDS),
+    ("HalfOperandBData", HalfOperandBData::FIELDS),
+    ("HalfImmediateData", HalfImmediateData::FIELDS),
+];
+
+/// An inconsistency found in the instruction definitions.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum DefinitionIssue {
+    /// Two fields of a layout share some bits.
+    OverlappingFields(Opcode, &'static str, &'static str),
+    /// A field of a layout uses some of the opcode bits.
+    OpcodeCollision(Opcode, &'static str),
+    /// A field doesn't have the same bit range as the shared layout used to encode it.
+    WidthMismatch(Opcode, &'static str, &'static str),
+}
+
+impl fmt::Display for DefinitionIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DefinitionIssue::OverlappingFields(opcode, first, second) => {
+                write!(f, "{:?}: {} overlaps {}", opcode, first, second)
+            }
+            DefinitionIssue::OpcodeCollision(opcode, field) => {
+                write!(f, "{:?}: {} overlaps the opcode bits", opcode, field)
+            }
+            DefinitionIssue::WidthMismatch(opcode, field, shared_layout) => {
+                write!(f, "{:?}: {} doesn't match {}", opcode, field, shared_layout)
+            }
+        }
+    }
+}
+
+/// Check if a field of an instruction is encoded by a field of a shared layout.
+fn is_encoded_by(field: &FieldDefinition, shared_field: &FieldDefinition) -> bool {
+    if shared_field.name == "operand" {
+        // NOTE: the operand helpers are used for every register starting at their position.
+        field.kind() == FieldKind::Register && field.lsb == shared_field.lsb
+    } else {
+        field.name == shared_field.name
+    }
+}
+
+/// Walk every instruction definition and report the inconsistencies found.
+pub fn check_definitions() -> Vec<DefinitionIssue> {
+    let mut issues = Vec::new();
+
+    for &opcode in Opcode::ALL {
+        let fields = opcode.fields();
+
+        for (i, field) in fields.iter().enumerate() {
+            for other in &fields[i + 1..] {
+                if field.mask() & other.mask() != 0 {
+                    issues.push(DefinitionIssue::OverlappingFields(
+                        opcode, field.name, other.name,
+                    ));
+                }
+            }
+
+            if field.mask() & opcode.mask() != 0 {
+                issues.push(DefinitionIssue::OpcodeCollision(opcode, field.name));
+            }
+
+            for (shared_name, shared_fields) in SHARED_LAYOUTS {
+                for shared_field in shared_fields.iter() {
+                    if is_encoded_by(field, shared_field)
+                        && (field.msb, field.lsb) != (shared_field.msb, shared_field.lsb)
+                    {
+                        issues.push(DefinitionIssue::WidthMismatch(
+                            opcode,
+                            field.name,
+                            shared_name,
+                        ));
+                    }
+                }
+            }
+        }
+    }
+
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn definitions_are_consistent() {
+        let issues: Vec<String> = check_definitions()
+            .iter()
+            .map(|issue| issue.to_string())
+            .collect();
+
+        assert!(issues.is_empty(), "{:#?}", issues);
+    }
+}
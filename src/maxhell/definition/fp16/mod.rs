@@ -0,0 +1,311 @@
+use half::f16;
+
+use super::PredicateOperation;
+use crate::{enum_with_val, instruction_layout};
+
+instruction_layout! {
+    pub struct HalfOperandAData {
+        pub negate_a, set_negate_a: 43;
+        pub absolute_a, set_absolute_a: 44;
+        pub u8, from into HalfSwizzle, a_swizzle, set_a_swizzle: 48, 47;
+    }
+}
+
+instruction_layout! {
+    pub struct HalfOperandBData {
+        pub u8, from into HalfSwizzle, b_swizzle, set_b_swizzle: 29, 28;
+        pub absolute_b, set_absolute_b: 30;
+        pub negate_b, set_negate_b: 31;
+    }
+}
+
+instruction_layout! {
+    pub struct HalfImmediateData {
+        pub u16, immediate_low, set_immediate_low: 28, 20;
+        pub negate_immediate_low, set_negate_immediate_low: 29;
+        pub u16, immediate_high, set_immediate_high: 38, 30;
+        pub negate_immediate_high, set_negate_immediate_high: 56;
+    }
+}
+
+instruction_layout! {
+    pub struct Hadd2Instruction {
+        pub u8, destination_register, set_destination_register: 7, 0;
+        pub u8, source_register_a, set_source_register_a: 15, 8;
+        source_predicate;
+        pub u8, source_register_b, set_source_register_b: 27, 20;
+        pub u8, from into HalfSwizzle, b_swizzle, set_b_swizzle: 29, 28;
+        pub absolute_b, set_absolute_b: 30;
+        pub negate_b, set_negate_b: 31;
+        pub sat_flag, set_sat_flag: 32;
+        pub ftz_flag, set_ftz_flag: 39;
+        pub negate_a, set_negate_a: 43;
+        pub absolute_a, set_absolute_a: 44;
+        pub u8, from into HalfSwizzle, a_swizzle, set_a_swizzle: 48, 47;
+        pub u8, from into HalfMerge, merge, set_merge: 50, 49;
+    }
+}
+
+instruction_layout! {
+    pub struct Hadd2ImmInstruction {
+        pub u8, destination_register, set_destination_register: 7, 0;
+        pub u8, source_register_a, set_source_register_a: 15, 8;
+        source_predicate;
+        pub u16, immediate_low, set_immediate_low: 28, 20;
+        pub negate_immediate_low, set_negate_immediate_low: 29;
+        pub u16, immediate_high, set_immediate_high: 38, 30;
+        pub ftz_flag, set_ftz_flag: 39;
+        pub negate_a, set_negate_a: 43;
+        pub absolute_a, set_absolute_a: 44;
+        pub u8, from into HalfSwizzle, a_swizzle, set_a_swizzle: 48, 47;
+        pub u8, from into HalfMerge, merge, set_merge: 50, 49;
+        pub sat_flag, set_sat_flag: 52;
+        pub negate_immediate_high, set_negate_immediate_high: 56;
+    }
+}
+
+instruction_layout! {
+    pub struct Hmul2Instruction {
+        pub u8, destination_register, set_destination_register: 7, 0;
+        pub u8, source_register_a, set_source_register_a: 15, 8;
+        source_predicate;
+        pub u8, source_register_b, set_source_register_b: 27, 20;
+        pub u8, from into HalfSwizzle, b_swizzle, set_b_swizzle: 29, 28;
+        pub absolute_b, set_absolute_b: 30;
+        pub negate_b, set_negate_b: 31;
+        pub sat_flag, set_sat_flag: 32;
+        pub u8, from into FloatMultiplyMode, multiply_mode, set_multiply_mode: 40, 39;
+        pub negate_a, set_negate_a: 43;
+        pub absolute_a, set_absolute_a: 44;
+        pub u8, from into HalfSwizzle, a_swizzle, set_a_swizzle: 48, 47;
+        pub u8, from into HalfMerge, merge, set_merge: 50, 49;
+    }
+}
+
+instruction_layout! {
+    pub struct Hmul2ImmInstruction {
+        pub u8, destination_register, set_destination_register: 7, 0;
+        pub u8, source_register_a, set_source_register_a: 15, 8;
+        source_predicate;
+        pub u16, immediate_low, set_immediate_low: 28, 20;
+        pub negate_immediate_low, set_negate_immediate_low: 29;
+        pub u16, immediate_high, set_immediate_high: 38, 30;
+        pub u8, from into FloatMultiplyMode, multiply_mode, set_multiply_mode: 40, 39;
+        pub negate_a, set_negate_a: 43;
+        pub absolute_a, set_absolute_a: 44;
+        pub u8, from into HalfSwizzle, a_swizzle, set_a_swizzle: 48, 47;
+        pub u8, from into HalfMerge, merge, set_merge: 50, 49;
+        pub sat_flag, set_sat_flag: 52;
+        pub negate_immediate_high, set_negate_immediate_high: 56;
+    }
+}
+
+instruction_layout! {
+    pub struct Hfma2Instruction {
+        pub u8, destination_register, set_destination_register: 7, 0;
+        pub u8, source_register_a, set_source_register_a: 15, 8;
+        source_predicate;
+        pub u8, source_register_b, set_source_register_b: 27, 20;
+        pub u8, from into HalfSwizzle, b_swizzle, set_b_swizzle: 29, 28;
+        pub negate_c, set_negate_c: 30;
+        pub negate_b, set_negate_b: 31;
+        pub sat_flag, set_sat_flag: 32;
+        pub u8, from into HalfSwizzle, c_swizzle, set_c_swizzle: 36, 35;
+        pub u8, from into FloatMultiplyMode, multiply_mode, set_multiply_mode: 38, 37;
+        pub u8, source_register_c, set_source_register_c: 46, 39;
+        pub u8, from into HalfSwizzle, a_swizzle, set_a_swizzle: 48, 47;
+        pub u8, from into HalfMerge, merge, set_merge: 50, 49;
+    }
+}
+
+instruction_layout! {
+    pub struct Hfma2ImmInstruction {
+        pub u8, destination_register, set_destination_register: 7, 0;
+        pub u8, source_register_a, set_source_register_a: 15, 8;
+        source_predicate;
+        pub u16, immediate_low, set_immediate_low: 28, 20;
+        pub negate_immediate_low, set_negate_immediate_low: 29;
+        pub u16, immediate_high, set_immediate_high: 38, 30;
+        pub u8, source_register_c, set_source_register_c: 46, 39;
+        pub u8, from into HalfSwizzle, a_swizzle, set_a_swizzle: 48, 47;
+        pub u8, from into HalfMerge, merge, set_merge: 50, 49;
+        pub negate_c, set_negate_c: 51;
+        pub sat_flag, set_sat_flag: 52;
+        pub u8, from into HalfSwizzle, c_swizzle, set_c_swizzle: 54, 53;
+        pub negate_immediate_high, set_negate_immediate_high: 56;
+        pub u8, from into FloatMultiplyMode, multiply_mode, set_multiply_mode: 58, 57;
+    }
+}
+
+instruction_layout! {
+    pub struct Hsetp2Instruction {
+        pub u8, destination_predicate_register_b, set_destination_predicate_register_b: 2, 0;
+        pub u8, destination_predicate_register_a, set_destination_predicate_register_a: 5, 3;
+        pub ftz_flag, set_ftz_flag: 6;
+        pub u8, source_register_a, set_source_register_a: 15, 8;
+        source_predicate;
+        pub u8, source_register_b, set_source_register_b: 27, 20;
+        pub u8, from into HalfSwizzle, b_swizzle, set_b_swizzle: 29, 28;
+        pub absolute_b, set_absolute_b: 30;
+        pub negate_b, set_negate_b: 31;
+        pub u8, from into FloatCompareOperation, compare_operation, set_compare_operation: 38, 35;
+        pub u8, combine_predicate_register, set_combine_predicate_register: 41, 39;
+        pub invert_combine_predicate, set_invert_combine_predicate: 42;
+        pub negate_a, set_negate_a: 43;
+        pub absolute_a, set_absolute_a: 44;
+        pub u8, from into PredicateOperation, predicate_operation, set_predicate_operation: 46, 45;
+        pub u8, from into HalfSwizzle, a_swizzle, set_a_swizzle: 48, 47;
+        pub h_and_flag, set_h_and_flag: 49;
+    }
+}
+
+instruction_layout! {
+    pub struct Hsetp2ImmInstruction {
+        pub u8, destination_predicate_register_b, set_destination_predicate_register_b: 2, 0;
+        pub u8, destination_predicate_register_a, set_destination_predicate_register_a: 5, 3;
+        pub ftz_flag, set_ftz_flag: 6;
+        pub u8, source_register_a, set_source_register_a: 15, 8;
+        source_predicate;
+        pub u16, immediate_low, set_immediate_low: 28, 20;
+        pub negate_immediate_low, set_negate_immediate_low: 29;
+        pub u16, immediate_high, set_immediate_high: 38, 30;
+        pub u8, combine_predicate_register, set_combine_predicate_register: 41, 39;
+        pub invert_combine_predicate, set_invert_combine_predicate: 42;
+        pub negate_a, set_negate_a: 43;
+        pub absolute_a, set_absolute_a: 44;
+        pub u8, from into PredicateOperation, predicate_operation, set_predicate_operation: 46, 45;
+        pub u8, from into HalfSwizzle, a_swizzle, set_a_swizzle: 48, 47;
+        pub u8, from into FloatCompareOperation, compare_operation, set_compare_operation: 52, 49;
+        pub h_and_flag, set_h_and_flag: 53;
+        pub negate_immediate_high, set_negate_immediate_high: 56;
+    }
+}
+
+instruction_layout! {
+    pub struct Hset2Instruction {
+        pub u8, destination_register, set_destination_register: 7, 0;
+        pub u8, source_register_a, set_source_register_a: 15, 8;
+        source_predicate;
+        pub u8, source_register_b, set_source_register_b: 27, 20;
+        pub u8, from into HalfSwizzle, b_swizzle, set_b_swizzle: 29, 28;
+        pub absolute_b, set_absolute_b: 30;
+        pub negate_b, set_negate_b: 31;
+        pub u8, from into FloatCompareOperation, compare_operation, set_compare_operation: 38, 35;
+        pub u8, combine_predicate_register, set_combine_predicate_register: 41, 39;
+        pub invert_combine_predicate, set_invert_combine_predicate: 42;
+        pub negate_a, set_negate_a: 43;
+        pub absolute_a, set_absolute_a: 44;
+        pub u8, from into PredicateOperation, predicate_operation, set_predicate_operation: 46, 45;
+        pub u8, from into HalfSwizzle, a_swizzle, set_a_swizzle: 48, 47;
+        pub bf_flag, set_bf_flag: 49;
+        pub ftz_flag, set_ftz_flag: 50;
+    }
+}
+
+instruction_layout! {
+    pub struct Hset2ImmInstruction {
+        pub u8, destination_register, set_destination_register: 7, 0;
+        pub u8, source_register_a, set_source_register_a: 15, 8;
+        source_predicate;
+        pub u16, immediate_low, set_immediate_low: 28, 20;
+        pub negate_immediate_low, set_negate_immediate_low: 29;
+        pub u16, immediate_high, set_immediate_high: 38, 30;
+        pub u8, combine_predicate_register, set_combine_predicate_register: 41, 39;
+        pub invert_combine_predicate, set_invert_combine_predicate: 42;
+        pub negate_a, set_negate_a: 43;
+        pub absolute_a, set_absolute_a: 44;
+        pub u8, from into PredicateOperation, predicate_operation, set_predicate_operation: 46, 45;
+        pub u8, from into HalfSwizzle, a_swizzle, set_a_swizzle: 48, 47;
+        pub u8, from into FloatCompareOperation, compare_operation, set_compare_operation: 52, 49;
+        pub bf_flag, set_bf_flag: 53;
+        pub ftz_flag, set_ftz_flag: 54;
+        pub negate_immediate_high, set_negate_immediate_high: 56;
+    }
+}
+
+/// Two half-precision floats packed as the immediate operand of the SM53 instructions.
+///
+/// The hardware only keeps the sign and the upper 9 bits of exponent and mantissa of
+/// each value, the lower 6 bits of the mantissa must be zero.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct HalfPair {
+    pub low: u16,
+    pub high: u16,
+}
+
+impl HalfPair {
+    pub fn new(low: f16, high: f16) -> Self {
+        HalfPair {
+            low: low.to_bits(),
+            high: high.to_bits(),
+        }
+    }
+
+    pub fn from_bits(low: u16, high: u16) -> Self {
+        HalfPair { low, high }
+    }
+
+    pub fn low(&self) -> f16 {
+        f16::from_bits(self.low)
+    }
+
+    pub fn high(&self) -> f16 {
+        f16::from_bits(self.high)
+    }
+
+    /// Check if both values can be encoded without losing precision.
+    pub fn is_exact(&self) -> bool {
+        (self.low & 0x3F) == 0 && (self.high & 0x3F) == 0
+    }
+}
+
+enum_with_val! {
+    #[derive(PartialEq, Eq)]
+    pub struct HalfSwizzle(u8) {
+        H1_H0 = 0,
+        F32 = 1,
+        H0_H0 = 2,
+        H1_H1 = 3
+    }
+}
+
+enum_with_val! {
+    #[derive(PartialEq, Eq)]
+    pub struct HalfMerge(u8) {
+        H1_H0 = 0,
+        F32 = 1,
+        MRG_H0 = 2,
+        MRG_H1 = 3
+    }
+}
+
+enum_with_val! {
+    #[derive(PartialEq, Eq)]
+    pub struct FloatMultiplyMode(u8) {
+        NONE = 0,
+        FTZ = 1,
+        FMZ = 2
+    }
+}
+
+enum_with_val! {
+    #[derive(PartialEq, Eq)]
+    pub struct FloatCompareOperation(u8) {
+        F = 0,
+        LT = 1,
+        EQ = 2,
+        LE = 3,
+        GT = 4,
+        NE = 5,
+        GE = 6,
+        NUM = 7,
+        NAN = 8,
+        LTU = 9,
+        EQU = 10,
+        LEU = 11,
+        GTU = 12,
+        NEU = 13,
+        GEU = 14,
+        T = 15
+    }
+}
@@ -1,327 +1,856 @@
-use std::convert::From;
+use crate::{enum_with_val, instruction_layout, opcodes};
 
-use crate::enum_with_val;
+pub mod check;
+mod fp16;
 
-bitfield! {
-    pub struct Instruction(u64);
-    impl Debug;
-    pub data, set_data: 31, 0;
-    pub u32, from into Opcode, opcode, set_opcode : 63, 32;
+pub use fp16::*;
+
+instruction_layout! {
+    pub struct Imm16Data {
+        pub u16, imm16, set_imm16: 35, 20;
+    }
 }
 
-bitfield! {
-    pub struct Imm16Data(u64);
-    impl Debug;
-    pub u16, imm16, set_imm16: 35, 20;
+instruction_layout! {
+    pub struct Imm8Data {
+        pub u8, imm8, set_imm8: 15, 8;
+    }
 }
 
-bitfield! {
-    pub struct Imm8Data(u64);
-    impl Debug;
-    pub u8, imm8, set_imm8: 15, 8;
+instruction_layout! {
+    pub struct Imm32Data {
+        pub u32, imm32, set_imm32: 51, 20;
+    }
 }
 
-bitfield! {
-    pub struct Imm32Data(u64);
-    impl Debug;
-    pub u32, imm32, set_imm32: 51, 20;
+instruction_layout! {
+    pub struct Operand0Data {
+        pub u8, operand, set_operand: 7, 0;
+    }
 }
 
-bitfield! {
-    pub struct Operand0Data(u64);
-    impl Debug;
-    pub u8, operand, set_operand: 7, 0;
+instruction_layout! {
+    pub struct Operand1Data {
+        pub u8, operand, set_operand: 15, 8;
+    }
 }
 
-bitfield! {
-    pub struct Operand1Data(u64);
-    impl Debug;
-    pub u8, operand, set_operand: 15, 8;
+instruction_layout! {
+    pub struct Operand2Data {
+        pub u8, operand, set_operand: 27, 20;
+    }
 }
 
-bitfield! {
-    pub struct Operand2Data(u64);
-    impl Debug;
-    pub u8, operand, set_operand: 27, 20;
+instruction_layout! {
+    pub struct Operand3Data {
+        pub u8, operand, set_operand: 46, 39;
+    }
 }
 
-bitfield! {
-    pub struct Operand3Data(u64);
-    impl Debug;
-    pub u8, operand, set_operand: 46, 39;
+instruction_layout! {
+    pub struct SourcePredicateData {
+        pub u8, source_predicate_register, set_source_predicate_register: 18, 16;
+        pub invert_source_predicate, set_invert_source_predicate: 19;
+    }
 }
 
-bitfield! {
-    pub struct SourcePredicateData(u64);
-    impl Debug;
-    pub u8, source_predicate_register, set_source_predicate_register: 18, 16;
-    pub invert_source_predicate, set_invert_source_predicate: 19;
+instruction_layout! {
+    pub struct DestinationPredicateData {
+        pub u8, destination_predicate_register, set_destination_predicate_register: 46, 44;
+    }
+}
+
+instruction_layout! {
+    pub struct NopInstruction {
+        pub u8, from into ControlCode, cc_flags, set_cc_flags: 12, 8;
+        pub trigger, set_trigger: 13;
+        source_predicate;
+        pub u16, imm16, set_imm16: 35, 20;
+    }
 }
 
-bitfield! {
-    pub struct DestinationPredicateData(u64);
-    impl Debug;
+instruction_layout! {
+    pub struct SamInstruction {}
+}
 
-    pub u8, destination_predicate_register, set_destination_predicate_register: 46, 44;
+instruction_layout! {
+    pub struct RamInstruction {}
 }
 
-bitfield! {
-    pub struct NopInstruction(u64);
-    impl Debug;
+// NOTE: Also used by Opcode::SYNC, Opcode::BRK and Opcode::CONT.
+instruction_layout! {
+    pub struct RetInstruction {
+        pub u8, from into ControlCode, cc_flags, set_cc_flags: 4, 0;
+        source_predicate;
+    }
+}
 
-    pub u8, from into ControlCode, cc_flags, set_cc_flags: 12, 8;
-    pub trigger, set_trigger: 13;
-    pub u8, source_predicate_register, set_source_predicate_register: 18, 16;
-    pub invert_source_predicate, set_invert_source_predicate: 19;
-    pub u16, imm16, set_imm16: 36, 20;
+instruction_layout! {
+    pub struct ExitInstruction {
+        pub u8, from into ControlCode, cc_flags, set_cc_flags: 4, 0;
+        pub u8, keep_refcount, set_keep_refcount: 5;
+        source_predicate;
+    }
 }
 
-bitfield! {
-    pub struct SamInstruction(u64);
-    impl Debug;
+instruction_layout! {
+    pub struct BraInstruction {
+        pub u8, from into ControlCode, cc_flags, set_cc_flags: 4, 0;
+        pub constant_buffer_flag, set_constant_buffer_flag: 5;
+        source_predicate;
+        // NOTE: relative to the address of the next instruction, in bytes.
+        pub i32, offset, set_offset: 43, 20;
+    }
 }
 
-bitfield! {
-    pub struct RamInstruction(u64);
-    impl Debug;
+// NOTE: Used by Opcode::SSY, Opcode::PBK, Opcode::PCNT and Opcode::PRET to push an entry
+// on the reconvergence stack.
+instruction_layout! {
+    pub struct FlowPushInstruction {
+        pub constant_buffer_flag, set_constant_buffer_flag: 5;
+        // NOTE: relative to the address of the next instruction, in bytes.
+        pub i32, offset, set_offset: 43, 20;
+    }
 }
 
-bitfield! {
-    pub struct RetInstruction(u64);
-    impl Debug;
+instruction_layout! {
+    pub struct GetLMEMBASEInstruction {
+        pub u8, source_register, set_source_register: 7, 0;
+    }
+}
 
-    pub u8, from into ControlCode, cc_flags, set_cc_flags: 4, 0;
-    pub u8, source_predicate_register, set_source_predicate_register: 18, 16;
-    pub invert_source_predicate, set_invert_source_predicate: 19;
+instruction_layout! {
+    pub struct SetLMEMBASEInstruction {
+        pub u8, destination_register, set_destination_register: 15, 8;
+    }
 }
 
-bitfield! {
-    pub struct ExitInstruction(u64);
-    impl Debug;
+instruction_layout! {
+    pub struct IdeInstruction {
+        pub u16, imm16, set_imm16: 35, 20;
+        pub disable, set_disable: 5;
+    }
+}
 
-    pub u8, from into ControlCode, cc_flags, set_cc_flags: 4, 0;
-    pub u8, keep_refcount, set_keep_refcount: 5;
-    pub u8, source_predicate_register, set_source_predicate_register: 18, 16;
-    pub invert_source_predicate, set_invert_source_predicate: 19;
+instruction_layout! {
+    pub struct KilInstruction {
+        pub u8, from into ControlCode, cc_flags, set_cc_flags: 4, 0;
+        source_predicate;
+    }
 }
 
-bitfield! {
-    pub struct GetLMEMBASEInstruction(u64);
-    impl Debug;
+instruction_layout! {
+    pub struct Al2pInstruction {
+        pub u8, destination_register, set_destination_register: 7, 0;
+        pub u8, source_register, set_source_register: 15, 8;
+        source_predicate;
+        pub i16, load_offset, set_load_offset: 30, 20;
+        pub o_flag, set_o_flag: 32;
 
-    pub u8, source_register, set_source_register: 7, 0;
+        pub u8, destination_predicate_register, set_destination_predicate_register: 46, 44;
+        pub u8, from into AtributeLoadMode, mode, set_mode: 48, 47;
+    }
 }
 
-bitfield! {
-    pub struct SetLMEMBASEInstruction(u64);
-    impl Debug;
+instruction_layout! {
+    pub struct AldInstruction {
+        pub u8, destination_register, set_destination_register: 7, 0;
+        pub u8, source_offset_register, set_source_offset_register: 15, 8;
+        source_predicate;
+        // NOTE: only valid if no_physical_flag is set.
+        pub i16, load_offset, set_load_offset: 30, 20;
+        pub u8, source_register, set_source_register: 46, 39;
+        pub no_physical_flag, set_no_physical_flag: 31;
+        pub o_flag, set_o_flag: 32;
+        pub u8, from into AtributeLoadMode, mode, set_mode: 48, 47;
+    }
+}
 
-    pub u8, destination_register, set_destination_register: 15, 8;
+instruction_layout! {
+    pub struct AstInstruction {
+        pub u8, destination_register, set_destination_register: 7, 0;
+        pub u8, source_offset_register, set_source_offset_register: 15, 8;
+        source_predicate;
+        // NOTE: only valid if no_physical_flag is set.
+        pub i16, load_offset, set_load_offset: 30, 20;
+        pub u8, source_register_b, set_source_register_b: 46, 39;
+        pub no_physical_flag, set_no_physical_flag: 31;
+        pub u8, from into AtributeLoadMode, mode, set_mode: 48, 47;
+    }
 }
 
-bitfield! {
-    pub struct IdeInstruction(u64);
-    impl Debug;
+instruction_layout! {
+    pub struct AtomsIntruction {
+        pub u8, destination_register, set_destination_register: 7, 0;
+        pub u8, source_register_a, set_source_register_a: 15, 8;
+        source_predicate;
+        pub u8, source_register_b, set_source_register_b: 27, 20;
+        pub u8, from into AtomsPrimitiveType, type_size, set_type_size: 29, 28;
+        pub i32, register_a_offset_shr_2, set_register_a_offset_shr_2: 51, 30;
+        pub u8, from into AtomsOperation, operation, set_operation: 55, 52;
+    }
+}
 
-    pub u16, imm16, set_imm16: 36, 20;
-    pub disabe, set_disabe: 5;
+instruction_layout! {
+    pub struct AtomsCasIntruction {
+        pub u8, destination_register, set_destination_register: 7, 0;
+        pub u8, source_register_a, set_source_register_a: 15, 8;
+        source_predicate;
+        // NOTE: the comparison value is implicitly held by the register preceding source_register_b.
+        pub u8, source_register_b, set_source_register_b: 27, 20, bias 1 =>
+            compare_register, set_compare_register;
+        pub i32, register_a_offset_shr_2, set_register_a_offset_shr_2: 51, 30;
+        pub u8, from into AtomicCasPrimitiveType, type_size, set_type_size: 52, 52;
+        pub u8, from into AtomsCasOperation, operation, set_operation: 54, 53;
+    }
 }
 
-bitfield! {
-    pub struct KilInstruction(u64);
-    impl Debug;
+instruction_layout! {
+    pub struct AtomInstruction {
+        pub u8, destination_register, set_destination_register: 7, 0;
+        pub u8, source_register_a, set_source_register_a: 15, 8;
+        source_predicate;
+        pub u8, source_register_b, set_source_register_b: 27, 20;
+        pub i32, register_a_offset, set_register_a_offset: 47, 28;
+        pub u8, from into AtomPrimitiveType, type_size, set_type_size: 51, 49;
+        pub u8, from into AtomOperation, operation, set_operation: 55, 52;
+        pub e_flag, set_e_flag: 48;
+    }
+}
 
-    pub u8, from into ControlCode, cc_flags, set_cc_flags: 4, 0;
-    pub u8, source_predicate_register, set_source_predicate_register: 18, 16;
-    pub invert_source_predicate, set_invert_source_predicate: 19;
+instruction_layout! {
+    pub struct AtomCasInstruction {
+        pub u8, destination_register, set_destination_register: 7, 0;
+        pub u8, source_register_a, set_source_register_a: 15, 8;
+        source_predicate;
+        pub u8, source_register_b, set_source_register_b: 27, 20;
+        pub i32, register_a_offset, set_register_a_offset: 47, 28;
+        pub e_flag, set_e_flag: 48;
+        pub u8, from into AtomicCasPrimitiveType, type_size, set_type_size: 49, 49;
+    }
 }
 
-bitfield! {
-    pub struct Al2pInstruction(u64);
-    impl Debug;
+instruction_layout! {
+    pub struct RedInstruction {
+        pub u8, source_register_b, set_source_register_b: 7, 0;
+        pub u8, source_register_a, set_source_register_a: 15, 8;
+        source_predicate;
+        pub u8, from into AtomPrimitiveType, type_size, set_type_size: 22, 20;
+        pub u8, from into AtomOperation, operation, set_operation: 25, 23;
+        pub i32, register_a_offset, set_register_a_offset: 47, 28;
+        pub e_flag, set_e_flag: 48;
+    }
+}
 
-    pub u8, destination_register, set_destination_register: 7, 0;
-    pub u8, source_register, set_source_register: 15, 8;
-    pub u8, source_predicate_register, set_source_predicate_register: 18, 16;
-    pub invert_source_predicate, set_invert_source_predicate: 19;
-    pub i16, load_offset, set_load_offset: 30, 20;
-    pub o_flag, set_o_flag: 32;
+instruction_layout! {
+    pub struct B2RInstruction {
+        pub u8, destination_register, set_destination_register: 7, 0;
+        pub u8, imm8, set_imm8: 15, 8;
+        source_predicate;
+        pub u8, from into B2ROperation, operation, set_operation: 33, 32;
+        pub u8, destination_predicate_register, set_destination_predicate_register: 46, 44;
+    }
+}
 
-    pub u8, destination_predicate_register, set_destination_predicate_register: 46, 44;
-    pub u8, from into AtributeLoadMode, mode, set_mode: 48, 47;
+instruction_layout! {
+    pub struct BarInstruction {
+        pub u8, barrier, set_barrier: 15, 8;
+        source_predicate;
+        pub u16, thread_count, set_thread_count: 31, 20;
+        pub u8, from into BarMode, mode, set_mode: 34, 32;
+        pub u8, from into BarReductionOperation, reduction, set_reduction: 36, 35;
+        // NOTE: only used by BarMode::RED.
+        pub u8, reduction_predicate_register, set_reduction_predicate_register: 41, 39;
+        pub invert_reduction_predicate, set_invert_reduction_predicate: 42;
+        pub barrier_immediate_flag, set_barrier_immediate_flag: 43;
+        pub thread_count_immediate_flag, set_thread_count_immediate_flag: 44;
+    }
 }
 
-bitfield! {
-    pub struct AldInstruction(u64);
-    impl Debug;
+instruction_layout! {
+    pub struct MembarInstruction {
+        pub u8, from into MembarLevel, level, set_level: 9, 8;
+        source_predicate;
+    }
+}
 
-    pub u8, destination_register, set_destination_register: 7, 0;
-    pub u8, source_offset_register, set_source_offset_register: 15, 8;
-    pub u8, source_predicate_register, set_source_predicate_register: 18, 16;
-    pub invert_source_predicate, set_invert_source_predicate: 19;
-    // NOTE: only valid if no_physical_flag is set.
-    pub i16, load_offset, set_load_offset: 30, 20;
-    pub u8, source_register, set_source_register: 46, 39;
-    pub no_physical_flag, set_no_physical_flag: 31;
-    pub o_flag, set_o_flag: 32;
-    pub u8, from into AtributeLoadMode, mode, set_mode: 48, 47;
+instruction_layout! {
+    pub struct DepbarInstruction {
+        pub u8, dependency_mask, set_dependency_mask: 5, 0;
+        source_predicate;
+        pub u8, count, set_count: 25, 20;
+        pub u8, scoreboard, set_scoreboard: 28, 26;
+        pub le_flag, set_le_flag: 29;
+    }
 }
 
-bitfield! {
-    pub struct AstInstruction(u64);
-    impl Debug;
-
-    pub u8, destination_register, set_destination_register: 7, 0;
-    pub u8, source_offset_register, set_source_offset_register: 15, 8;
-    pub u8, source_predicate_register, set_source_predicate_register: 18, 16;
-    pub invert_source_predicate, set_invert_source_predicate: 19;
-    // NOTE: only valid if no_physical_flag is set.
-    pub i16, load_offset, set_load_offset: 30, 20;
-    pub u8, source_registerb, set_source_registerb: 46, 39;
-    pub no_physical_flag, set_no_physical_flag: 31;
-    pub u8, from into AtributeLoadMode, mode, set_mode: 48, 47;
-}
-
-bitfield! {
-    pub struct AtomsIntruction(u64);
-    impl Debug;
-
-    pub u8, destniation_register, set_destination_register: 7, 0;
-    pub u8, source_register_a, set_source_register_a: 15, 8;
-    pub u8, source_predicate_register, set_source_predicate_register: 18, 16;
-    pub invert_source_predicate, set_invert_source_predicate: 19;
-    pub u8, source_register_b, set_source_register_b: 27, 20;
-    pub u8, from into AtomsPrimitiveType, type_size, set_type_size: 30, 28;
-    pub i32, register_a_offset_shr_2, set_register_a_offset_shr_2: 51, 30;
-    pub u8, from into AtomsOperation, operation, set_operation: 55, 52;
-}
-
-bitfield! {
-    pub struct AtomsCasIntruction(u64);
-    impl Debug;
-
-    pub u8, destniation_register, set_destination_register: 7, 0;
-    pub u8, source_register_a, set_source_register_a: 15, 8;
-    pub u8, source_predicate_register, set_source_predicate_register: 18, 16;
-    pub invert_source_predicate, set_invert_source_predicate: 19;
-    pub u8, source_register_b, set_source_register_b: 27, 20;
-    pub i32, register_a_offset_shr_2, set_register_a_offset_shr_2: 51, 30;
-    pub u8, from into AtomicCasPrimitiveType, type_size, set_type_size: 52, 52;
-    pub u8, from into AtomsCasOperation, operation, set_operation: 54, 53;
-}
-
-bitfield! {
-    pub struct AtomInstruction(u64);
-    impl Debug;
-
-    pub u8, destniation_register, set_destination_register: 7, 0;
-    pub u8, source_register_a, set_source_register_a: 15, 8;
-    pub u8, source_predicate_register, set_source_predicate_register: 18, 16;
-    pub invert_source_predicate, set_invert_source_predicate: 19;
-    pub u8, source_register_b, set_source_register_b: 27, 20;
-    pub i32, register_a_offset, set_register_a_offset: 47, 28;
-    pub u8, from into AtomPrimitiveType, type_size, set_type_size: 51, 49;
-    pub u8, from into AtomOperation, operation, set_operation: 55, 52;
-    pub e_flag, set_e_flag: 48;
-}
-
-bitfield! {
-    pub struct AtomCasInstruction(u64);
-    impl Debug;
-
-    pub u8, destniation_register, set_destination_register: 7, 0;
-    pub u8, source_register_a, set_source_register_a: 15, 8;
-    pub u8, source_predicate_register, set_source_predicate_register: 18, 16;
-    pub invert_source_predicate, set_invert_source_predicate: 19;
-    pub u8, source_register_b, set_source_register_b: 27, 20;
-    pub i32, register_a_offset, set_register_a_offset: 47, 28;
-    pub e_flag, set_e_flag: 48;
-    pub u8, from into AtomicCasPrimitiveType, type_size, set_type_size: 49, 49;
-}
-
-bitfield! {
-    pub struct B2RInstruction(u64);
-    impl Debug;
-
-    pub u8, destination_register, set_destination_register: 7, 0;
-    pub u8, imm8, set_imm8: 15, 8;
-    pub u8, source_predicate_register, set_source_predicate_register: 18, 16;
-    pub invert_source_predicate, set_invert_source_predicate: 19;
-    pub u8, from into B2ROperation, operation, set_operation: 33, 32;
-    pub u8, destination_predicate_register, set_destination_predicate_register: 46, 44;
-}
-
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
-#[allow(non_camel_case_types)]
-pub enum Opcode {
-    AL2P,
-    ALD,
-    AST,
-    ATOM,
-    ATOM_CAS,
-    ATOMS,
-    ATOMS_CAS,
-    B2R,
-    EXIT,
-    GETLMEMBASE,
-    IDE,
-    KIL,
-    NOP,
-    RAM,
-    RET,
-    SAM,
-    SETLMEMBASE,
-}
-
-impl From<u32> for Opcode {
-    fn from(value: u32) -> Self {
-        match value {
-            0xefa00000 => Opcode::AL2P,
-            0xefd80000 => Opcode::ALD,
-            0xeff00000 => Opcode::AST,
-            0xec000000 => Opcode::ATOMS,
-            0xed000000 => Opcode::ATOM,
-            0xee400000 => Opcode::ATOMS_CAS,
-            0xeef00000 => Opcode::ATOM_CAS,
-            0xf0b80000 => Opcode::B2R,
-            0xe3000000 => Opcode::EXIT,
-            0xe2d00000 => Opcode::GETLMEMBASE,
-            0xe3900000 => Opcode::IDE,
-            0xe3300000 => Opcode::KIL,
-            0x50b00000 => Opcode::NOP,
-            0xe3800000 => Opcode::RAM,
-            0xe3200000 => Opcode::RET,
-            0xe3700000 => Opcode::SAM,
-            0xe2f00000 => Opcode::SETLMEMBASE,
-            _ => panic!("Invalid Opcode value"),
-        }
+instruction_layout! {
+    pub struct CctlInstruction {
+        pub u8, from into CacheOperation, operation, set_operation: 3, 0;
+        pub u8, from into CacheType, cache, set_cache: 6, 4;
+        pub u8, source_register, set_source_register: 15, 8;
+        source_predicate;
+        pub i32, offset_shr_2, set_offset_shr_2: 51, 22;
     }
 }
 
-impl From<Opcode> for u32 {
-    fn from(opcode: Opcode) -> u32 {
-        match opcode {
-            Opcode::AL2P => 0xefa00000,
-            Opcode::ALD => 0xefd80000,
-            Opcode::AST => 0xeff00000,
-            Opcode::ATOMS => 0xec000000,
-            Opcode::ATOM => 0xed000000,
-            Opcode::ATOMS_CAS => 0xee400000,
-            Opcode::ATOM_CAS => 0xeef00000,
-            Opcode::B2R => 0xf0b80000,
-            Opcode::EXIT => 0xe3000000,
-            Opcode::GETLMEMBASE => 0xe2d00000,
-            Opcode::IDE => 0xe3900000,
-            Opcode::KIL => 0xe3300000,
-            Opcode::NOP => 0x50b00000,
-            Opcode::SAM => 0xe3700000,
-            Opcode::RAM => 0xe3800000,
-            Opcode::RET => 0xe3200000,
-            Opcode::SETLMEMBASE => 0xe2f00000,
-        }
+instruction_layout! {
+    pub struct CctllInstruction {
+        pub u8, from into CacheOperation, operation, set_operation: 3, 0;
+        pub u8, source_register, set_source_register: 15, 8;
+        source_predicate;
+        pub i32, offset_shr_2, set_offset_shr_2: 45, 22;
+    }
+}
+
+instruction_layout! {
+    pub struct TexInstruction {
+        pub u8, destination_register, set_destination_register: 7, 0;
+        pub u8, source_register_a, set_source_register_a: 15, 8;
+        source_predicate;
+        pub u8, source_register_b, set_source_register_b: 27, 20;
+        pub u8, from into TextureType, texture_type, set_texture_type: 30, 28;
+        pub u8, component_mask, set_component_mask: 34, 31;
+        pub ndv_flag, set_ndv_flag: 35;
+        pub u16, texture_index, set_texture_index: 48, 36;
+        pub nodep_flag, set_nodep_flag: 49;
+        pub dc_flag, set_dc_flag: 50;
+        pub u8, sparse_predicate_register, set_sparse_predicate_register: 53, 51;
+        pub aoffi_flag, set_aoffi_flag: 54;
+        pub u8, from into TextureLodMode, lod_mode, set_lod_mode: 57, 55;
+        pub lc_flag, set_lc_flag: 58;
+    }
+}
+
+instruction_layout! {
+    pub struct TexBindlessInstruction {
+        pub u8, destination_register, set_destination_register: 7, 0;
+        pub u8, source_register_a, set_source_register_a: 15, 8;
+        source_predicate;
+        pub u8, source_register_b, set_source_register_b: 27, 20;
+        pub u8, from into TextureType, texture_type, set_texture_type: 30, 28;
+        pub u8, component_mask, set_component_mask: 34, 31;
+        pub ndv_flag, set_ndv_flag: 35;
+        pub aoffi_flag, set_aoffi_flag: 36;
+        pub u8, from into TextureLodMode, lod_mode, set_lod_mode: 39, 37;
+        pub lc_flag, set_lc_flag: 40;
+        pub nodep_flag, set_nodep_flag: 49;
+        pub dc_flag, set_dc_flag: 50;
+        pub u8, sparse_predicate_register, set_sparse_predicate_register: 53, 51;
+    }
+}
+
+// NOTE: Also used by Opcode::TLDS as the layout is the same.
+instruction_layout! {
+    pub struct TexsInstruction {
+        pub u8, destination_register_b, set_destination_register_b: 7, 0;
+        pub u8, source_register_a, set_source_register_a: 15, 8;
+        source_predicate;
+        pub u8, source_register_b, set_source_register_b: 27, 20;
+        pub u8, destination_register_a, set_destination_register_a: 35, 28;
+        pub u16, texture_index, set_texture_index: 48, 36;
+        pub nodep_flag, set_nodep_flag: 49;
+        pub u8, component_swizzle, set_component_swizzle: 52, 50;
+        pub u8, encoding, set_encoding: 56, 53;
+        pub fp16_flag, set_fp16_flag: 59;
+    }
+}
+
+instruction_layout! {
+    pub struct Tld4sInstruction {
+        pub u8, destination_register_b, set_destination_register_b: 7, 0;
+        pub u8, source_register_a, set_source_register_a: 15, 8;
+        source_predicate;
+        pub u8, source_register_b, set_source_register_b: 27, 20;
+        pub u8, destination_register_a, set_destination_register_a: 35, 28;
+        pub u16, texture_index, set_texture_index: 48, 36;
+        pub nodep_flag, set_nodep_flag: 49;
+        pub dc_flag, set_dc_flag: 50;
+        pub aoffi_flag, set_aoffi_flag: 51;
+        pub u8, from into TextureComponent, component, set_component: 53, 52;
+        pub fp16_flag, set_fp16_flag: 55;
+    }
+}
+
+// NOTE: Also used by Opcode::TLD_B, texture_index is ignored in this case.
+instruction_layout! {
+    pub struct TldInstruction {
+        pub u8, destination_register, set_destination_register: 7, 0;
+        pub u8, source_register_a, set_source_register_a: 15, 8;
+        source_predicate;
+        pub u8, source_register_b, set_source_register_b: 27, 20;
+        pub u8, from into TextureType, texture_type, set_texture_type: 30, 28;
+        pub u8, component_mask, set_component_mask: 34, 31;
+        pub aoffi_flag, set_aoffi_flag: 35;
+        pub u16, texture_index, set_texture_index: 48, 36;
+        pub nodep_flag, set_nodep_flag: 49;
+        pub ms_flag, set_ms_flag: 50;
+        // NOTE: LZ when unset, LL when set.
+        pub ll_flag, set_ll_flag: 55;
+    }
+}
+
+instruction_layout! {
+    pub struct Tld4Instruction {
+        pub u8, destination_register, set_destination_register: 7, 0;
+        pub u8, source_register_a, set_source_register_a: 15, 8;
+        source_predicate;
+        pub u8, source_register_b, set_source_register_b: 27, 20;
+        pub u8, from into TextureType, texture_type, set_texture_type: 30, 28;
+        pub u8, component_mask, set_component_mask: 34, 31;
+        pub ndv_flag, set_ndv_flag: 35;
+        pub u16, texture_index, set_texture_index: 48, 36;
+        pub nodep_flag, set_nodep_flag: 49;
+        pub dc_flag, set_dc_flag: 50;
+        pub u8, from into TextureGatherOffset, offset_mode, set_offset_mode: 55, 54;
+        pub u8, from into TextureComponent, component, set_component: 57, 56;
+    }
+}
+
+instruction_layout! {
+    pub struct Tld4BindlessInstruction {
+        pub u8, destination_register, set_destination_register: 7, 0;
+        pub u8, source_register_a, set_source_register_a: 15, 8;
+        source_predicate;
+        pub u8, source_register_b, set_source_register_b: 27, 20;
+        pub u8, from into TextureType, texture_type, set_texture_type: 30, 28;
+        pub u8, component_mask, set_component_mask: 34, 31;
+        pub ndv_flag, set_ndv_flag: 35;
+        pub u8, from into TextureGatherOffset, offset_mode, set_offset_mode: 37, 36;
+        pub u8, from into TextureComponent, component, set_component: 39, 38;
+        pub nodep_flag, set_nodep_flag: 49;
+        pub dc_flag, set_dc_flag: 50;
+    }
+}
+
+// NOTE: Also used by Opcode::TXQ_B, texture_index is ignored in this case.
+instruction_layout! {
+    pub struct TxqInstruction {
+        pub u8, destination_register, set_destination_register: 7, 0;
+        pub u8, source_register, set_source_register: 15, 8;
+        source_predicate;
+        pub u8, from into TextureQuery, query, set_query: 27, 22;
+        pub u8, component_mask, set_component_mask: 34, 31;
+        pub u16, texture_index, set_texture_index: 48, 36;
+        pub nodep_flag, set_nodep_flag: 49;
+    }
+}
+
+// NOTE: Also used by Opcode::TMML_B, texture_index is ignored in this case.
+instruction_layout! {
+    pub struct TmmlInstruction {
+        pub u8, destination_register, set_destination_register: 7, 0;
+        pub u8, source_register_a, set_source_register_a: 15, 8;
+        source_predicate;
+        pub u8, source_register_b, set_source_register_b: 27, 20;
+        pub u8, from into TextureType, texture_type, set_texture_type: 30, 28;
+        pub u8, component_mask, set_component_mask: 34, 31;
+        pub ndv_flag, set_ndv_flag: 35;
+        pub u16, texture_index, set_texture_index: 48, 36;
+        pub nodep_flag, set_nodep_flag: 49;
+    }
+}
+
+// NOTE: Also used by Opcode::TXD_B, texture_index is ignored in this case.
+instruction_layout! {
+    pub struct TxdInstruction {
+        pub u8, destination_register, set_destination_register: 7, 0;
+        pub u8, source_register_a, set_source_register_a: 15, 8;
+        source_predicate;
+        pub u8, source_register_b, set_source_register_b: 27, 20;
+        pub u8, from into TextureType, texture_type, set_texture_type: 30, 28;
+        pub u8, component_mask, set_component_mask: 34, 31;
+        pub aoffi_flag, set_aoffi_flag: 35;
+        pub u16, texture_index, set_texture_index: 48, 36;
+        pub nodep_flag, set_nodep_flag: 49;
+    }
+}
+
+// NOTE: Also used by Opcode::SUST, destination_register holds the value to store in this case.
+instruction_layout! {
+    pub struct SuldInstruction {
+        pub u8, destination_register, set_destination_register: 7, 0;
+        pub u8, source_register, set_source_register: 15, 8;
+        source_predicate;
+        // NOTE: component mask when d_flag is unset, SurfaceSize otherwise.
+        pub u8, format, set_format: 23, 20;
+        pub u8, from into SurfaceType, surface_type, set_surface_type: 35, 33;
+        pub u16, surface_index, set_surface_index: 48, 36;
+        pub u8, from into SurfaceClampMode, clamp_mode, set_clamp_mode: 50, 49;
+        pub d_flag, set_d_flag: 52;
+    }
+}
+
+// NOTE: Also used by Opcode::SUST, destination_register holds the value to store in this case.
+instruction_layout! {
+    pub struct SuldBindlessInstruction {
+        pub u8, destination_register, set_destination_register: 7, 0;
+        pub u8, source_register, set_source_register: 15, 8;
+        source_predicate;
+        // NOTE: component mask when d_flag is unset, SurfaceSize otherwise.
+        pub u8, format, set_format: 23, 20;
+        pub u8, from into SurfaceType, surface_type, set_surface_type: 35, 33;
+        pub u8, surface_register, set_surface_register: 46, 39;
+        pub u8, from into SurfaceClampMode, clamp_mode, set_clamp_mode: 50, 49;
+        pub d_flag, set_d_flag: 52;
+    }
+}
+
+// NOTE: Only the bindless form is supported by surface atomics.
+instruction_layout! {
+    pub struct SuatomInstruction {
+        pub u8, destination_register, set_destination_register: 7, 0;
+        pub u8, source_register_a, set_source_register_a: 15, 8;
+        source_predicate;
+        pub u8, source_register_b, set_source_register_b: 27, 20;
+        pub u8, from into AtomOperation, operation, set_operation: 32, 29;
+        pub u8, from into SurfaceType, surface_type, set_surface_type: 35, 33;
+        pub u8, from into AtomPrimitiveType, type_size, set_type_size: 38, 36;
+        pub u8, surface_register, set_surface_register: 46, 39;
+        pub u8, from into SurfaceClampMode, clamp_mode, set_clamp_mode: 50, 49;
+    }
+}
+
+instruction_layout! {
+    pub struct SuredInstruction {
+        pub u8, source_register_b, set_source_register_b: 7, 0;
+        pub u8, source_register_a, set_source_register_a: 15, 8;
+        source_predicate;
+        pub u8, from into AtomPrimitiveType, type_size, set_type_size: 22, 20;
+        pub u8, from into AtomOperation, operation, set_operation: 26, 24;
+        pub u8, from into SurfaceType, surface_type, set_surface_type: 35, 33;
+        pub u8, surface_register, set_surface_register: 46, 39;
+        pub u8, from into SurfaceClampMode, clamp_mode, set_clamp_mode: 50, 49;
+    }
+}
+
+instruction_layout! {
+    pub struct IpaInstruction {
+        pub u8, destination_register, set_destination_register: 7, 0;
+        pub u8, source_register_a, set_source_register_a: 15, 8;
+        source_predicate;
+        pub u8, source_register_b, set_source_register_b: 27, 20;
+        pub u8, attribute_shr_2, set_attribute_shr_2: 37, 30;
+        pub idx_flag, set_idx_flag: 38;
+        pub u8, source_register_c, set_source_register_c: 46, 39;
+        pub sat_flag, set_sat_flag: 51;
+        pub u8, from into IpaSampleMode, sample_mode, set_sample_mode: 53, 52;
+        pub u8, from into IpaInterpolationMode, interpolation_mode, set_interpolation_mode: 55, 54;
+    }
+}
+
+instruction_layout! {
+    pub struct PixldInstruction {
+        pub u8, destination_register, set_destination_register: 7, 0;
+        pub u8, source_register, set_source_register: 15, 8;
+        source_predicate;
+        pub i8, offset, set_offset: 27, 20;
+        pub u8, from into PixelLoadMode, mode, set_mode: 33, 31;
+        pub u8, destination_predicate_register, set_destination_predicate_register: 46, 44;
+    }
+}
+
+instruction_layout! {
+    pub struct OutInstruction {
+        pub u8, destination_register, set_destination_register: 7, 0;
+        pub u8, source_register_a, set_source_register_a: 15, 8;
+        source_predicate;
+        pub u8, source_register_b, set_source_register_b: 27, 20;
+        pub u8, from into OutMode, mode, set_mode: 40, 39;
+    }
+}
+
+instruction_layout! {
+    pub struct OutImmInstruction {
+        pub u8, destination_register, set_destination_register: 7, 0;
+        pub u8, source_register_a, set_source_register_a: 15, 8;
+        source_predicate;
+        pub u8, stream, set_stream: 27, 20;
+        pub u8, from into OutMode, mode, set_mode: 40, 39;
+    }
+}
+
+instruction_layout! {
+    pub struct IsberdInstruction {
+        pub u8, destination_register, set_destination_register: 7, 0;
+        pub u8, source_register, set_source_register: 15, 8;
+        source_predicate;
+        pub skew_flag, set_skew_flag: 31;
+        pub o_flag, set_o_flag: 32;
+        pub u8, from into IsbeMode, mode, set_mode: 34, 33;
+        pub u8, from into IsbeShift, shift, set_shift: 48, 47;
+    }
+}
+
+instruction_layout! {
+    pub struct IsbewrInstruction {
+        pub u8, source_register_a, set_source_register_a: 15, 8;
+        source_predicate;
+        pub u8, source_register_b, set_source_register_b: 27, 20;
+        pub u8, from into IsbeMode, mode, set_mode: 34, 33;
+        pub u8, from into IsbeShift, shift, set_shift: 48, 47;
+    }
+}
+
+// NOTE: Used by Opcode::VADD, Opcode::VABSDIFF, Opcode::VSHL and Opcode::VSHR.
+instruction_layout! {
+    pub struct VideoInstruction {
+        pub u8, destination_register, set_destination_register: 7, 0;
+        pub u8, source_register_a, set_source_register_a: 15, 8;
+        source_predicate;
+        pub u8, source_register_b, set_source_register_b: 27, 20;
+        pub u8, from into VideoSelector, b_selector, set_b_selector: 30, 28;
+        pub u8, from into VideoSelector, a_selector, set_a_selector: 38, 36;
+        pub u8, source_register_c, set_source_register_c: 46, 39;
+        pub cc_flag, set_cc_flag: 47;
+        pub a_signed_flag, set_a_signed_flag: 48;
+        pub b_signed_flag, set_b_signed_flag: 49;
+        pub u8, from into VideoSecondaryOperation, secondary_operation, set_secondary_operation: 53, 51;
+        pub sat_flag, set_sat_flag: 55;
+    }
+}
+
+// NOTE: Used by Opcode::VADD_IMM, Opcode::VABSDIFF_IMM, Opcode::VSHL_IMM and Opcode::VSHR_IMM.
+instruction_layout! {
+    pub struct VideoImmInstruction {
+        pub u8, destination_register, set_destination_register: 7, 0;
+        pub u8, source_register_a, set_source_register_a: 15, 8;
+        source_predicate;
+        pub u16, imm16, set_imm16: 35, 20;
+        pub u8, from into VideoSelector, a_selector, set_a_selector: 38, 36;
+        pub u8, source_register_c, set_source_register_c: 46, 39;
+        pub cc_flag, set_cc_flag: 47;
+        pub a_signed_flag, set_a_signed_flag: 48;
+        pub b_signed_flag, set_b_signed_flag: 49;
+        pub u8, from into VideoSecondaryOperation, secondary_operation, set_secondary_operation: 53, 51;
+        pub sat_flag, set_sat_flag: 55;
+    }
+}
+
+instruction_layout! {
+    pub struct VmnmxInstruction {
+        pub u8, destination_register, set_destination_register: 7, 0;
+        pub u8, source_register_a, set_source_register_a: 15, 8;
+        source_predicate;
+        pub u8, source_register_b, set_source_register_b: 27, 20;
+        pub u8, from into VideoSelector, b_selector, set_b_selector: 30, 28;
+        pub u8, from into VideoSelector, a_selector, set_a_selector: 38, 36;
+        pub u8, source_register_c, set_source_register_c: 46, 39;
+        pub cc_flag, set_cc_flag: 47;
+        pub a_signed_flag, set_a_signed_flag: 48;
+        pub b_signed_flag, set_b_signed_flag: 49;
+        pub u8, from into VideoSecondaryOperation, secondary_operation, set_secondary_operation: 53, 51;
+        pub sat_flag, set_sat_flag: 55;
+        pub mx_flag, set_mx_flag: 56;
+    }
+}
+
+instruction_layout! {
+    pub struct VmnmxImmInstruction {
+        pub u8, destination_register, set_destination_register: 7, 0;
+        pub u8, source_register_a, set_source_register_a: 15, 8;
+        source_predicate;
+        pub u16, imm16, set_imm16: 35, 20;
+        pub u8, from into VideoSelector, a_selector, set_a_selector: 38, 36;
+        pub u8, source_register_c, set_source_register_c: 46, 39;
+        pub cc_flag, set_cc_flag: 47;
+        pub a_signed_flag, set_a_signed_flag: 48;
+        pub b_signed_flag, set_b_signed_flag: 49;
+        pub u8, from into VideoSecondaryOperation, secondary_operation, set_secondary_operation: 53, 51;
+        pub sat_flag, set_sat_flag: 55;
+        pub mx_flag, set_mx_flag: 56;
+    }
+}
+
+instruction_layout! {
+    pub struct VmadInstruction {
+        pub u8, destination_register, set_destination_register: 7, 0;
+        pub u8, source_register_a, set_source_register_a: 15, 8;
+        source_predicate;
+        pub u8, source_register_b, set_source_register_b: 27, 20;
+        pub u8, from into VideoSelector, b_selector, set_b_selector: 30, 28;
+        pub u8, from into VideoSelector, a_selector, set_a_selector: 38, 36;
+        pub u8, source_register_c, set_source_register_c: 46, 39;
+        pub cc_flag, set_cc_flag: 47;
+        pub a_signed_flag, set_a_signed_flag: 48;
+        pub b_signed_flag, set_b_signed_flag: 49;
+        pub u8, from into VideoScale, scale, set_scale: 52, 51;
+        pub sat_flag, set_sat_flag: 55;
+    }
+}
+
+instruction_layout! {
+    pub struct VmadImmInstruction {
+        pub u8, destination_register, set_destination_register: 7, 0;
+        pub u8, source_register_a, set_source_register_a: 15, 8;
+        source_predicate;
+        pub u16, imm16, set_imm16: 35, 20;
+        pub u8, from into VideoSelector, a_selector, set_a_selector: 38, 36;
+        pub u8, source_register_c, set_source_register_c: 46, 39;
+        pub cc_flag, set_cc_flag: 47;
+        pub a_signed_flag, set_a_signed_flag: 48;
+        pub b_signed_flag, set_b_signed_flag: 49;
+        pub u8, from into VideoScale, scale, set_scale: 52, 51;
+        pub sat_flag, set_sat_flag: 55;
+    }
+}
+
+instruction_layout! {
+    pub struct VsetInstruction {
+        pub u8, destination_register, set_destination_register: 7, 0;
+        pub u8, source_register_a, set_source_register_a: 15, 8;
+        source_predicate;
+        pub u8, source_register_b, set_source_register_b: 27, 20;
+        pub u8, from into VideoSelector, b_selector, set_b_selector: 30, 28;
+        pub u8, from into VideoSelector, a_selector, set_a_selector: 38, 36;
+        pub u8, source_register_c, set_source_register_c: 46, 39;
+        pub cc_flag, set_cc_flag: 47;
+        pub a_signed_flag, set_a_signed_flag: 48;
+        pub b_signed_flag, set_b_signed_flag: 49;
+        pub u8, from into VideoSecondaryOperation, secondary_operation, set_secondary_operation: 53, 51;
+        pub u8, from into VideoCompareOperation, compare_operation, set_compare_operation: 56, 54;
+    }
+}
+
+instruction_layout! {
+    pub struct VsetImmInstruction {
+        pub u8, destination_register, set_destination_register: 7, 0;
+        pub u8, source_register_a, set_source_register_a: 15, 8;
+        source_predicate;
+        pub u16, imm16, set_imm16: 35, 20;
+        pub u8, from into VideoSelector, a_selector, set_a_selector: 38, 36;
+        pub u8, source_register_c, set_source_register_c: 46, 39;
+        pub cc_flag, set_cc_flag: 47;
+        pub a_signed_flag, set_a_signed_flag: 48;
+        pub b_signed_flag, set_b_signed_flag: 49;
+        pub u8, from into VideoSecondaryOperation, secondary_operation, set_secondary_operation: 53, 51;
+        pub u8, from into VideoCompareOperation, compare_operation, set_compare_operation: 56, 54;
+    }
+}
+
+instruction_layout! {
+    pub struct VsetpInstruction {
+        pub u8, destination_predicate_register_b, set_destination_predicate_register_b: 2, 0;
+        pub u8, destination_predicate_register_a, set_destination_predicate_register_a: 5, 3;
+        pub u8, source_register_a, set_source_register_a: 15, 8;
+        source_predicate;
+        pub u8, source_register_b, set_source_register_b: 27, 20;
+        pub u8, from into VideoSelector, b_selector, set_b_selector: 30, 28;
+        pub u8, from into VideoSelector, a_selector, set_a_selector: 38, 36;
+        pub u8, combine_predicate_register, set_combine_predicate_register: 41, 39;
+        pub invert_combine_predicate, set_invert_combine_predicate: 42;
+        pub u8, from into VideoCompareOperation, compare_operation, set_compare_operation: 45, 43;
+        pub u8, from into PredicateOperation, predicate_operation, set_predicate_operation: 47, 46;
+        pub a_signed_flag, set_a_signed_flag: 48;
+        pub b_signed_flag, set_b_signed_flag: 49;
+    }
+}
+
+instruction_layout! {
+    pub struct VsetpImmInstruction {
+        pub u8, destination_predicate_register_b, set_destination_predicate_register_b: 2, 0;
+        pub u8, destination_predicate_register_a, set_destination_predicate_register_a: 5, 3;
+        pub u8, source_register_a, set_source_register_a: 15, 8;
+        source_predicate;
+        pub u16, imm16, set_imm16: 35, 20;
+        pub u8, from into VideoSelector, a_selector, set_a_selector: 38, 36;
+        pub u8, combine_predicate_register, set_combine_predicate_register: 41, 39;
+        pub invert_combine_predicate, set_invert_combine_predicate: 42;
+        pub u8, from into VideoCompareOperation, compare_operation, set_compare_operation: 45, 43;
+        pub u8, from into PredicateOperation, predicate_operation, set_predicate_operation: 47, 46;
+        pub a_signed_flag, set_a_signed_flag: 48;
+        pub b_signed_flag, set_b_signed_flag: 49;
     }
 }
 
+// NOTE: the opcode bits of an instruction are every bit of the upper word not used by its layout.
+opcodes! {
+    AL2P => Al2pInstruction(0xefa0_0000_0000_0000, 0xfffe_0ffe_0000_0000),
+    ALD => AldInstruction(0xefd8_0000_0000_0000, 0xfffe_007e_0000_0000),
+    AST => AstInstruction(0xeff0_0000_0000_0000, 0xfffe_007f_0000_0000),
+    ATOM => AtomInstruction(0xed00_0000_0000_0000, 0xff00_0000_0000_0000),
+    ATOMS => AtomsIntruction(0xec00_0000_0000_0000, 0xff00_0000_0000_0000),
+    ATOMS_CAS => AtomsCasIntruction(0xee00_0000_0000_0000, 0xff80_0000_0000_0000),
+    ATOM_CAS => AtomCasInstruction(0xeef0_0000_0000_0000, 0xfffc_0000_0000_0000),
+    B2R => B2RInstruction(0xf0b8_0000_0000_0000, 0xffff_8ffc_0000_0000),
+    BAR => BarInstruction(0xf0a8_0000_0000_0000, 0xffff_e060_0000_0000),
+    BRA => BraInstruction(0xe240_0000_0000_0000, 0xffff_f000_0000_0000),
+    BRK => RetInstruction(0xe340_0000_0000_0000, 0xffff_ffff_0000_0000),
+    CCTL => CctlInstruction(0xef60_0000_0000_0000, 0xfff0_0000_0000_0000),
+    CCTLL => CctllInstruction(0xef80_0000_0000_0000, 0xffff_c000_0000_0000),
+    CONT => RetInstruction(0xe350_0000_0000_0000, 0xffff_ffff_0000_0000),
+    DEPBAR => DepbarInstruction(0xf0f0_0000_0000_0000, 0xffff_ffff_0000_0000),
+    EXIT => ExitInstruction(0xe300_0000_0000_0000, 0xffff_ffff_0000_0000),
+    GETLMEMBASE => GetLMEMBASEInstruction(0xe2d0_0000_0000_0000, 0xffff_ffff_0000_0000),
+    HADD2 => Hadd2Instruction(0x5d10_0000_0000_0000, 0xfff8_677e_0000_0000),
+    HADD2_IMM => Hadd2ImmInstruction(0x7a00_0000_0000_0000, 0xfee8_6700_0000_0000),
+    HFMA2 => Hfma2Instruction(0x5d00_0000_0000_0000, 0xfff8_0006_0000_0000),
+    HFMA2_IMM => Hfma2ImmInstruction(0x7000_0000_0000_0000, 0xf880_0000_0000_0000),
+    HMUL2 => Hmul2Instruction(0x5d08_0000_0000_0000, 0xfff8_667e_0000_0000),
+    HMUL2_IMM => Hmul2ImmInstruction(0x7800_0000_0000_0000, 0xfee8_6600_0000_0000),
+    HSET2 => Hset2Instruction(0x5d18_0000_0000_0000, 0xfff8_0007_0000_0000),
+    HSET2_IMM => Hset2ImmInstruction(0x7c00_0000_0000_0000, 0xfe80_0000_0000_0000),
+    HSETP2 => Hsetp2Instruction(0x5d20_0000_0000_0000, 0xfffc_0007_0000_0000),
+    HSETP2_IMM => Hsetp2ImmInstruction(0x7e00_0000_0000_0000, 0xfec0_0000_0000_0000),
+    IDE => IdeInstruction(0xe390_0000_0000_0000, 0xffff_fff0_0000_0000),
+    IPA => IpaInstruction(0xe000_0000_0000_0000, 0xff07_8000_0000_0000),
+    ISBERD => IsberdInstruction(0xefd0_0000_0000_0000, 0xfffe_7ff8_0000_0000),
+    ISBEWR => IsbewrInstruction(0xffc0_0000_0000_0000, 0xfffe_7ff9_0000_0000),
+    KIL => KilInstruction(0xe330_0000_0000_0000, 0xffff_ffff_0000_0000),
+    MEMBAR => MembarInstruction(0xef98_0000_0000_0000, 0xffff_ffff_0000_0000),
+    NOP => NopInstruction(0x50b0_0000_0000_0000, 0xffff_fff0_0000_0000),
+    OUT => OutInstruction(0xfbe0_0000_0000_0000, 0xffff_fe7f_0000_0000),
+    OUT_IMM => OutImmInstruction(0xf6e0_0000_0000_0000, 0xffff_fe7f_0000_0000),
+    PBK => FlowPushInstruction(0xe2a0_0000_0000_0000, 0xffff_f000_0000_0000),
+    PCNT => FlowPushInstruction(0xe2b0_0000_0000_0000, 0xffff_f000_0000_0000),
+    PIXLD => PixldInstruction(0xefe8_0000_0000_0000, 0xffff_8ffc_0000_0000),
+    PRET => FlowPushInstruction(0xe270_0000_0000_0000, 0xffff_f000_0000_0000),
+    RAM => RamInstruction(0xe380_0000_0000_0000, 0xffff_ffff_0000_0000),
+    RED => RedInstruction(0xebf8_0000_0000_0000, 0xfffe_0000_0000_0000),
+    RET => RetInstruction(0xe320_0000_0000_0000, 0xffff_ffff_0000_0000),
+    SAM => SamInstruction(0xe370_0000_0000_0000, 0xffff_ffff_0000_0000),
+    SETLMEMBASE => SetLMEMBASEInstruction(0xe2f0_0000_0000_0000, 0xffff_ffff_0000_0000),
+    SSY => FlowPushInstruction(0xe290_0000_0000_0000, 0xffff_f000_0000_0000),
+    SUATOM => SuatomInstruction(0xea00_0000_0000_0000, 0xfff9_8000_0000_0000),
+    SULD => SuldInstruction(0xeb08_0000_0000_0000, 0xffe8_0001_0000_0000),
+    SULD_B => SuldBindlessInstruction(0xeb00_0000_0000_0000, 0xffe9_8071_0000_0000),
+    SURED => SuredInstruction(0xeb40_0000_0000_0000, 0xfff9_8071_0000_0000),
+    SUST => SuldInstruction(0xeb28_0000_0000_0000, 0xffe8_0001_0000_0000),
+    SUST_B => SuldBindlessInstruction(0xeb20_0000_0000_0000, 0xffe9_8071_0000_0000),
+    SYNC => RetInstruction(0xf0f8_0000_0000_0000, 0xffff_ffff_0000_0000),
+    TEX => TexInstruction(0xc000_0000_0000_0000, 0xf800_0000_0000_0000),
+    TEXS => TexsInstruction(0xd000_0000_0000_0000, 0xf600_0000_0000_0000),
+    TEX_B => TexBindlessInstruction(0xde80_0000_0000_0000, 0xffc1_fe00_0000_0000),
+    TLD => TldInstruction(0xdc38_0000_0000_0000, 0xff78_0000_0000_0000),
+    TLD4 => Tld4Instruction(0xc838_0000_0000_0000, 0xfc38_0000_0000_0000),
+    TLD4S => Tld4sInstruction(0xdf00_0000_0000_0000, 0xff40_0000_0000_0000),
+    TLD4_B => Tld4BindlessInstruction(0xdef8_0000_0000_0000, 0xfff9_ff00_0000_0000),
+    TLDS => TexsInstruction(0xd200_0000_0000_0000, 0xf600_0000_0000_0000),
+    TLD_B => TldInstruction(0xdd38_0000_0000_0000, 0xff78_0000_0000_0000),
+    TMML => TmmlInstruction(0xdf58_0000_0000_0000, 0xfffc_0000_0000_0000),
+    TMML_B => TmmlInstruction(0xdf60_0000_0000_0000, 0xfffc_0000_0000_0000),
+    TXD => TxdInstruction(0xde38_0000_0000_0000, 0xfffc_0000_0000_0000),
+    TXD_B => TxdInstruction(0xde78_0000_0000_0000, 0xfffc_0000_0000_0000),
+    TXQ => TxqInstruction(0xdf48_0000_0000_0000, 0xfffc_0008_0000_0000),
+    TXQ_B => TxqInstruction(0xdf50_0000_0000_0000, 0xfffc_0008_0000_0000),
+    VABSDIFF => VideoInstruction(0x5404_0000_0000_0000, 0xff44_000f_0000_0000),
+    VABSDIFF_IMM => VideoImmInstruction(0x5400_0000_0000_0000, 0xff44_0000_0000_0000),
+    VADD => VideoInstruction(0x2004_0000_0000_0000, 0xff44_000f_0000_0000),
+    VADD_IMM => VideoImmInstruction(0x2000_0000_0000_0000, 0xff44_0000_0000_0000),
+    VMAD => VmadInstruction(0x5f04_0000_0000_0000, 0xff64_000f_0000_0000),
+    VMAD_IMM => VmadImmInstruction(0x5f00_0000_0000_0000, 0xff64_0000_0000_0000),
+    VMNMX => VmnmxInstruction(0x3a04_0000_0000_0000, 0xfe44_000f_0000_0000),
+    VMNMX_IMM => VmnmxImmInstruction(0x3a00_0000_0000_0000, 0xfe44_0000_0000_0000),
+    VSET => VsetInstruction(0x4004_0000_0000_0000, 0xfe04_000f_0000_0000),
+    VSETP => VsetpInstruction(0x50f4_0000_0000_0000, 0xfffc_000f_0000_0000),
+    VSETP_IMM => VsetpImmInstruction(0x50f0_0000_0000_0000, 0xfffc_0000_0000_0000),
+    VSET_IMM => VsetImmInstruction(0x4000_0000_0000_0000, 0xfe04_0000_0000_0000),
+    VSHL => VideoInstruction(0x5704_0000_0000_0000, 0xff44_000f_0000_0000),
+    VSHL_IMM => VideoImmInstruction(0x5700_0000_0000_0000, 0xff44_0000_0000_0000),
+    VSHR => VideoInstruction(0x5604_0000_0000_0000, 0xff44_000f_0000_0000),
+    VSHR_IMM => VideoImmInstruction(0x5600_0000_0000_0000, 0xff44_0000_0000_0000),
+}
+
 enum_with_val! {
     #[derive(PartialEq, Eq)]
     pub struct ControlCode(u8) {
@@ -391,12 +920,15 @@ enum_with_val! {
 }
 
 enum_with_val! {
-    #[derive(PartialEq, Eq)]
+    #[derive(PartialEq, Eq, Clone, Copy)]
     pub struct AtomPrimitiveType(u8) {
         U32 = 0,
         S32 = 1,
         U64 = 2,
         F32 = 3,
+        // Only available on SM60 and later.
+        F16X2 = 4,
+        S64 = 5,
     }
 }
 
@@ -416,7 +948,7 @@ enum_with_val! {
 }
 
 enum_with_val! {
-    #[derive(PartialEq, Eq)]
+    #[derive(PartialEq, Eq, Clone, Copy)]
     pub struct AtomOperation(u8) {
         ADD = 0,
         MIN = 1,
@@ -433,6 +965,29 @@ enum_with_val! {
     }
 }
 
+impl AtomPrimitiveType {
+    pub fn is_float(&self) -> bool {
+        *self == AtomPrimitiveType::F32 || *self == AtomPrimitiveType::F16X2
+    }
+}
+
+impl AtomOperation {
+    /// Check if the operation can be performed on the given primitive type.
+    ///
+    /// Opcode::RED shares its operations with Opcode::ATOM but cannot exchange values.
+    pub fn is_valid(&self, type_size: &AtomPrimitiveType, is_reduction: bool) -> bool {
+        match *self {
+            AtomOperation::ADD => true,
+            AtomOperation::MIN | AtomOperation::MAX => !type_size.is_float(),
+            AtomOperation::INC | AtomOperation::DEC => *type_size == AtomPrimitiveType::U32,
+            AtomOperation::AND | AtomOperation::OR | AtomOperation::XOR => !type_size.is_float(),
+            AtomOperation::EXCH => !is_reduction && !type_size.is_float(),
+            AtomOperation::SAFE_ADD => !is_reduction && *type_size == AtomPrimitiveType::U64,
+            _ => false,
+        }
+    }
+}
+
 enum_with_val! {
     #[derive(PartialEq, Eq)]
     pub struct AtomicCasPrimitiveType(u8) {
@@ -458,4 +1013,320 @@ enum_with_val! {
         RESULT = 2,
         SYNC = 3 // TODO: seems not valid?
     }
-}
\ No newline at end of file
+}
+
+enum_with_val! {
+    #[derive(PartialEq, Eq)]
+    pub struct BarMode(u8) {
+        SYNC = 0,
+        ARV = 1,
+        RED = 2,
+        SCAN = 3,
+        SYNCALL = 4
+    }
+}
+
+enum_with_val! {
+    #[derive(PartialEq, Eq)]
+    pub struct BarReductionOperation(u8) {
+        POPC = 0,
+        AND = 1,
+        OR = 2
+    }
+}
+
+enum_with_val! {
+    #[derive(PartialEq, Eq)]
+    pub struct MembarLevel(u8) {
+        CTA = 0,
+        GL = 1,
+        SYS = 2,
+        VC = 3
+    }
+}
+
+enum_with_val! {
+    #[derive(PartialEq, Eq)]
+    pub struct CacheOperation(u8) {
+        QRY1 = 0,
+        PF1 = 1,
+        PF1_5 = 2,
+        PF2 = 3,
+        WB = 4,
+        IV = 5,
+        IVALL = 6,
+        RS = 7,
+        RSLB = 9
+    }
+}
+
+enum_with_val! {
+    #[derive(PartialEq, Eq)]
+    pub struct CacheType(u8) {
+        // TODO: check on hw what the default cache level is.
+        D = 0,
+        U = 1,
+        C = 2,
+        I = 3,
+        T = 4
+    }
+}
+
+enum_with_val! {
+    #[derive(PartialEq, Eq, Clone, Copy)]
+    pub struct TextureType(u8) {
+        TEXTURE_1D = 0,
+        TEXTURE_1D_ARRAY = 1,
+        TEXTURE_2D = 2,
+        TEXTURE_2D_ARRAY = 3,
+        TEXTURE_3D = 4,
+        TEXTURE_3D_ARRAY = 5,
+        TEXTURE_CUBE = 6,
+        TEXTURE_CUBE_ARRAY = 7
+    }
+}
+
+enum_with_val! {
+    #[derive(PartialEq, Eq, Clone, Copy)]
+    pub struct TextureLodMode(u8) {
+        NONE = 0,
+        LZ = 1,
+        LB = 2,
+        LL = 3,
+        LBA = 6,
+        LLA = 7
+    }
+}
+
+enum_with_val! {
+    #[derive(PartialEq, Eq)]
+    pub struct TextureComponent(u8) {
+        R = 0,
+        G = 1,
+        B = 2,
+        A = 3
+    }
+}
+
+enum_with_val! {
+    #[derive(PartialEq, Eq)]
+    pub struct TextureGatherOffset(u8) {
+        NONE = 0,
+        AOFFI = 1,
+        PTP = 2
+    }
+}
+
+enum_with_val! {
+    #[derive(PartialEq, Eq)]
+    pub struct TextureQuery(u8) {
+        DIMENSION = 1,
+        TEXTURE_TYPE = 2,
+        SAMPLE_POSITION = 5,
+        FILTER = 16,
+        LOD = 18,
+        WRAP = 20,
+        BORDER_COLOR = 22
+    }
+}
+
+/// Component masks that can be written by the compressed texture instructions when
+/// only destination_register_a is used, indexed by component_swizzle.
+pub const TEXTURE_SINGLE_DESTINATION_MASKS: [u8; 8] = [0x1, 0x2, 0x4, 0x8, 0x3, 0x9, 0xA, 0xC];
+
+/// Component masks that can be written by the compressed texture instructions when
+/// both destination registers are used, indexed by component_swizzle.
+pub const TEXTURE_DUAL_DESTINATION_MASKS: [u8; 5] = [0x7, 0xB, 0xD, 0xE, 0xF];
+
+/// Texture types and LOD modes supported by Opcode::TEXS, indexed by encoding.
+///
+/// The last element of every entry is the depth compare flag.
+pub const TEXS_ENCODINGS: [(TextureType, TextureLodMode, bool); 14] = [
+    (TextureType::TEXTURE_1D, TextureLodMode::LZ, false),
+    (TextureType::TEXTURE_2D, TextureLodMode::NONE, false),
+    (TextureType::TEXTURE_2D, TextureLodMode::LZ, false),
+    (TextureType::TEXTURE_2D, TextureLodMode::LL, false),
+    (TextureType::TEXTURE_2D, TextureLodMode::NONE, true),
+    (TextureType::TEXTURE_2D, TextureLodMode::LL, true),
+    (TextureType::TEXTURE_2D, TextureLodMode::LZ, true),
+    (TextureType::TEXTURE_2D_ARRAY, TextureLodMode::NONE, false),
+    (TextureType::TEXTURE_2D_ARRAY, TextureLodMode::LZ, false),
+    (TextureType::TEXTURE_2D_ARRAY, TextureLodMode::LZ, true),
+    (TextureType::TEXTURE_3D, TextureLodMode::NONE, false),
+    (TextureType::TEXTURE_3D, TextureLodMode::LZ, false),
+    (TextureType::TEXTURE_CUBE, TextureLodMode::NONE, false),
+    (TextureType::TEXTURE_CUBE, TextureLodMode::LL, false),
+];
+
+/// Texture types and LOD modes supported by Opcode::TLDS.
+///
+/// Every entry is made of the encoding, the texture type, the LOD mode, the AOFFI flag and the MS flag.
+pub const TLDS_ENCODINGS: [(u8, TextureType, TextureLodMode, bool, bool); 9] = [
+    (0, TextureType::TEXTURE_1D, TextureLodMode::LZ, false, false),
+    (1, TextureType::TEXTURE_1D, TextureLodMode::LL, false, false),
+    (2, TextureType::TEXTURE_2D, TextureLodMode::LZ, false, false),
+    (4, TextureType::TEXTURE_2D, TextureLodMode::LZ, true, false),
+    (5, TextureType::TEXTURE_2D, TextureLodMode::LL, false, false),
+    (6, TextureType::TEXTURE_2D, TextureLodMode::LZ, false, true),
+    (7, TextureType::TEXTURE_3D, TextureLodMode::LZ, false, false),
+    (
+        8,
+        TextureType::TEXTURE_2D_ARRAY,
+        TextureLodMode::LZ,
+        false,
+        false,
+    ),
+    (12, TextureType::TEXTURE_2D, TextureLodMode::LL, true, false),
+];
+
+enum_with_val! {
+    #[derive(PartialEq, Eq)]
+    pub struct SurfaceType(u8) {
+        SURFACE_1D = 0,
+        SURFACE_1D_BUFFER = 1,
+        SURFACE_1D_ARRAY = 2,
+        SURFACE_2D = 3,
+        SURFACE_2D_ARRAY = 4,
+        SURFACE_3D = 5
+    }
+}
+
+enum_with_val! {
+    #[derive(PartialEq, Eq)]
+    pub struct SurfaceClampMode(u8) {
+        IGN = 0,
+        SDCL = 1,
+        TRAP = 2
+    }
+}
+
+enum_with_val! {
+    #[derive(PartialEq, Eq)]
+    pub struct SurfaceSize(u8) {
+        U8 = 0,
+        S8 = 1,
+        U16 = 2,
+        S16 = 3,
+        B32 = 4,
+        B64 = 5,
+        B128 = 6
+    }
+}
+
+enum_with_val! {
+    #[derive(PartialEq, Eq)]
+    pub struct IpaInterpolationMode(u8) {
+        PASS = 0,
+        MULTIPLY = 1,
+        CONSTANT = 2,
+        SC = 3
+    }
+}
+
+enum_with_val! {
+    #[derive(PartialEq, Eq)]
+    pub struct IpaSampleMode(u8) {
+        DEFAULT = 0,
+        CENTROID = 1,
+        OFFSET = 2
+    }
+}
+
+enum_with_val! {
+    #[derive(PartialEq, Eq)]
+    pub struct PixelLoadMode(u8) {
+        COUNT = 0,
+        COVMASK = 1,
+        COVERED = 2,
+        OFFSET = 3,
+        CENTROID_OFFSET = 4,
+        MY_INDEX = 5
+    }
+}
+
+enum_with_val! {
+    #[derive(PartialEq, Eq)]
+    pub struct OutMode(u8) {
+        EMIT = 1,
+        CUT = 2,
+        EMIT_THEN_CUT = 3
+    }
+}
+
+enum_with_val! {
+    #[derive(PartialEq, Eq)]
+    pub struct IsbeMode(u8) {
+        DEFAULT = 0,
+        PATCH = 1,
+        PRIM = 2,
+        ATTR = 3
+    }
+}
+
+enum_with_val! {
+    #[derive(PartialEq, Eq)]
+    pub struct IsbeShift(u8) {
+        DEFAULT = 0,
+        U16 = 1,
+        B32 = 2
+    }
+}
+
+enum_with_val! {
+    #[derive(PartialEq, Eq, Clone, Copy)]
+    pub struct VideoSelector(u8) {
+        B0 = 0,
+        B1 = 1,
+        B2 = 2,
+        B3 = 3,
+        H0 = 4,
+        H1 = 5,
+        W = 6
+    }
+}
+
+enum_with_val! {
+    #[derive(PartialEq, Eq)]
+    pub struct VideoSecondaryOperation(u8) {
+        NONE = 0,
+        MRG_16H = 1,
+        MRG_16L = 2,
+        MRG_8B0 = 3,
+        MRG_8B2 = 4,
+        ACC = 5,
+        MIN = 6,
+        MAX = 7
+    }
+}
+
+enum_with_val! {
+    #[derive(PartialEq, Eq)]
+    pub struct VideoScale(u8) {
+        NONE = 0,
+        SHR_7 = 1,
+        SHR_15 = 2
+    }
+}
+
+enum_with_val! {
+    #[derive(PartialEq, Eq)]
+    pub struct VideoCompareOperation(u8) {
+        F = 0,
+        LT = 1,
+        EQ = 2,
+        LE = 3,
+        GT = 4,
+        NE = 5,
+        GE = 6,
+        T = 7
+    }
+}
+
+enum_with_val! {
+    #[derive(PartialEq, Eq)]
+    pub struct PredicateOperation(u8) {
+        AND = 0,
+        OR = 1,
+        XOR = 2
+    }
+}
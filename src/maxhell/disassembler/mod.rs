@@ -0,0 +1,90 @@
+use std::fmt;
+
+use super::definition::*;
+use super::util::{FieldDefinition, FieldKind};
+
+const SOURCE_PREDICATE_REGISTER: &str = "source_predicate_register";
+const INVERT_SOURCE_PREDICATE: &str = "invert_source_predicate";
+
+/// Disassemble an instruction, returns None if the opcode is unknown.
+pub fn disassemble(instruction: u64) -> Option<String> {
+    DecodedInstruction::decode(instruction).map(|decoded| decoded.to_string())
+}
+
+fn write_field_value(f: &mut fmt::Formatter, field: &FieldDefinition, value: u64) -> fmt::Result {
+    match field.kind() {
+        FieldKind::Register if value == 0xFF => write!(f, "RZ"),
+        FieldKind::Register => write!(f, "R{}", value),
+        FieldKind::Predicate if value == 7 => write!(f, "PT"),
+        FieldKind::Predicate => write!(f, "P{}", value),
+        FieldKind::Enum => match field.variant_name(value) {
+            Some(name) => write!(f, "{}", name),
+            None => write!(f, "0x{:x}", value),
+        },
+        FieldKind::Flag | FieldKind::Immediate => {
+            let value = field.sign_extend(value);
+
+            if value < 0 {
+                write!(f, "-0x{:x}", value.unsigned_abs())
+            } else {
+                write!(f, "0x{:x}", value)
+            }
+        }
+    }
+}
+
+impl fmt::Display for DecodedInstruction {
+    /// Print the instruction as `@!P0 MNEMONIC field=value, flag, ...`.
+    ///
+    /// Fields are printed in the order of the layout, flags are only printed when set
+    /// and the predicate guard is omitted when the instruction always executes.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let opcode = self.opcode();
+        let raw = self.raw();
+        let fields = opcode.fields();
+
+        let predicate = fields
+            .iter()
+            .find(|field| field.name == SOURCE_PREDICATE_REGISTER);
+        let invert_predicate = fields
+            .iter()
+            .find(|field| field.name == INVERT_SOURCE_PREDICATE)
+            .map(|field| field.extract(raw) != 0)
+            .unwrap_or(false);
+
+        if let Some(predicate) = predicate {
+            let value = predicate.extract(raw);
+
+            if value != 7 || invert_predicate {
+                write!(f, "@{}", if invert_predicate { "!" } else { "" })?;
+                write_field_value(f, predicate, value)?;
+                write!(f, " ")?;
+            }
+        }
+
+        write!(f, "{}", opcode.name())?;
+
+        let mut separator = " ";
+
+        for field in fields {
+            if field.name == SOURCE_PREDICATE_REGISTER || field.name == INVERT_SOURCE_PREDICATE {
+                continue;
+            }
+
+            let value = field.extract(raw);
+
+            if field.kind() == FieldKind::Flag {
+                if value != 0 {
+                    write!(f, "{}{}", separator, field.name)?;
+                    separator = ", ";
+                }
+            } else {
+                write!(f, "{}{}=", separator, field.name)?;
+                write_field_value(f, field, value)?;
+                separator = ", ";
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,172 @@
+use std::error::Error;
+use std::fmt;
+
+use super::definition::*;
+use super::program::Program;
+use super::target::Target;
+
+#[cfg(test)]
+mod tests;
+
+/// Number of words in a group made of a scheduling control word and its instructions.
+const GROUP_WORDS: usize = 4;
+
+/// Error returned when importing a shader dump.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum DumpError {
+    /// The token at the given line isn't a hexadecimal word.
+    InvalidToken(usize, String),
+    /// A 64-bit word at the given line follows an unpaired 32-bit word.
+    MisalignedWord(usize),
+    /// The dump ends with an unpaired 32-bit word.
+    OddWordCount,
+    /// The instruction at the given index doesn't match any opcode.
+    InvalidInstruction(usize, u64),
+    /// The instruction at the given index has bits that the assembler cannot set.
+    UnrepresentableInstruction(usize, u64),
+}
+
+impl fmt::Display for DumpError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DumpError::InvalidToken(line, token) => {
+                write!(f, "line {}: invalid word {}", line, token)
+            }
+            DumpError::MisalignedWord(line) => {
+                write!(
+                    f,
+                    "line {}: 64-bit word following a single 32-bit word",
+                    line
+                )
+            }
+            DumpError::OddWordCount => write!(f, "the dump ends with a single 32-bit word"),
+            DumpError::InvalidInstruction(index, instruction) => {
+                write!(f, "invalid instruction 0x{:016x} at {}", instruction, index)
+            }
+            DumpError::UnrepresentableInstruction(index, instruction) => write!(
+                f,
+                "instruction 0x{:016x} at {} uses bits outside of its fields",
+                instruction, index
+            ),
+        }
+    }
+}
+
+impl Error for DumpError {}
+
+/// Whether the scheduling control words are part of a dump.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum Scheduling {
+    Present,
+    Absent,
+    /// Consider the scheduling control words present if every fourth word, starting from
+    /// the first one, isn't an instruction.
+    #[default]
+    Detect,
+}
+
+/// Parse a hex dump as printed by nouveau and envytools into 64-bit words.
+///
+/// Words are separated by whitespaces or commas and may be prefixed by `0x`. Words of
+/// 8 digits or less are 32-bit words paired in little-endian order, as printed by
+/// `NV50_PROG_DEBUG`. Lines can be commented with `#` or `//`.
+pub fn parse_dump(text: &str) -> Result<Vec<u64>, DumpError> {
+    let mut words = Vec::new();
+    let mut low_word = None;
+
+    for (index, line) in text.lines().enumerate() {
+        let line_number = index + 1;
+        let line = line.split("//").next().unwrap_or_default();
+        let line = line.split('#').next().unwrap_or_default();
+
+        for token in line
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|token| !token.is_empty())
+        {
+            let digits = token
+                .strip_prefix("0x")
+                .or_else(|| token.strip_prefix("0X"))
+                .unwrap_or(token);
+
+            let invalid_token = || DumpError::InvalidToken(line_number, String::from(token));
+
+            if digits.is_empty() || digits.len() > 16 {
+                return Err(invalid_token());
+            }
+
+            let value = u64::from_str_radix(digits, 16).map_err(|_| invalid_token())?;
+
+            if digits.len() > 8 {
+                if low_word.is_some() {
+                    return Err(DumpError::MisalignedWord(line_number));
+                }
+
+                words.push(value);
+            } else if let Some(low) = low_word.take() {
+                words.push(low | (value << 32));
+            } else {
+                low_word = Some(value);
+            }
+        }
+    }
+
+    if low_word.is_some() {
+        return Err(DumpError::OddWordCount);
+    }
+
+    Ok(words)
+}
+
+/// Check if the words of a dump look like they include the scheduling control words.
+pub fn has_scheduling_words(words: &[u64]) -> bool {
+    !words.is_empty()
+        && words
+            .iter()
+            .step_by(GROUP_WORDS)
+            .all(|word| Opcode::decode(*word).is_none())
+}
+
+/// Import a hex dump as a program.
+pub fn import_dump(
+    target: Target,
+    text: &str,
+    scheduling: Scheduling,
+) -> Result<Program, DumpError> {
+    let words = parse_dump(text)?;
+
+    let has_scheduling_words = match scheduling {
+        Scheduling::Present => true,
+        Scheduling::Absent => false,
+        Scheduling::Detect => has_scheduling_words(&words),
+    };
+
+    let mut program = Program::new(target);
+
+    // TODO: keep the scheduling control words once the program holds them.
+    for (index, word) in words.iter().enumerate() {
+        if !has_scheduling_words || index % GROUP_WORDS != 0 {
+            program.push(*word);
+        }
+    }
+
+    Ok(program)
+}
+
+/// Write a program as source that can be assembled again.
+pub fn to_source(program: &Program) -> Result<String, DumpError> {
+    let mut source = String::new();
+
+    for (index, instruction) in program.instructions().iter().enumerate() {
+        let decoded = DecodedInstruction::decode(*instruction)
+            .ok_or(DumpError::InvalidInstruction(index, *instruction))?;
+
+        // NOTE: the assembler can only set the bits covered by the opcode and the fields.
+        if decoded.opcode().encode(&decoded.field_values()) != *instruction {
+            return Err(DumpError::UnrepresentableInstruction(index, *instruction));
+        }
+
+        source.push_str(&format!("{} // 0x{:016x}\n", decoded, instruction));
+    }
+
+    Ok(source)
+}
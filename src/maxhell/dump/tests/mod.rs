@@ -0,0 +1,77 @@
+use super::*;
+use crate::maxhell::assembler::assemble_source;
+
+const TARGET: Target = Target::SM50;
+
+const SCHED: u64 = 0x001f_8000_fc00_07e0;
+const RAM: u64 = 0xe380_0000_0000_0000;
+const SAM: u64 = 0xe370_0000_0000_0000;
+const EXIT: u64 = 0xe300_0000_0007_000f;
+
+#[test]
+fn parses_64_bit_words() {
+    let text = "0x001f8000fc0007e0 0xe380000000000000\n\
+                e370000000000000, 0xe30000000007000f // EXIT\n";
+
+    assert_eq!(parse_dump(text), Ok(vec![SCHED, RAM, SAM, EXIT]));
+}
+
+#[test]
+fn parses_32_bit_words() {
+    // The format printed by NV50_PROG_DEBUG, low words first.
+    let text = "# program binary\n\
+                0xfc0007e0 0x001f8000 0x00000000 0xe3800000\n\
+                0x00000000 0xe3700000 0x0007000f 0xe3000000\n";
+
+    assert_eq!(parse_dump(text), Ok(vec![SCHED, RAM, SAM, EXIT]));
+}
+
+#[test]
+fn rejects_invalid_dumps() {
+    assert_eq!(
+        parse_dump("0xe380000000000000\n0xzz"),
+        Err(DumpError::InvalidToken(2, String::from("0xzz")))
+    );
+    assert_eq!(
+        parse_dump("0xfc0007e0 0xe380000000000000"),
+        Err(DumpError::MisalignedWord(1))
+    );
+    assert_eq!(parse_dump("0xfc0007e0"), Err(DumpError::OddWordCount));
+}
+
+#[test]
+fn detects_scheduling_words() {
+    let with_scheduling = format!("{:x} {:x} {:x} {:x}", SCHED, RAM, SAM, EXIT);
+    let without_scheduling = format!("{:x} {:x} {:x}", RAM, SAM, EXIT);
+
+    for text in &[with_scheduling, without_scheduling] {
+        let program = import_dump(TARGET, text, Scheduling::Detect).unwrap();
+
+        assert_eq!(program.instructions(), [RAM, SAM, EXIT]);
+    }
+
+    let program = import_dump(TARGET, &format!("{:x} {:x}", RAM, SAM), Scheduling::Present);
+
+    assert_eq!(program.unwrap().instructions(), [SAM]);
+}
+
+#[test]
+fn source_assembles_again() {
+    let text = format!("{:x} {:x} {:x} {:x}", SCHED, RAM, SAM, EXIT);
+    let program = import_dump(TARGET, &text, Scheduling::Detect).unwrap();
+    let source = to_source(&program).unwrap();
+
+    assert_eq!(
+        assemble_source(TARGET, &source).unwrap().instructions(),
+        program.instructions()
+    );
+
+    // Bits 6 and 7 of EXIT aren't covered by any field.
+    let mut program = Program::new(TARGET);
+    program.push(0xe300_0000_0007_00cf);
+
+    assert!(matches!(
+        to_source(&program),
+        Err(DumpError::UnrepresentableInstruction(0, _))
+    ));
+}
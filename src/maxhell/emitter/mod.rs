@@ -0,0 +1,161 @@
+use std::convert::TryInto;
+use std::error::Error;
+use std::fmt;
+use std::io::{self, Write};
+use std::str::FromStr;
+
+#[cfg(test)]
+mod tests;
+
+/// Writes the binary of a shader to an output.
+pub trait Emitter {
+    fn emit(&self, output: &mut dyn Write, binary: &[u8]) -> io::Result<()>;
+}
+
+/// Split a binary in little-endian 64-bit words.
+///
+/// NOTE: the last word is padded with zeros, shader binaries are always made of whole words.
+fn words(binary: &[u8]) -> impl Iterator<Item = u64> + '_ {
+    binary.chunks(8).map(|chunk| {
+        let mut word = [0; 8];
+
+        word[..chunk.len()].copy_from_slice(chunk);
+
+        u64::from_le_bytes(word.as_slice().try_into().unwrap())
+    })
+}
+
+/// Write the binary as it is.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RawEmitter;
+
+impl Emitter for RawEmitter {
+    fn emit(&self, output: &mut dyn Write, binary: &[u8]) -> io::Result<()> {
+        output.write_all(binary)
+    }
+}
+
+/// Write one hexadecimal word per line.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct HexEmitter;
+
+impl Emitter for HexEmitter {
+    fn emit(&self, output: &mut dyn Write, binary: &[u8]) -> io::Result<()> {
+        for word in words(binary) {
+            writeln!(output, "{:016x}", word)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Write a C header declaring an array of words.
+#[derive(Debug, Clone)]
+pub struct CHeaderEmitter {
+    pub name: String,
+}
+
+impl Default for CHeaderEmitter {
+    fn default() -> Self {
+        CHeaderEmitter {
+            name: String::from("shader"),
+        }
+    }
+}
+
+impl Emitter for CHeaderEmitter {
+    fn emit(&self, output: &mut dyn Write, binary: &[u8]) -> io::Result<()> {
+        writeln!(output, "// Generated by maxhell-as, do not edit.")?;
+        writeln!(output, "#pragma once")?;
+        writeln!(output)?;
+        writeln!(output, "#include <stdint.h>")?;
+        writeln!(output)?;
+        writeln!(output, "static const uint64_t {}[] = {{", self.name)?;
+
+        for word in words(binary) {
+            writeln!(output, "    0x{:016x},", word)?;
+        }
+
+        writeln!(output, "}};")
+    }
+}
+
+/// Write a Rust module declaring a constant array of words.
+#[derive(Debug, Clone)]
+pub struct RustEmitter {
+    pub name: String,
+}
+
+impl Default for RustEmitter {
+    fn default() -> Self {
+        RustEmitter {
+            name: String::from("SHADER"),
+        }
+    }
+}
+
+impl Emitter for RustEmitter {
+    fn emit(&self, output: &mut dyn Write, binary: &[u8]) -> io::Result<()> {
+        writeln!(output, "// Generated by maxhell-as, do not edit.")?;
+        writeln!(
+            output,
+            "pub const {}: [u64; {}] = [",
+            self.name,
+            binary.len().div_ceil(8)
+        )?;
+
+        for word in words(binary) {
+            writeln!(output, "    0x{:016x},", word)?;
+        }
+
+        writeln!(output, "];")
+    }
+}
+
+/// The output formats selectable from the command line.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum OutputFormat {
+    #[default]
+    Raw,
+    Hex,
+    C,
+    Rust,
+}
+
+impl OutputFormat {
+    /// The emitter writing this format, with the default names for the declarations.
+    pub fn emitter(self) -> Box<dyn Emitter> {
+        match self {
+            OutputFormat::Raw => Box::new(RawEmitter),
+            OutputFormat::Hex => Box::new(HexEmitter),
+            OutputFormat::C => Box::new(CHeaderEmitter::default()),
+            OutputFormat::Rust => Box::new(RustEmitter::default()),
+        }
+    }
+}
+
+/// Error returned when parsing an unknown output format name.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ParseOutputFormatError(String);
+
+impl fmt::Display for ParseOutputFormatError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Unknown output format \"{}\"", self.0)
+    }
+}
+
+impl Error for ParseOutputFormatError {}
+
+impl FromStr for OutputFormat {
+    type Err = ParseOutputFormatError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "raw" | "bin" => Ok(OutputFormat::Raw),
+            "hex" => Ok(OutputFormat::Hex),
+            "c" => Ok(OutputFormat::C),
+            "rust" | "rs" => Ok(OutputFormat::Rust),
+            _ => Err(ParseOutputFormatError(String::from(value))),
+        }
+    }
+}
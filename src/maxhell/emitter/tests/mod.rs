@@ -0,0 +1,71 @@
+use super::*;
+
+const BINARY: [u8; 16] = [
+    0xe0, 0x07, 0x00, 0xfc, 0x00, 0x80, 0x1f, 0x00, 0x0f, 0x00, 0x07, 0x00, 0x00, 0x00, 0x00, 0xe3,
+];
+
+fn emit(emitter: &dyn Emitter, binary: &[u8]) -> String {
+    let mut output = Vec::new();
+
+    emitter.emit(&mut output, binary).unwrap();
+
+    String::from_utf8(output).unwrap()
+}
+
+#[test]
+fn emits_raw_binary() {
+    let mut output = Vec::new();
+
+    RawEmitter.emit(&mut output, &BINARY).unwrap();
+
+    assert_eq!(output, BINARY);
+}
+
+#[test]
+fn emits_hex_words() {
+    assert_eq!(
+        emit(&HexEmitter, &BINARY),
+        "001f8000fc0007e0\ne30000000007000f\n"
+    );
+    // The last word is padded.
+    assert_eq!(emit(&HexEmitter, &[0x1, 0x2]), "0000000000000201\n");
+}
+
+#[test]
+fn emits_c_header() {
+    assert_eq!(
+        emit(&CHeaderEmitter::default(), &BINARY),
+        "// Generated by maxhell-as, do not edit.\n\
+         #pragma once\n\
+         \n\
+         #include <stdint.h>\n\
+         \n\
+         static const uint64_t shader[] = {\n    \
+         0x001f8000fc0007e0,\n    \
+         0xe30000000007000f,\n\
+         };\n"
+    );
+}
+
+#[test]
+fn emits_rust_module() {
+    let emitter = RustEmitter {
+        name: String::from("EXIT"),
+    };
+
+    assert_eq!(
+        emit(&emitter, &BINARY),
+        "// Generated by maxhell-as, do not edit.\n\
+         pub const EXIT: [u64; 2] = [\n    \
+         0x001f8000fc0007e0,\n    \
+         0xe30000000007000f,\n\
+         ];\n"
+    );
+}
+
+#[test]
+fn parses_format_names() {
+    assert_eq!("C".parse::<OutputFormat>(), Ok(OutputFormat::C));
+    assert_eq!("rs".parse::<OutputFormat>(), Ok(OutputFormat::Rust));
+    assert!("xxd".parse::<OutputFormat>().is_err());
+}
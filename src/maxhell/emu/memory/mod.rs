@@ -0,0 +1,106 @@
+use std::convert::{TryFrom, TryInto};
+use std::fmt;
+use std::ops::Range;
+
+use super::EmulatorError;
+
+/// The memory spaces reachable by a shader.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum MemorySpace {
+    Shared,
+    Local,
+    Global,
+}
+
+impl fmt::Display for MemorySpace {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            MemorySpace::Shared => "shared",
+            MemorySpace::Local => "local",
+            MemorySpace::Global => "global",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+/// A little-endian memory buffer starting at address 0.
+#[derive(Debug, Clone)]
+pub struct Memory {
+    space: MemorySpace,
+    data: Vec<u8>,
+}
+
+impl Memory {
+    pub fn new(space: MemorySpace, size: usize) -> Self {
+        Memory {
+            space,
+            data: vec![0; size],
+        }
+    }
+
+    pub fn space(&self) -> MemorySpace {
+        self.space
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        &self.data
+    }
+
+    pub fn as_mut_slice(&mut self) -> &mut [u8] {
+        &mut self.data
+    }
+
+    /// Check an access of `size` bytes, accesses must be naturally aligned.
+    fn range(&self, address: u64, size: usize) -> Result<Range<usize>, EmulatorError> {
+        if !address.is_multiple_of(size as u64) {
+            return Err(EmulatorError::MisalignedAccess(self.space, address));
+        }
+
+        usize::try_from(address)
+            .ok()
+            .filter(|start| {
+                start
+                    .checked_add(size)
+                    .is_some_and(|end| end <= self.data.len())
+            })
+            .map(|start| start..start + size)
+            .ok_or(EmulatorError::OutOfBounds(self.space, address))
+    }
+
+    pub fn read_u32(&self, address: u64) -> Result<u32, EmulatorError> {
+        let range = self.range(address, 4)?;
+
+        Ok(u32::from_le_bytes(self.data[range].try_into().unwrap()))
+    }
+
+    pub fn write_u32(&mut self, address: u64, value: u32) -> Result<(), EmulatorError> {
+        let range = self.range(address, 4)?;
+
+        self.data[range].copy_from_slice(&value.to_le_bytes());
+
+        Ok(())
+    }
+
+    pub fn read_u64(&self, address: u64) -> Result<u64, EmulatorError> {
+        let range = self.range(address, 8)?;
+
+        Ok(u64::from_le_bytes(self.data[range].try_into().unwrap()))
+    }
+
+    pub fn write_u64(&mut self, address: u64, value: u64) -> Result<(), EmulatorError> {
+        let range = self.range(address, 8)?;
+
+        self.data[range].copy_from_slice(&value.to_le_bytes());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,1036 @@
+use std::error::Error;
+use std::fmt;
+
+use half::f16;
+
+use super::definition::*;
+use super::program::{instruction_address, instruction_index, Program};
+use super::target::Target;
+
+mod memory;
+#[cfg(test)]
+mod tests;
+
+pub use memory::*;
+
+/// Number of lanes in a warp.
+pub const WARP_SIZE: usize = 32;
+
+const REGISTER_COUNT: usize = 255;
+const PREDICATE_COUNT: usize = 7;
+
+/// Default number of entries of the reconvergence stack.
+pub const DEFAULT_STACK_LIMIT: usize = 32;
+
+const RZ: u8 = 0xFF;
+const PT: u8 = 7;
+
+/// Error returned by the emulator.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum EmulatorError {
+    /// The program counter left the program.
+    EndOfProgram(usize),
+    /// The instruction at the given index doesn't match any opcode.
+    InvalidInstruction(usize, u64),
+    /// The instruction isn't emulated or not available on the target.
+    UnsupportedInstruction(usize, Opcode),
+    /// The instruction uses a value of a field that isn't emulated.
+    UnsupportedOperand(usize, Opcode, &'static str),
+    OutOfBounds(MemorySpace, u64),
+    MisalignedAccess(MemorySpace, u64),
+    /// The warp didn't finish after the given number of steps.
+    StepLimitExceeded(usize),
+    /// The branch target at the given address isn't an instruction of the program.
+    InvalidBranchTarget(usize, u64),
+    /// The reconvergence stack is full.
+    StackOverflow(usize),
+    /// The reconvergence stack doesn't have the entry needed by the instruction.
+    StackUnderflow(usize),
+}
+
+impl fmt::Display for EmulatorError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EmulatorError::EndOfProgram(pc) => {
+                write!(f, "reached the end of the program at {}", pc)
+            }
+            EmulatorError::InvalidInstruction(pc, instruction) => {
+                write!(f, "invalid instruction 0x{:016x} at {}", instruction, pc)
+            }
+            EmulatorError::UnsupportedInstruction(pc, opcode) => {
+                write!(f, "{:?} at {} is not supported", opcode, pc)
+            }
+            EmulatorError::UnsupportedOperand(pc, opcode, field) => {
+                write!(f, "{:?} at {}: unsupported value of {}", opcode, pc, field)
+            }
+            EmulatorError::OutOfBounds(space, address) => {
+                write!(
+                    f,
+                    "out of bounds {} memory access at 0x{:x}",
+                    space, address
+                )
+            }
+            EmulatorError::MisalignedAccess(space, address) => {
+                write!(f, "misaligned {} memory access at 0x{:x}", space, address)
+            }
+            EmulatorError::StepLimitExceeded(steps) => {
+                write!(f, "the warp didn't finish after {} steps", steps)
+            }
+            EmulatorError::InvalidBranchTarget(pc, address) => {
+                write!(f, "invalid branch target 0x{:x} at {}", address, pc)
+            }
+            EmulatorError::StackOverflow(pc) => write!(f, "stack overflow at {}", pc),
+            EmulatorError::StackUnderflow(pc) => write!(f, "stack underflow at {}", pc),
+        }
+    }
+}
+
+impl Error for EmulatorError {}
+
+/// Type of the values handled by an atomic operation.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum DataType {
+    U32,
+    S32,
+    U64,
+    S64,
+    F32,
+    /// Two half-precision floats packed in 32 bits.
+    F16X2,
+}
+
+impl DataType {
+    fn is_64bit(self) -> bool {
+        matches!(self, DataType::U64 | DataType::S64)
+    }
+
+    fn mask(self) -> u64 {
+        if self.is_64bit() {
+            u64::MAX
+        } else {
+            u64::from(u32::MAX)
+        }
+    }
+}
+
+impl From<AtomsPrimitiveType> for DataType {
+    fn from(value: AtomsPrimitiveType) -> Self {
+        match value {
+            AtomsPrimitiveType::S32 => DataType::S32,
+            AtomsPrimitiveType::U64 => DataType::U64,
+            AtomsPrimitiveType::S64 => DataType::S64,
+            _ => DataType::U32,
+        }
+    }
+}
+
+impl From<AtomPrimitiveType> for DataType {
+    fn from(value: AtomPrimitiveType) -> Self {
+        match value {
+            AtomPrimitiveType::S32 => DataType::S32,
+            AtomPrimitiveType::U64 => DataType::U64,
+            AtomPrimitiveType::F32 => DataType::F32,
+            AtomPrimitiveType::F16X2 => DataType::F16X2,
+            AtomPrimitiveType::S64 => DataType::S64,
+            _ => DataType::U32,
+        }
+    }
+}
+
+impl From<AtomicCasPrimitiveType> for DataType {
+    fn from(value: AtomicCasPrimitiveType) -> Self {
+        match value {
+            AtomicCasPrimitiveType::U64 => DataType::U64,
+            _ => DataType::U32,
+        }
+    }
+}
+
+/// Compute the value stored by an atomic operation, returns None if the operation isn't
+/// defined for the type.
+fn atomic_result(
+    operation: &AtomOperation,
+    data_type: DataType,
+    old: u64,
+    value: u64,
+) -> Option<u64> {
+    let result = match (operation, data_type) {
+        (&AtomOperation::ADD, DataType::F32) => {
+            u64::from((f32::from_bits(old as u32) + f32::from_bits(value as u32)).to_bits())
+        }
+        // NOTE: denormal halves aren't flushed to zero.
+        (&AtomOperation::ADD, DataType::F16X2) => {
+            let add = |shift: u32| {
+                let sum = f16::from_bits((old >> shift) as u16).to_f32()
+                    + f16::from_bits((value >> shift) as u16).to_f32();
+
+                u64::from(f16::from_f32(sum).to_bits()) << shift
+            };
+
+            add(0) | add(16)
+        }
+        (&AtomOperation::ADD, _) | (&AtomOperation::SAFE_ADD, _) => old.wrapping_add(value),
+        (_, DataType::F32) | (_, DataType::F16X2) => return None,
+        (&AtomOperation::MIN, DataType::S32) => (old as i32).min(value as i32) as u32 as u64,
+        (&AtomOperation::MIN, DataType::S64) => (old as i64).min(value as i64) as u64,
+        (&AtomOperation::MIN, _) => old.min(value),
+        (&AtomOperation::MAX, DataType::S32) => (old as i32).max(value as i32) as u32 as u64,
+        (&AtomOperation::MAX, DataType::S64) => (old as i64).max(value as i64) as u64,
+        (&AtomOperation::MAX, _) => old.max(value),
+        (&AtomOperation::INC, _) if old >= value => 0,
+        (&AtomOperation::INC, _) => old + 1,
+        (&AtomOperation::DEC, _) if old == 0 || old > value => value,
+        (&AtomOperation::DEC, _) => old - 1,
+        (&AtomOperation::AND, _) => old & value,
+        (&AtomOperation::OR, _) => old | value,
+        (&AtomOperation::XOR, _) => old ^ value,
+        (&AtomOperation::EXCH, _) => value,
+        _ => return None,
+    };
+
+    Some(result & data_type.mask())
+}
+
+/// Reason an entry was pushed on the reconvergence stack.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum StackEntryKind {
+    /// Lanes not taking a divergent branch.
+    Divergence,
+    /// Pushed by Opcode::SSY, popped once every lane reached Opcode::SYNC.
+    Sync,
+    /// Pushed by Opcode::PBK, lanes executing Opcode::BRK wait for it.
+    Break,
+    /// Pushed by Opcode::PCNT, lanes executing Opcode::CONT wait for it.
+    Continue,
+    /// Pushed by Opcode::PRET, lanes executing Opcode::RET wait for it.
+    Return,
+}
+
+/// Lanes to resume at an instruction once the active lanes are done.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct StackEntry {
+    pub kind: StackEntryKind,
+    pub mask: u32,
+    pub pc: usize,
+}
+
+/// State of a lane of the warp.
+#[derive(Debug, Clone)]
+struct Lane {
+    registers: [u32; REGISTER_COUNT],
+    predicates: [bool; PREDICATE_COUNT],
+    local: Memory,
+}
+
+/// Functional emulator running a program on a single warp.
+///
+/// Lanes execute instructions in lockstep, atomic operations are performed in lane order.
+/// As the warp is alone in its block, barriers never wait.
+///
+/// Divergence is handled like the hardware does, with a stack of lanes waiting to be
+/// resumed. Once no lane is active, the top of the stack is popped.
+#[derive(Debug, Clone)]
+pub struct Emulator {
+    target: Target,
+    instructions: Vec<u64>,
+    pc: usize,
+    lanes: Vec<Lane>,
+    /// Lanes currently executing instructions.
+    active_mask: u32,
+    /// Lanes that left the program.
+    exited_mask: u32,
+    /// Lanes that left the program with Opcode::KIL.
+    killed_mask: u32,
+    /// Lanes holding a reference on the warp, released when exiting without keep_refcount.
+    refcount_mask: u32,
+    stack: Vec<StackEntry>,
+    stack_limit: usize,
+    shared: Memory,
+    global: Memory,
+    /// Result of the last BAR.RED, read with B2R.RESULT.
+    barrier_result: u32,
+}
+
+impl Emulator {
+    pub fn new(
+        program: &Program,
+        shared_size: usize,
+        local_size: usize,
+        global_size: usize,
+    ) -> Self {
+        let lane = Lane {
+            registers: [0; REGISTER_COUNT],
+            predicates: [false; PREDICATE_COUNT],
+            local: Memory::new(MemorySpace::Local, local_size),
+        };
+
+        Emulator {
+            target: program.target(),
+            instructions: program.instructions().to_vec(),
+            pc: 0,
+            lanes: vec![lane; WARP_SIZE],
+            active_mask: u32::MAX,
+            exited_mask: 0,
+            killed_mask: 0,
+            refcount_mask: u32::MAX,
+            stack: Vec::new(),
+            stack_limit: DEFAULT_STACK_LIMIT,
+            shared: Memory::new(MemorySpace::Shared, shared_size),
+            global: Memory::new(MemorySpace::Global, global_size),
+            barrier_result: 0,
+        }
+    }
+
+    pub fn target(&self) -> Target {
+        self.target
+    }
+
+    /// Index of the next instruction to execute.
+    pub fn pc(&self) -> usize {
+        self.pc
+    }
+
+    pub fn active_mask(&self) -> u32 {
+        self.active_mask
+    }
+
+    pub fn exited_mask(&self) -> u32 {
+        self.exited_mask
+    }
+
+    pub fn killed_mask(&self) -> u32 {
+        self.killed_mask
+    }
+
+    pub fn refcount_mask(&self) -> u32 {
+        self.refcount_mask
+    }
+
+    /// Entries of the reconvergence stack, the top is last.
+    pub fn stack(&self) -> &[StackEntry] {
+        &self.stack
+    }
+
+    pub fn set_stack_limit(&mut self, limit: usize) {
+        self.stack_limit = limit;
+    }
+
+    /// Check if every lane left the program.
+    pub fn is_finished(&self) -> bool {
+        self.exited_mask == u32::MAX
+    }
+
+    pub fn register(&self, lane: usize, register: u8) -> u32 {
+        if register == RZ {
+            0
+        } else {
+            self.lanes[lane].registers[register as usize]
+        }
+    }
+
+    pub fn set_register(&mut self, lane: usize, register: u8, value: u32) {
+        if register != RZ {
+            self.lanes[lane].registers[register as usize] = value;
+        }
+    }
+
+    pub fn predicate(&self, lane: usize, predicate: u8) -> bool {
+        debug_assert!(predicate < 8);
+
+        predicate == PT || self.lanes[lane].predicates[predicate as usize]
+    }
+
+    pub fn set_predicate(&mut self, lane: usize, predicate: u8, value: bool) {
+        debug_assert!(predicate < 8);
+
+        if predicate != PT {
+            self.lanes[lane].predicates[predicate as usize] = value;
+        }
+    }
+
+    pub fn shared(&self) -> &Memory {
+        &self.shared
+    }
+
+    pub fn shared_mut(&mut self) -> &mut Memory {
+        &mut self.shared
+    }
+
+    pub fn local(&self, lane: usize) -> &Memory {
+        &self.lanes[lane].local
+    }
+
+    pub fn local_mut(&mut self, lane: usize) -> &mut Memory {
+        &mut self.lanes[lane].local
+    }
+
+    pub fn global(&self) -> &Memory {
+        &self.global
+    }
+
+    pub fn global_mut(&mut self) -> &mut Memory {
+        &mut self.global
+    }
+
+    /// Run the warp until every lane left the program, returns the number of steps.
+    pub fn run(&mut self, max_steps: usize) -> Result<usize, EmulatorError> {
+        let mut steps = 0;
+
+        while !self.is_finished() {
+            if steps == max_steps {
+                return Err(EmulatorError::StepLimitExceeded(steps));
+            }
+
+            self.step()?;
+            steps += 1;
+        }
+
+        Ok(steps)
+    }
+
+    /// Execute the next instruction.
+    ///
+    /// On error, the program counter stays on the faulting instruction but lanes executed
+    /// before the fault keep their side effects.
+    pub fn step(&mut self) -> Result<(), EmulatorError> {
+        let pc = self.pc;
+        let raw = *self
+            .instructions
+            .get(pc)
+            .ok_or(EmulatorError::EndOfProgram(pc))?;
+        let instruction =
+            DecodedInstruction::decode(raw).ok_or(EmulatorError::InvalidInstruction(pc, raw))?;
+
+        if !self.target.supports(instruction.opcode()) {
+            return Err(EmulatorError::UnsupportedInstruction(
+                pc,
+                instruction.opcode(),
+            ));
+        }
+
+        self.pc = self.execute(pc, instruction)?;
+
+        if self.active_mask == 0 {
+            self.reconverge(pc)?;
+        }
+
+        Ok(())
+    }
+
+    /// Resume the lanes at the top of the stack until some are active.
+    fn reconverge(&mut self, pc: usize) -> Result<(), EmulatorError> {
+        while self.active_mask == 0 {
+            let entry = match self.stack.pop() {
+                Some(entry) => entry,
+                None if self.is_finished() => return Ok(()),
+                // NOTE: some lanes wait for an entry that doesn't exist.
+                None => return Err(EmulatorError::StackUnderflow(pc)),
+            };
+
+            self.active_mask = entry.mask & !self.exited_mask;
+            self.pc = entry.pc;
+        }
+
+        Ok(())
+    }
+
+    fn push(
+        &mut self,
+        pc: usize,
+        kind: StackEntryKind,
+        mask: u32,
+        target: usize,
+    ) -> Result<(), EmulatorError> {
+        if self.stack.len() >= self.stack_limit {
+            return Err(EmulatorError::StackOverflow(pc));
+        }
+
+        self.stack.push(StackEntry {
+            kind,
+            mask,
+            pc: target,
+        });
+
+        Ok(())
+    }
+
+    /// Make lanes wait for the closest entry of the given kind.
+    ///
+    /// The lanes are removed from the entries above it so they are only resumed by it.
+    fn wait_for(
+        &mut self,
+        pc: usize,
+        kind: StackEntryKind,
+        lanes: u32,
+    ) -> Result<(), EmulatorError> {
+        let index = self
+            .stack
+            .iter()
+            .rposition(|entry| entry.kind == kind)
+            .ok_or(EmulatorError::StackUnderflow(pc))?;
+
+        for entry in &mut self.stack[index + 1..] {
+            entry.mask &= !lanes;
+        }
+
+        self.active_mask &= !lanes;
+
+        Ok(())
+    }
+
+    /// Make lanes leave the program.
+    fn exit_lanes(&mut self, lanes: u32, keep_refcount: bool) {
+        self.active_mask &= !lanes;
+        self.exited_mask |= lanes;
+
+        if !keep_refcount {
+            self.refcount_mask &= !lanes;
+        }
+
+        for entry in &mut self.stack {
+            entry.mask &= !lanes;
+        }
+    }
+
+    /// Index of the instruction targeted by a relative branch.
+    fn branch_target(&self, pc: usize, offset: i32) -> Result<usize, EmulatorError> {
+        let address = (instruction_address(pc) + 8).wrapping_add(offset as i64 as u64);
+
+        instruction_index(address)
+            .filter(|index| *index < self.instructions.len())
+            .ok_or(EmulatorError::InvalidBranchTarget(pc, address))
+    }
+
+    /// Lanes executing an instruction guarded by the given predicate.
+    fn guard_mask(&self, predicate: u8, invert: bool) -> u32 {
+        (0..WARP_SIZE)
+            .filter(|lane| self.active_mask & (1 << lane) != 0)
+            .filter(|lane| self.predicate(*lane, predicate) != invert)
+            .fold(0, |mask, lane| mask | (1 << lane))
+    }
+
+    /// Evaluate a control code.
+    fn condition(
+        &self,
+        pc: usize,
+        opcode: Opcode,
+        cc_flags: ControlCode,
+    ) -> Result<bool, EmulatorError> {
+        // TODO: model the condition code register.
+        match cc_flags {
+            ControlCode::TRUE => Ok(true),
+            ControlCode::FALSE => Ok(false),
+            _ => Err(EmulatorError::UnsupportedOperand(pc, opcode, "cc_flags")),
+        }
+    }
+
+    fn read_registers(&self, lane: usize, register: u8, data_type: DataType) -> u64 {
+        let low = u64::from(self.register(lane, register));
+
+        if data_type.is_64bit() && register != RZ {
+            low | u64::from(self.register(lane, register.saturating_add(1))) << 32
+        } else {
+            low
+        }
+    }
+
+    fn write_registers(&mut self, lane: usize, register: u8, data_type: DataType, value: u64) {
+        self.set_register(lane, register, value as u32);
+
+        if data_type.is_64bit() && register != RZ {
+            self.set_register(lane, register.saturating_add(1), (value >> 32) as u32);
+        }
+    }
+
+    /// Address held by a register, a pair of registers when `wide` is set.
+    fn read_address(&self, lane: usize, register: u8, wide: bool, offset: i32) -> u64 {
+        let data_type = if wide { DataType::U64 } else { DataType::U32 };
+        let address = self.read_registers(lane, register, data_type);
+
+        address.wrapping_add(offset as i64 as u64)
+    }
+
+    fn execute(
+        &mut self,
+        pc: usize,
+        instruction: DecodedInstruction,
+    ) -> Result<usize, EmulatorError> {
+        let opcode = instruction.opcode();
+
+        match instruction {
+            DecodedInstruction::NOP(inst) => {
+                self.condition(pc, opcode, inst.cc_flags())?;
+            }
+            DecodedInstruction::SAM(_)
+            | DecodedInstruction::RAM(_)
+            | DecodedInstruction::MEMBAR(_)
+            | DecodedInstruction::DEPBAR(_) => {}
+            DecodedInstruction::EXIT(inst) => {
+                if self.condition(pc, opcode, inst.cc_flags())? {
+                    let lanes = self.guard_mask(
+                        inst.source_predicate_register(),
+                        inst.invert_source_predicate(),
+                    );
+
+                    self.exit_lanes(lanes, inst.keep_refcount());
+                }
+            }
+            DecodedInstruction::KIL(inst) => {
+                if self.condition(pc, opcode, inst.cc_flags())? {
+                    let lanes = self.guard_mask(
+                        inst.source_predicate_register(),
+                        inst.invert_source_predicate(),
+                    );
+
+                    self.exit_lanes(lanes, false);
+                    self.killed_mask |= lanes;
+                }
+            }
+            DecodedInstruction::BRA(inst) => return self.execute_bra(pc, inst),
+            DecodedInstruction::SSY(inst) => {
+                self.execute_flow_push(pc, inst, StackEntryKind::Sync)?
+            }
+            DecodedInstruction::PBK(inst) => {
+                self.execute_flow_push(pc, inst, StackEntryKind::Break)?
+            }
+            DecodedInstruction::PCNT(inst) => {
+                self.execute_flow_push(pc, inst, StackEntryKind::Continue)?
+            }
+            DecodedInstruction::PRET(inst) => {
+                self.execute_flow_push(pc, inst, StackEntryKind::Return)?
+            }
+            DecodedInstruction::SYNC(inst) => {
+                if self.stack.is_empty() {
+                    return Err(EmulatorError::StackUnderflow(pc));
+                }
+
+                // NOTE: lanes are already part of the entry they wait for.
+                if self.condition(pc, opcode, inst.cc_flags())? {
+                    self.active_mask &= !self.guard_mask(
+                        inst.source_predicate_register(),
+                        inst.invert_source_predicate(),
+                    );
+                }
+            }
+            DecodedInstruction::BRK(inst) => {
+                self.execute_flow_pop(pc, inst, opcode, StackEntryKind::Break)?
+            }
+            DecodedInstruction::CONT(inst) => {
+                self.execute_flow_pop(pc, inst, opcode, StackEntryKind::Continue)?
+            }
+            DecodedInstruction::RET(inst) => {
+                let has_return = self
+                    .stack
+                    .iter()
+                    .any(|entry| entry.kind == StackEntryKind::Return);
+
+                if has_return {
+                    self.execute_flow_pop(pc, inst, opcode, StackEntryKind::Return)?;
+                } else if self.condition(pc, opcode, inst.cc_flags())? {
+                    // NOTE: without Opcode::PRET, RET returns from the program.
+                    let lanes = self.guard_mask(
+                        inst.source_predicate_register(),
+                        inst.invert_source_predicate(),
+                    );
+
+                    self.exit_lanes(lanes, false);
+                }
+            }
+            DecodedInstruction::BAR(inst) => self.execute_bar(pc, inst)?,
+            DecodedInstruction::B2R(inst) => self.execute_b2r(pc, inst)?,
+            DecodedInstruction::ATOMS(inst) => self.execute_atoms(pc, inst)?,
+            DecodedInstruction::ATOMS_CAS(inst) => self.execute_atoms_cas(pc, inst)?,
+            DecodedInstruction::ATOM(inst) => self.execute_atom(pc, inst)?,
+            DecodedInstruction::ATOM_CAS(inst) => self.execute_atom_cas(inst)?,
+            DecodedInstruction::RED(inst) => self.execute_red(pc, inst)?,
+            _ => return Err(EmulatorError::UnsupportedInstruction(pc, opcode)),
+        }
+
+        Ok(pc + 1)
+    }
+
+    fn execute_bra(&mut self, pc: usize, inst: BraInstruction) -> Result<usize, EmulatorError> {
+        if inst.constant_buffer_flag() {
+            return Err(EmulatorError::UnsupportedOperand(
+                pc,
+                Opcode::BRA,
+                "constant_buffer_flag",
+            ));
+        }
+
+        let target = self.branch_target(pc, inst.offset())?;
+
+        if !self.condition(pc, Opcode::BRA, inst.cc_flags())? {
+            return Ok(pc + 1);
+        }
+
+        let taken = self.guard_mask(
+            inst.source_predicate_register(),
+            inst.invert_source_predicate(),
+        );
+
+        if taken == 0 {
+            Ok(pc + 1)
+        } else if taken == self.active_mask {
+            Ok(target)
+        } else {
+            self.push(
+                pc,
+                StackEntryKind::Divergence,
+                self.active_mask & !taken,
+                pc + 1,
+            )?;
+            self.active_mask = taken;
+
+            Ok(target)
+        }
+    }
+
+    fn execute_flow_push(
+        &mut self,
+        pc: usize,
+        inst: FlowPushInstruction,
+        kind: StackEntryKind,
+    ) -> Result<(), EmulatorError> {
+        let opcode = match kind {
+            StackEntryKind::Sync => Opcode::SSY,
+            StackEntryKind::Break => Opcode::PBK,
+            StackEntryKind::Continue => Opcode::PCNT,
+            _ => Opcode::PRET,
+        };
+
+        if inst.constant_buffer_flag() {
+            return Err(EmulatorError::UnsupportedOperand(
+                pc,
+                opcode,
+                "constant_buffer_flag",
+            ));
+        }
+
+        let target = self.branch_target(pc, inst.offset())?;
+
+        self.push(pc, kind, self.active_mask, target)
+    }
+
+    fn execute_flow_pop(
+        &mut self,
+        pc: usize,
+        inst: RetInstruction,
+        opcode: Opcode,
+        kind: StackEntryKind,
+    ) -> Result<(), EmulatorError> {
+        if !self.condition(pc, opcode, inst.cc_flags())? {
+            return Ok(());
+        }
+
+        let lanes = self.guard_mask(
+            inst.source_predicate_register(),
+            inst.invert_source_predicate(),
+        );
+
+        self.wait_for(pc, kind, lanes)
+    }
+
+    fn execute_bar(&mut self, pc: usize, inst: BarInstruction) -> Result<(), EmulatorError> {
+        let lanes = self.guard_mask(
+            inst.source_predicate_register(),
+            inst.invert_source_predicate(),
+        );
+
+        match inst.mode() {
+            BarMode::SYNC | BarMode::ARV | BarMode::SYNCALL => {}
+            BarMode::RED => {
+                let votes = (0..WARP_SIZE)
+                    .filter(|lane| lanes & (1 << lane) != 0)
+                    .filter(|lane| {
+                        self.predicate(*lane, inst.reduction_predicate_register())
+                            != inst.invert_reduction_predicate()
+                    })
+                    .fold(0u32, |mask, lane| mask | (1 << lane));
+
+                self.barrier_result = match inst.reduction() {
+                    BarReductionOperation::POPC => votes.count_ones(),
+                    BarReductionOperation::AND => (votes == lanes) as u32,
+                    BarReductionOperation::OR => (votes != 0) as u32,
+                    _ => {
+                        return Err(EmulatorError::UnsupportedOperand(
+                            pc,
+                            Opcode::BAR,
+                            "reduction",
+                        ))
+                    }
+                };
+            }
+            _ => return Err(EmulatorError::UnsupportedOperand(pc, Opcode::BAR, "mode")),
+        }
+
+        Ok(())
+    }
+
+    fn execute_b2r(&mut self, pc: usize, inst: B2RInstruction) -> Result<(), EmulatorError> {
+        let lanes = self.guard_mask(
+            inst.source_predicate_register(),
+            inst.invert_source_predicate(),
+        );
+
+        let value = match inst.operation() {
+            // NOTE: the warp is alone in its block, no barrier has pending arrivals.
+            B2ROperation::BAR => 0,
+            B2ROperation::RESULT => self.barrier_result,
+            _ => {
+                return Err(EmulatorError::UnsupportedOperand(
+                    pc,
+                    Opcode::B2R,
+                    "operation",
+                ))
+            }
+        };
+
+        for lane in (0..WARP_SIZE).filter(|lane| lanes & (1 << lane) != 0) {
+            self.set_register(lane, inst.destination_register(), value);
+            self.set_predicate(lane, inst.destination_predicate_register(), value != 0);
+        }
+
+        Ok(())
+    }
+
+    fn execute_atoms(&mut self, pc: usize, inst: AtomsIntruction) -> Result<(), EmulatorError> {
+        let lanes = self.guard_mask(
+            inst.source_predicate_register(),
+            inst.invert_source_predicate(),
+        );
+        let data_type = DataType::from(inst.type_size());
+        let operation = AtomOperation::from(u8::from(inst.operation()));
+
+        if operation == AtomOperation::SAFE_ADD {
+            return Err(EmulatorError::UnsupportedOperand(
+                pc,
+                Opcode::ATOMS,
+                "operation",
+            ));
+        }
+
+        for lane in (0..WARP_SIZE).filter(|lane| lanes & (1 << lane) != 0) {
+            let address = self.read_address(
+                lane,
+                inst.source_register_a(),
+                false,
+                inst.register_a_offset_shr_2() << 2,
+            );
+            let value = self.read_registers(lane, inst.source_register_b(), data_type);
+            let old = self.read_memory(MemorySpace::Shared, lane, address, data_type)?;
+            let result = atomic_result(&operation, data_type, old, value).ok_or(
+                EmulatorError::UnsupportedOperand(pc, Opcode::ATOMS, "operation"),
+            )?;
+
+            self.write_memory(MemorySpace::Shared, lane, address, data_type, result)?;
+            self.write_registers(lane, inst.destination_register(), data_type, old);
+        }
+
+        Ok(())
+    }
+
+    fn execute_atoms_cas(
+        &mut self,
+        pc: usize,
+        inst: AtomsCasIntruction,
+    ) -> Result<(), EmulatorError> {
+        let lanes = self.guard_mask(
+            inst.source_predicate_register(),
+            inst.invert_source_predicate(),
+        );
+        let data_type = DataType::from(inst.type_size());
+
+        if inst.operation() != AtomsCasOperation::CAS {
+            return Err(EmulatorError::UnsupportedOperand(
+                pc,
+                Opcode::ATOMS_CAS,
+                "operation",
+            ));
+        }
+
+        for lane in (0..WARP_SIZE).filter(|lane| lanes & (1 << lane) != 0) {
+            let address = self.read_address(
+                lane,
+                inst.source_register_a(),
+                false,
+                inst.register_a_offset_shr_2() << 2,
+            );
+
+            self.compare_and_swap(
+                MemorySpace::Shared,
+                lane,
+                address,
+                inst.compare_register(),
+                inst.destination_register(),
+                data_type,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    fn execute_atom(&mut self, pc: usize, inst: AtomInstruction) -> Result<(), EmulatorError> {
+        let lanes = self.guard_mask(
+            inst.source_predicate_register(),
+            inst.invert_source_predicate(),
+        );
+        let data_type = DataType::from(inst.type_size());
+        let operation = inst.operation();
+
+        if !operation.is_valid(&inst.type_size(), false) {
+            return Err(EmulatorError::UnsupportedOperand(
+                pc,
+                Opcode::ATOM,
+                "operation",
+            ));
+        }
+
+        for lane in (0..WARP_SIZE).filter(|lane| lanes & (1 << lane) != 0) {
+            let address = self.read_address(
+                lane,
+                inst.source_register_a(),
+                inst.e_flag(),
+                inst.register_a_offset(),
+            );
+            let value = self.read_registers(lane, inst.source_register_b(), data_type);
+            let old = self.read_memory(MemorySpace::Global, lane, address, data_type)?;
+            let result = atomic_result(&operation, data_type, old, value).ok_or(
+                EmulatorError::UnsupportedOperand(pc, Opcode::ATOM, "operation"),
+            )?;
+
+            self.write_memory(MemorySpace::Global, lane, address, data_type, result)?;
+            self.write_registers(lane, inst.destination_register(), data_type, old);
+        }
+
+        Ok(())
+    }
+
+    fn execute_atom_cas(&mut self, inst: AtomCasInstruction) -> Result<(), EmulatorError> {
+        let lanes = self.guard_mask(
+            inst.source_predicate_register(),
+            inst.invert_source_predicate(),
+        );
+        let data_type = DataType::from(inst.type_size());
+
+        for lane in (0..WARP_SIZE).filter(|lane| lanes & (1 << lane) != 0) {
+            let address = self.read_address(
+                lane,
+                inst.source_register_a(),
+                inst.e_flag(),
+                inst.register_a_offset(),
+            );
+
+            self.compare_and_swap(
+                MemorySpace::Global,
+                lane,
+                address,
+                inst.source_register_b(),
+                inst.destination_register(),
+                data_type,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    fn execute_red(&mut self, pc: usize, inst: RedInstruction) -> Result<(), EmulatorError> {
+        let lanes = self.guard_mask(
+            inst.source_predicate_register(),
+            inst.invert_source_predicate(),
+        );
+        let data_type = DataType::from(inst.type_size());
+        let operation = inst.operation();
+
+        if !operation.is_valid(&inst.type_size(), true) {
+            return Err(EmulatorError::UnsupportedOperand(
+                pc,
+                Opcode::RED,
+                "operation",
+            ));
+        }
+
+        for lane in (0..WARP_SIZE).filter(|lane| lanes & (1 << lane) != 0) {
+            let address = self.read_address(
+                lane,
+                inst.source_register_a(),
+                inst.e_flag(),
+                inst.register_a_offset(),
+            );
+            let value = self.read_registers(lane, inst.source_register_b(), data_type);
+            let old = self.read_memory(MemorySpace::Global, lane, address, data_type)?;
+            let result = atomic_result(&operation, data_type, old, value).ok_or(
+                EmulatorError::UnsupportedOperand(pc, Opcode::RED, "operation"),
+            )?;
+
+            self.write_memory(MemorySpace::Global, lane, address, data_type, result)?;
+        }
+
+        Ok(())
+    }
+
+    /// Compare the memory with the first register (pair) and store the following one on equality.
+    fn compare_and_swap(
+        &mut self,
+        space: MemorySpace,
+        lane: usize,
+        address: u64,
+        source_register: u8,
+        destination_register: u8,
+        data_type: DataType,
+    ) -> Result<(), EmulatorError> {
+        let stride = if data_type.is_64bit() { 2 } else { 1 };
+        let compare = self.read_registers(lane, source_register, data_type);
+        let value = self.read_registers(lane, source_register.saturating_add(stride), data_type);
+        let old = self.read_memory(space, lane, address, data_type)?;
+
+        if old == compare {
+            self.write_memory(space, lane, address, data_type, value)?;
+        }
+
+        self.write_registers(lane, destination_register, data_type, old);
+
+        Ok(())
+    }
+
+    fn memory(&mut self, space: MemorySpace, lane: usize) -> &mut Memory {
+        match space {
+            MemorySpace::Shared => &mut self.shared,
+            MemorySpace::Local => &mut self.lanes[lane].local,
+            MemorySpace::Global => &mut self.global,
+        }
+    }
+
+    fn read_memory(
+        &mut self,
+        space: MemorySpace,
+        lane: usize,
+        address: u64,
+        data_type: DataType,
+    ) -> Result<u64, EmulatorError> {
+        let memory = self.memory(space, lane);
+
+        if data_type.is_64bit() {
+            memory.read_u64(address)
+        } else {
+            memory.read_u32(address).map(u64::from)
+        }
+    }
+
+    fn write_memory(
+        &mut self,
+        space: MemorySpace,
+        lane: usize,
+        address: u64,
+        data_type: DataType,
+        value: u64,
+    ) -> Result<(), EmulatorError> {
+        let memory = self.memory(space, lane);
+
+        if data_type.is_64bit() {
+            memory.write_u64(address, value)
+        } else {
+            memory.write_u32(address, value as u32)
+        }
+    }
+}
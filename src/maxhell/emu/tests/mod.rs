@@ -0,0 +1,426 @@
+use super::*;
+use crate::maxhell::encoder;
+use crate::maxhell::program::instruction_address;
+
+const TARGET: Target = Target::SM50;
+
+const MEMORY_SIZE: usize = 0x100;
+
+const MAX_STEPS: usize = 64;
+
+fn emulator(instructions: &[u64]) -> Emulator {
+    let mut program = Program::new(TARGET);
+
+    for instruction in instructions {
+        program.push(*instruction);
+    }
+
+    Emulator::new(&program, MEMORY_SIZE, MEMORY_SIZE, MEMORY_SIZE)
+}
+
+fn exit() -> u64 {
+    encoder::encode_exit(TARGET, 7, false, ControlCode::TRUE, false).unwrap()
+}
+
+#[test]
+fn exit_deactivates_guarded_lanes() {
+    let mut emulator = emulator(&[
+        encoder::encode_exit(TARGET, 0, false, ControlCode::TRUE, false).unwrap(),
+        exit(),
+    ]);
+
+    for lane in 0..WARP_SIZE {
+        emulator.set_predicate(lane, 0, lane % 2 == 0);
+    }
+
+    emulator.step().unwrap();
+    assert_eq!(emulator.active_mask(), 0xaaaa_aaaa);
+
+    assert_eq!(emulator.run(MAX_STEPS), Ok(1));
+    assert!(emulator.is_finished());
+}
+
+#[test]
+fn zero_registers_are_constant() {
+    let mut emulator = emulator(&[exit()]);
+
+    emulator.set_register(3, RZ, 42);
+    emulator.set_predicate(3, PT, false);
+
+    assert_eq!(emulator.register(3, RZ), 0);
+    assert!(emulator.predicate(3, PT));
+}
+
+#[test]
+fn atoms_add_is_performed_in_lane_order() {
+    let mut emulator = emulator(&[
+        encoder::encode_atoms(
+            TARGET,
+            7,
+            false,
+            4,
+            1,
+            2,
+            0x10,
+            AtomsPrimitiveType::U32,
+            AtomsOperation::ADD,
+        )
+        .unwrap(),
+        exit(),
+    ]);
+
+    for lane in 0..WARP_SIZE {
+        emulator.set_register(lane, 1, 0x4);
+        emulator.set_register(lane, 2, 1);
+    }
+
+    emulator.run(MAX_STEPS).unwrap();
+
+    assert_eq!(emulator.shared().read_u32(0x14), Ok(WARP_SIZE as u32));
+
+    for lane in 0..WARP_SIZE {
+        assert_eq!(emulator.register(lane, 4), lane as u32);
+    }
+}
+
+#[test]
+fn atoms_cas_swaps_on_equality() {
+    let mut emulator = emulator(&[
+        encoder::encode_atoms_cas(
+            TARGET,
+            7,
+            false,
+            4,
+            RZ,
+            2,
+            0,
+            AtomicCasPrimitiveType::U32,
+            AtomsCasOperation::CAS,
+        )
+        .unwrap(),
+        exit(),
+    ]);
+
+    for lane in 0..WARP_SIZE {
+        // NOTE: the compared value is in the register preceding the one given to the encoder.
+        emulator.set_register(lane, 1, lane as u32);
+        emulator.set_register(lane, 2, lane as u32 + 1);
+    }
+
+    emulator.run(MAX_STEPS).unwrap();
+
+    // Every lane finds the value stored by the previous one.
+    assert_eq!(emulator.shared().read_u32(0), Ok(WARP_SIZE as u32));
+
+    for lane in 0..WARP_SIZE {
+        assert_eq!(emulator.register(lane, 4), lane as u32);
+    }
+}
+
+#[test]
+fn atom_uses_wide_addresses_and_values() {
+    let mut emulator = emulator(&[
+        encoder::encode_atom(
+            TARGET,
+            7,
+            false,
+            4,
+            2,
+            6,
+            0x8,
+            AtomPrimitiveType::U64,
+            AtomOperation::SAFE_ADD,
+            true,
+        )
+        .unwrap(),
+        exit(),
+    ]);
+
+    emulator.global_mut().write_u64(0x18, 0xffff_ffff).unwrap();
+
+    for lane in 0..WARP_SIZE {
+        emulator.set_register(lane, 2, 0x10);
+        emulator.set_register(lane, 3, 0);
+        emulator.set_register(lane, 6, 1);
+        emulator.set_register(lane, 7, 0);
+    }
+
+    emulator.run(MAX_STEPS).unwrap();
+
+    assert_eq!(
+        emulator.global().read_u64(0x18),
+        Ok(0xffff_ffff + WARP_SIZE as u64)
+    );
+    assert_eq!(emulator.register(1, 4), 0);
+    assert_eq!(emulator.register(1, 5), 1);
+}
+
+#[test]
+fn red_min_compares_signed_values() {
+    let mut emulator = emulator(&[
+        encoder::encode_red(
+            TARGET,
+            7,
+            false,
+            RZ,
+            2,
+            0x20,
+            AtomPrimitiveType::S32,
+            AtomOperation::MIN,
+            false,
+        )
+        .unwrap(),
+        exit(),
+    ]);
+
+    for lane in 0..WARP_SIZE {
+        emulator.set_register(lane, 2, (lane as i32 - 16) as u32);
+    }
+
+    emulator.run(MAX_STEPS).unwrap();
+
+    assert_eq!(emulator.global().read_u32(0x20), Ok(-16i32 as u32));
+}
+
+#[test]
+fn bar_reduction_is_read_by_b2r() {
+    let mut emulator = emulator(&[
+        encoder::encode_bar(
+            TARGET,
+            7,
+            false,
+            BarMode::RED,
+            BarReductionOperation::POPC,
+            0,
+            true,
+            0,
+            true,
+            1,
+            false,
+        )
+        .unwrap(),
+        encoder::encode_b2r(TARGET, 7, false, 3, 2, B2ROperation::RESULT, 0).unwrap(),
+        exit(),
+    ]);
+
+    for lane in 0..WARP_SIZE {
+        emulator.set_predicate(lane, 1, lane < 5);
+    }
+
+    emulator.run(MAX_STEPS).unwrap();
+
+    for lane in 0..WARP_SIZE {
+        assert_eq!(emulator.register(lane, 3), 5);
+        assert!(emulator.predicate(lane, 2));
+    }
+}
+
+#[test]
+fn reports_errors() {
+    let mut out_of_bounds = emulator(&[encoder::encode_atom(
+        TARGET,
+        7,
+        false,
+        4,
+        1,
+        2,
+        MEMORY_SIZE as i32,
+        AtomPrimitiveType::U32,
+        AtomOperation::ADD,
+        false,
+    )
+    .unwrap()]);
+
+    assert_eq!(
+        out_of_bounds.step(),
+        Err(EmulatorError::OutOfBounds(
+            MemorySpace::Global,
+            MEMORY_SIZE as u64
+        ))
+    );
+    assert_eq!(out_of_bounds.pc(), 0);
+
+    let mut unsupported = emulator(&[encoder::encode_get_lmembase(TARGET, 1).unwrap()]);
+
+    assert_eq!(
+        unsupported.run(MAX_STEPS),
+        Err(EmulatorError::UnsupportedInstruction(
+            0,
+            Opcode::GETLMEMBASE
+        ))
+    );
+
+    let mut empty = emulator(&[]);
+
+    assert_eq!(empty.step(), Err(EmulatorError::EndOfProgram(0)));
+}
+
+/// Offset of a branch from the instruction at `from` to the one at `to`.
+fn offset(from: usize, to: usize) -> i32 {
+    instruction_address(to) as i32 - (instruction_address(from) as i32 + 8)
+}
+
+/// Count the lanes executing the instruction in the global memory.
+fn count(address: i32) -> u64 {
+    encoder::encode_red(
+        TARGET,
+        7,
+        false,
+        RZ,
+        2,
+        address,
+        AtomPrimitiveType::U32,
+        AtomOperation::ADD,
+        false,
+    )
+    .unwrap()
+}
+
+fn counter(emulator: &Emulator, address: u64) -> u32 {
+    emulator.global().read_u32(address).unwrap()
+}
+
+/// Prepare the registers used by count and set P0 on even lanes.
+fn split_lanes(emulator: &mut Emulator) {
+    for lane in 0..WARP_SIZE {
+        emulator.set_register(lane, 2, 1);
+        emulator.set_predicate(lane, 0, lane % 2 == 0);
+    }
+}
+
+#[test]
+fn divergent_branches_reconverge_at_sync() {
+    let mut emulator = emulator(&[
+        encoder::encode_ssy(TARGET, offset(0, 6)).unwrap(),
+        encoder::encode_bra(TARGET, 0, false, ControlCode::TRUE, offset(1, 4)).unwrap(),
+        count(0x0),
+        encoder::encode_sync(TARGET, 7, false, ControlCode::TRUE).unwrap(),
+        count(0x4),
+        encoder::encode_sync(TARGET, 7, false, ControlCode::TRUE).unwrap(),
+        count(0x8),
+        exit(),
+    ]);
+
+    split_lanes(&mut emulator);
+
+    emulator.step().unwrap();
+    emulator.step().unwrap();
+    assert_eq!(emulator.active_mask(), 0x5555_5555);
+    assert_eq!(emulator.pc(), 4);
+    assert_eq!(emulator.stack().len(), 2);
+
+    emulator.run(MAX_STEPS).unwrap();
+
+    assert_eq!(counter(&emulator, 0x0), 16);
+    assert_eq!(counter(&emulator, 0x4), 16);
+    assert_eq!(counter(&emulator, 0x8), 32);
+    assert!(emulator.stack().is_empty());
+}
+
+#[test]
+fn break_and_continue_resume_at_their_entry() {
+    let mut emulator = emulator(&[
+        encoder::encode_pbk(TARGET, offset(0, 7)).unwrap(),
+        encoder::encode_pcnt(TARGET, offset(1, 5)).unwrap(),
+        encoder::encode_cont(TARGET, 0, false, ControlCode::TRUE).unwrap(),
+        count(0x0),
+        encoder::encode_cont(TARGET, 7, false, ControlCode::TRUE).unwrap(),
+        count(0x4),
+        encoder::encode_brk(TARGET, 7, false, ControlCode::TRUE).unwrap(),
+        count(0x8),
+        exit(),
+    ]);
+
+    split_lanes(&mut emulator);
+    emulator.run(MAX_STEPS).unwrap();
+
+    assert_eq!(counter(&emulator, 0x0), 16);
+    assert_eq!(counter(&emulator, 0x4), 32);
+    assert_eq!(counter(&emulator, 0x8), 32);
+}
+
+#[test]
+fn return_resumes_after_the_call() {
+    let mut emulator = emulator(&[
+        encoder::encode_pret(TARGET, offset(0, 2)).unwrap(),
+        encoder::encode_bra(TARGET, 7, false, ControlCode::TRUE, offset(1, 4)).unwrap(),
+        count(0x4),
+        exit(),
+        count(0x0),
+        encoder::encode_ret(TARGET, 0, false, ControlCode::TRUE).unwrap(),
+        encoder::encode_ret(TARGET, 7, false, ControlCode::TRUE).unwrap(),
+    ]);
+
+    split_lanes(&mut emulator);
+    emulator.run(MAX_STEPS).unwrap();
+
+    assert_eq!(counter(&emulator, 0x0), 32);
+    assert_eq!(counter(&emulator, 0x4), 32);
+}
+
+#[test]
+fn exited_lanes_are_not_resumed() {
+    let mut emulator = emulator(&[
+        encoder::encode_ssy(TARGET, offset(0, 4)).unwrap(),
+        encoder::encode_kil(TARGET, 0, false, ControlCode::TRUE).unwrap(),
+        encoder::encode_exit(TARGET, 7, false, ControlCode::TRUE, true).unwrap(),
+        encoder::encode_sync(TARGET, 7, false, ControlCode::TRUE).unwrap(),
+        count(0x0),
+        exit(),
+    ]);
+
+    split_lanes(&mut emulator);
+    emulator.run(MAX_STEPS).unwrap();
+
+    assert_eq!(counter(&emulator, 0x0), 0);
+    assert_eq!(emulator.killed_mask(), 0x5555_5555);
+    assert_eq!(emulator.exited_mask(), u32::MAX);
+    assert_eq!(emulator.refcount_mask(), 0xaaaa_aaaa);
+}
+
+#[test]
+fn reports_stack_errors() {
+    let mut overflow = emulator(&[
+        encoder::encode_ssy(TARGET, offset(0, 2)).unwrap(),
+        encoder::encode_ssy(TARGET, offset(1, 2)).unwrap(),
+        exit(),
+    ]);
+
+    overflow.set_stack_limit(1);
+
+    assert_eq!(
+        overflow.run(MAX_STEPS),
+        Err(EmulatorError::StackOverflow(1))
+    );
+
+    let mut missing_entry = emulator(&[
+        encoder::encode_pbk(TARGET, offset(0, 2)).unwrap(),
+        encoder::encode_cont(TARGET, 7, false, ControlCode::TRUE).unwrap(),
+        exit(),
+    ]);
+
+    assert_eq!(
+        missing_entry.run(MAX_STEPS),
+        Err(EmulatorError::StackUnderflow(1))
+    );
+
+    let mut empty_stack = emulator(&[
+        encoder::encode_sync(TARGET, 7, false, ControlCode::TRUE).unwrap(),
+        exit(),
+    ]);
+
+    assert_eq!(
+        empty_stack.run(MAX_STEPS),
+        Err(EmulatorError::StackUnderflow(0))
+    );
+
+    let mut invalid_target = emulator(&[
+        encoder::encode_bra(TARGET, 7, false, ControlCode::TRUE, 0x100).unwrap(),
+        exit(),
+    ]);
+
+    assert_eq!(
+        invalid_target.run(MAX_STEPS),
+        Err(EmulatorError::InvalidBranchTarget(0, 0x110))
+    );
+}
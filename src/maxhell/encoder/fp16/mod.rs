@@ -0,0 +1,526 @@
+use super::*;
+
+fn encode_half_operand_a(
+    out: &mut u64,
+    source_register_a: u8,
+    a_swizzle: HalfSwizzle,
+    negate_a: bool,
+    absolute_a: bool,
+) {
+    encode_operand1(out, source_register_a);
+
+    let mut inst = HalfOperandAData(*out);
+
+    inst.set_a_swizzle(a_swizzle);
+    inst.set_negate_a(negate_a);
+    inst.set_absolute_a(absolute_a);
+
+    *out = inst.0;
+}
+
+fn encode_half_operand_b(
+    out: &mut u64,
+    source_register_b: u8,
+    b_swizzle: HalfSwizzle,
+    negate_b: bool,
+    absolute_b: bool,
+) {
+    encode_operand2(out, source_register_b);
+
+    let mut inst = HalfOperandBData(*out);
+
+    inst.set_b_swizzle(b_swizzle);
+    inst.set_negate_b(negate_b);
+    inst.set_absolute_b(absolute_b);
+
+    *out = inst.0;
+}
+
+fn encode_half_immediate(
+    out: &mut u64,
+    opcode: Opcode,
+    immediate: HalfPair,
+) -> Result<(), EncodeError> {
+    if !immediate.is_exact() {
+        return Err(EncodeError::InexactHalfImmediate(opcode, immediate));
+    }
+
+    let mut inst = HalfImmediateData(*out);
+
+    inst.set_immediate_low((immediate.low >> 6) & 0x1FF);
+    inst.set_negate_immediate_low((immediate.low & 0x8000) != 0);
+    inst.set_immediate_high((immediate.high >> 6) & 0x1FF);
+    inst.set_negate_immediate_high((immediate.high & 0x8000) != 0);
+
+    *out = inst.0;
+
+    Ok(())
+}
+
+pub fn encode_hadd2(
+    target: Target,
+    source_predicate_register: u8,
+    invert_source_predicate: bool,
+    destination_register: u8,
+    source_register_a: u8,
+    a_swizzle: HalfSwizzle,
+    negate_a: bool,
+    absolute_a: bool,
+    source_register_b: u8,
+    b_swizzle: HalfSwizzle,
+    negate_b: bool,
+    absolute_b: bool,
+    merge: HalfMerge,
+    ftz_flag: bool,
+    sat_flag: bool,
+) -> Result<u64, EncodeError> {
+    let mut inst = Hadd2Instruction(0);
+
+    encode_opcode(&mut inst.0, target, Opcode::HADD2)?;
+    encode_source_predicate(
+        &mut inst.0,
+        source_predicate_register,
+        invert_source_predicate,
+    );
+    encode_operand0(&mut inst.0, destination_register);
+    encode_half_operand_a(
+        &mut inst.0,
+        source_register_a,
+        a_swizzle,
+        negate_a,
+        absolute_a,
+    );
+    encode_half_operand_b(
+        &mut inst.0,
+        source_register_b,
+        b_swizzle,
+        negate_b,
+        absolute_b,
+    );
+
+    inst.set_merge(merge);
+    inst.set_ftz_flag(ftz_flag);
+    inst.set_sat_flag(sat_flag);
+
+    Ok(inst.0)
+}
+
+pub fn encode_hadd2_imm(
+    target: Target,
+    source_predicate_register: u8,
+    invert_source_predicate: bool,
+    destination_register: u8,
+    source_register_a: u8,
+    a_swizzle: HalfSwizzle,
+    negate_a: bool,
+    absolute_a: bool,
+    immediate: HalfPair,
+    merge: HalfMerge,
+    ftz_flag: bool,
+    sat_flag: bool,
+) -> Result<u64, EncodeError> {
+    let mut inst = Hadd2ImmInstruction(0);
+
+    encode_opcode(&mut inst.0, target, Opcode::HADD2_IMM)?;
+    encode_source_predicate(
+        &mut inst.0,
+        source_predicate_register,
+        invert_source_predicate,
+    );
+    encode_operand0(&mut inst.0, destination_register);
+    encode_half_operand_a(
+        &mut inst.0,
+        source_register_a,
+        a_swizzle,
+        negate_a,
+        absolute_a,
+    );
+    encode_half_immediate(&mut inst.0, Opcode::HADD2_IMM, immediate)?;
+
+    inst.set_merge(merge);
+    inst.set_ftz_flag(ftz_flag);
+    inst.set_sat_flag(sat_flag);
+
+    Ok(inst.0)
+}
+
+pub fn encode_hmul2(
+    target: Target,
+    source_predicate_register: u8,
+    invert_source_predicate: bool,
+    destination_register: u8,
+    source_register_a: u8,
+    a_swizzle: HalfSwizzle,
+    negate_a: bool,
+    absolute_a: bool,
+    source_register_b: u8,
+    b_swizzle: HalfSwizzle,
+    negate_b: bool,
+    absolute_b: bool,
+    merge: HalfMerge,
+    multiply_mode: FloatMultiplyMode,
+    sat_flag: bool,
+) -> Result<u64, EncodeError> {
+    let mut inst = Hmul2Instruction(0);
+
+    encode_opcode(&mut inst.0, target, Opcode::HMUL2)?;
+    encode_source_predicate(
+        &mut inst.0,
+        source_predicate_register,
+        invert_source_predicate,
+    );
+    encode_operand0(&mut inst.0, destination_register);
+    encode_half_operand_a(
+        &mut inst.0,
+        source_register_a,
+        a_swizzle,
+        negate_a,
+        absolute_a,
+    );
+    encode_half_operand_b(
+        &mut inst.0,
+        source_register_b,
+        b_swizzle,
+        negate_b,
+        absolute_b,
+    );
+
+    inst.set_merge(merge);
+    inst.set_multiply_mode(multiply_mode);
+    inst.set_sat_flag(sat_flag);
+
+    Ok(inst.0)
+}
+
+pub fn encode_hmul2_imm(
+    target: Target,
+    source_predicate_register: u8,
+    invert_source_predicate: bool,
+    destination_register: u8,
+    source_register_a: u8,
+    a_swizzle: HalfSwizzle,
+    negate_a: bool,
+    absolute_a: bool,
+    immediate: HalfPair,
+    merge: HalfMerge,
+    multiply_mode: FloatMultiplyMode,
+    sat_flag: bool,
+) -> Result<u64, EncodeError> {
+    let mut inst = Hmul2ImmInstruction(0);
+
+    encode_opcode(&mut inst.0, target, Opcode::HMUL2_IMM)?;
+    encode_source_predicate(
+        &mut inst.0,
+        source_predicate_register,
+        invert_source_predicate,
+    );
+    encode_operand0(&mut inst.0, destination_register);
+    encode_half_operand_a(
+        &mut inst.0,
+        source_register_a,
+        a_swizzle,
+        negate_a,
+        absolute_a,
+    );
+    encode_half_immediate(&mut inst.0, Opcode::HMUL2_IMM, immediate)?;
+
+    inst.set_merge(merge);
+    inst.set_multiply_mode(multiply_mode);
+    inst.set_sat_flag(sat_flag);
+
+    Ok(inst.0)
+}
+
+pub fn encode_hfma2(
+    target: Target,
+    source_predicate_register: u8,
+    invert_source_predicate: bool,
+    destination_register: u8,
+    source_register_a: u8,
+    a_swizzle: HalfSwizzle,
+    source_register_b: u8,
+    b_swizzle: HalfSwizzle,
+    negate_b: bool,
+    source_register_c: u8,
+    c_swizzle: HalfSwizzle,
+    negate_c: bool,
+    merge: HalfMerge,
+    multiply_mode: FloatMultiplyMode,
+    sat_flag: bool,
+) -> Result<u64, EncodeError> {
+    let mut inst = Hfma2Instruction(0);
+
+    encode_opcode(&mut inst.0, target, Opcode::HFMA2)?;
+    encode_source_predicate(
+        &mut inst.0,
+        source_predicate_register,
+        invert_source_predicate,
+    );
+    encode_operand0(&mut inst.0, destination_register);
+    encode_operand1(&mut inst.0, source_register_a);
+    encode_operand2(&mut inst.0, source_register_b);
+    encode_operand3(&mut inst.0, source_register_c);
+
+    inst.set_a_swizzle(a_swizzle);
+    inst.set_b_swizzle(b_swizzle);
+    inst.set_negate_b(negate_b);
+    inst.set_c_swizzle(c_swizzle);
+    inst.set_negate_c(negate_c);
+    inst.set_merge(merge);
+    inst.set_multiply_mode(multiply_mode);
+    inst.set_sat_flag(sat_flag);
+
+    Ok(inst.0)
+}
+
+pub fn encode_hfma2_imm(
+    target: Target,
+    source_predicate_register: u8,
+    invert_source_predicate: bool,
+    destination_register: u8,
+    source_register_a: u8,
+    a_swizzle: HalfSwizzle,
+    immediate: HalfPair,
+    source_register_c: u8,
+    c_swizzle: HalfSwizzle,
+    negate_c: bool,
+    merge: HalfMerge,
+    multiply_mode: FloatMultiplyMode,
+    sat_flag: bool,
+) -> Result<u64, EncodeError> {
+    let mut inst = Hfma2ImmInstruction(0);
+
+    encode_opcode(&mut inst.0, target, Opcode::HFMA2_IMM)?;
+    encode_source_predicate(
+        &mut inst.0,
+        source_predicate_register,
+        invert_source_predicate,
+    );
+    encode_operand0(&mut inst.0, destination_register);
+    encode_operand1(&mut inst.0, source_register_a);
+    encode_half_immediate(&mut inst.0, Opcode::HFMA2_IMM, immediate)?;
+    encode_operand3(&mut inst.0, source_register_c);
+
+    inst.set_a_swizzle(a_swizzle);
+    inst.set_c_swizzle(c_swizzle);
+    inst.set_negate_c(negate_c);
+    inst.set_merge(merge);
+    inst.set_multiply_mode(multiply_mode);
+    inst.set_sat_flag(sat_flag);
+
+    Ok(inst.0)
+}
+
+pub fn encode_hsetp2(
+    target: Target,
+    source_predicate_register: u8,
+    invert_source_predicate: bool,
+    destination_predicate_register_a: u8,
+    destination_predicate_register_b: u8,
+    source_register_a: u8,
+    a_swizzle: HalfSwizzle,
+    negate_a: bool,
+    absolute_a: bool,
+    source_register_b: u8,
+    b_swizzle: HalfSwizzle,
+    negate_b: bool,
+    absolute_b: bool,
+    compare_operation: FloatCompareOperation,
+    predicate_operation: PredicateOperation,
+    combine_predicate_register: u8,
+    invert_combine_predicate: bool,
+    h_and_flag: bool,
+    ftz_flag: bool,
+) -> Result<u64, EncodeError> {
+    debug_assert!(destination_predicate_register_a < 8);
+    debug_assert!(destination_predicate_register_b < 8);
+    debug_assert!(combine_predicate_register < 8);
+
+    let mut inst = Hsetp2Instruction(0);
+
+    encode_opcode(&mut inst.0, target, Opcode::HSETP2)?;
+    encode_source_predicate(
+        &mut inst.0,
+        source_predicate_register,
+        invert_source_predicate,
+    );
+    encode_half_operand_a(
+        &mut inst.0,
+        source_register_a,
+        a_swizzle,
+        negate_a,
+        absolute_a,
+    );
+    encode_half_operand_b(
+        &mut inst.0,
+        source_register_b,
+        b_swizzle,
+        negate_b,
+        absolute_b,
+    );
+
+    inst.set_destination_predicate_register_a(destination_predicate_register_a);
+    inst.set_destination_predicate_register_b(destination_predicate_register_b);
+    inst.set_compare_operation(compare_operation);
+    inst.set_predicate_operation(predicate_operation);
+    inst.set_combine_predicate_register(combine_predicate_register);
+    inst.set_invert_combine_predicate(invert_combine_predicate);
+    inst.set_h_and_flag(h_and_flag);
+    inst.set_ftz_flag(ftz_flag);
+
+    Ok(inst.0)
+}
+
+pub fn encode_hsetp2_imm(
+    target: Target,
+    source_predicate_register: u8,
+    invert_source_predicate: bool,
+    destination_predicate_register_a: u8,
+    destination_predicate_register_b: u8,
+    source_register_a: u8,
+    a_swizzle: HalfSwizzle,
+    negate_a: bool,
+    absolute_a: bool,
+    immediate: HalfPair,
+    compare_operation: FloatCompareOperation,
+    predicate_operation: PredicateOperation,
+    combine_predicate_register: u8,
+    invert_combine_predicate: bool,
+    h_and_flag: bool,
+    ftz_flag: bool,
+) -> Result<u64, EncodeError> {
+    debug_assert!(destination_predicate_register_a < 8);
+    debug_assert!(destination_predicate_register_b < 8);
+    debug_assert!(combine_predicate_register < 8);
+
+    let mut inst = Hsetp2ImmInstruction(0);
+
+    encode_opcode(&mut inst.0, target, Opcode::HSETP2_IMM)?;
+    encode_source_predicate(
+        &mut inst.0,
+        source_predicate_register,
+        invert_source_predicate,
+    );
+    encode_half_operand_a(
+        &mut inst.0,
+        source_register_a,
+        a_swizzle,
+        negate_a,
+        absolute_a,
+    );
+    encode_half_immediate(&mut inst.0, Opcode::HSETP2_IMM, immediate)?;
+
+    inst.set_destination_predicate_register_a(destination_predicate_register_a);
+    inst.set_destination_predicate_register_b(destination_predicate_register_b);
+    inst.set_compare_operation(compare_operation);
+    inst.set_predicate_operation(predicate_operation);
+    inst.set_combine_predicate_register(combine_predicate_register);
+    inst.set_invert_combine_predicate(invert_combine_predicate);
+    inst.set_h_and_flag(h_and_flag);
+    inst.set_ftz_flag(ftz_flag);
+
+    Ok(inst.0)
+}
+
+pub fn encode_hset2(
+    target: Target,
+    source_predicate_register: u8,
+    invert_source_predicate: bool,
+    destination_register: u8,
+    source_register_a: u8,
+    a_swizzle: HalfSwizzle,
+    negate_a: bool,
+    absolute_a: bool,
+    source_register_b: u8,
+    b_swizzle: HalfSwizzle,
+    negate_b: bool,
+    absolute_b: bool,
+    compare_operation: FloatCompareOperation,
+    predicate_operation: PredicateOperation,
+    combine_predicate_register: u8,
+    invert_combine_predicate: bool,
+    bf_flag: bool,
+    ftz_flag: bool,
+) -> Result<u64, EncodeError> {
+    debug_assert!(combine_predicate_register < 8);
+
+    let mut inst = Hset2Instruction(0);
+
+    encode_opcode(&mut inst.0, target, Opcode::HSET2)?;
+    encode_source_predicate(
+        &mut inst.0,
+        source_predicate_register,
+        invert_source_predicate,
+    );
+    encode_operand0(&mut inst.0, destination_register);
+    encode_half_operand_a(
+        &mut inst.0,
+        source_register_a,
+        a_swizzle,
+        negate_a,
+        absolute_a,
+    );
+    encode_half_operand_b(
+        &mut inst.0,
+        source_register_b,
+        b_swizzle,
+        negate_b,
+        absolute_b,
+    );
+
+    inst.set_compare_operation(compare_operation);
+    inst.set_predicate_operation(predicate_operation);
+    inst.set_combine_predicate_register(combine_predicate_register);
+    inst.set_invert_combine_predicate(invert_combine_predicate);
+    inst.set_bf_flag(bf_flag);
+    inst.set_ftz_flag(ftz_flag);
+
+    Ok(inst.0)
+}
+
+pub fn encode_hset2_imm(
+    target: Target,
+    source_predicate_register: u8,
+    invert_source_predicate: bool,
+    destination_register: u8,
+    source_register_a: u8,
+    a_swizzle: HalfSwizzle,
+    negate_a: bool,
+    absolute_a: bool,
+    immediate: HalfPair,
+    compare_operation: FloatCompareOperation,
+    predicate_operation: PredicateOperation,
+    combine_predicate_register: u8,
+    invert_combine_predicate: bool,
+    bf_flag: bool,
+    ftz_flag: bool,
+) -> Result<u64, EncodeError> {
+    debug_assert!(combine_predicate_register < 8);
+
+    let mut inst = Hset2ImmInstruction(0);
+
+    encode_opcode(&mut inst.0, target, Opcode::HSET2_IMM)?;
+    encode_source_predicate(
+        &mut inst.0,
+        source_predicate_register,
+        invert_source_predicate,
+    );
+    encode_operand0(&mut inst.0, destination_register);
+    encode_half_operand_a(
+        &mut inst.0,
+        source_register_a,
+        a_swizzle,
+        negate_a,
+        absolute_a,
+    );
+    encode_half_immediate(&mut inst.0, Opcode::HSET2_IMM, immediate)?;
+
+    inst.set_compare_operation(compare_operation);
+    inst.set_predicate_operation(predicate_operation);
+    inst.set_combine_predicate_register(combine_predicate_register);
+    inst.set_invert_combine_predicate(invert_combine_predicate);
+    inst.set_bf_flag(bf_flag);
+    inst.set_ftz_flag(ftz_flag);
+
+    Ok(inst.0)
+}
@@ -1,13 +1,121 @@
 #![allow(dead_code, clippy::too_many_arguments)]
 
+use std::error::Error;
+use std::fmt;
+
 use super::definition::*;
+use super::target::Target;
+
+mod fp16;
+#[cfg(test)]
+mod tests;
+
+pub use fp16::*;
+
+/// Error returned by the encoders.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum EncodeError {
+    /// The instruction isn't available on the selected target.
+    UnsupportedInstruction(Opcode, Target),
+    /// The atomic operation cannot be performed on the primitive type.
+    InvalidAtomicOperation(Opcode, AtomOperation, AtomPrimitiveType),
+    /// Atomics on the primitive type aren't available on the selected target.
+    UnsupportedAtomicType(Opcode, AtomPrimitiveType, Target),
+    /// The texture instruction has no encoding for the texture type, LOD mode and flags.
+    UnsupportedTextureMode(Opcode, TextureType, TextureLodMode),
+    /// The texture instruction cannot write the set of components.
+    UnsupportedComponentMask(Opcode, u8),
+    /// The register given as operand B of a video instruction doesn't exist.
+    InvalidVideoRegister(Opcode, u16),
+    /// The register form of a video instruction requires a selector for operand B.
+    MissingVideoSelector(Opcode),
+    /// The immediate form of a video instruction doesn't have any selector for operand B.
+    UnexpectedVideoSelector(Opcode, VideoSelector),
+    /// The half-precision immediate would lose precision once encoded.
+    InexactHalfImmediate(Opcode, HalfPair),
+}
 
-fn encode_opcode(out: &mut u64, opcode: Opcode) {
-    let mut inst = Instruction(0);
+impl fmt::Display for EncodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EncodeError::UnsupportedInstruction(opcode, target) => {
+                write!(f, "{:?} is not supported on {}", opcode, target)
+            }
+            EncodeError::InvalidAtomicOperation(opcode, operation, type_size) => write!(
+                f,
+                "{:?} cannot perform {:?} on {:?}",
+                opcode, operation, type_size
+            ),
+            EncodeError::UnsupportedAtomicType(opcode, type_size, target) => write!(
+                f,
+                "{:?} on {:?} is not supported on {}",
+                opcode, type_size, target
+            ),
+            EncodeError::UnsupportedTextureMode(opcode, texture_type, lod_mode) => write!(
+                f,
+                "{:?} cannot encode {:?} with {:?} and the given flags",
+                opcode, texture_type, lod_mode
+            ),
+            EncodeError::UnsupportedComponentMask(opcode, component_mask) => write!(
+                f,
+                "{:?} cannot write the components 0x{:x}",
+                opcode, component_mask
+            ),
+            EncodeError::InvalidVideoRegister(opcode, register) => {
+                write!(f, "{:?} cannot read operand B from R{}", opcode, register)
+            }
+            EncodeError::MissingVideoSelector(opcode) => {
+                write!(f, "{:?} requires a selector for operand B", opcode)
+            }
+            EncodeError::UnexpectedVideoSelector(opcode, selector) => write!(
+                f,
+                "{:?} cannot use the selector {:?} with an immediate",
+                opcode, selector
+            ),
+            EncodeError::InexactHalfImmediate(opcode, immediate) => write!(
+                f,
+                "{:?} cannot encode the immediate ({}, {}) exactly",
+                opcode,
+                immediate.low(),
+                immediate.high()
+            ),
+        }
+    }
+}
 
-    inst.set_opcode(opcode);
+impl Error for EncodeError {}
 
-    *out = inst.0;
+fn encode_opcode(out: &mut u64, target: Target, opcode: Opcode) -> Result<(), EncodeError> {
+    if !target.supports(opcode) {
+        return Err(EncodeError::UnsupportedInstruction(opcode, target));
+    }
+
+    *out = opcode.value();
+
+    Ok(())
+}
+
+fn encode_atomic_operation(
+    target: Target,
+    opcode: Opcode,
+    operation: AtomOperation,
+    type_size: AtomPrimitiveType,
+) -> Result<(), EncodeError> {
+    let is_reduction = opcode == Opcode::RED || opcode == Opcode::SURED;
+
+    if !target.supports_atomic_type(type_size) {
+        return Err(EncodeError::UnsupportedAtomicType(
+            opcode, type_size, target,
+        ));
+    }
+
+    if !operation.is_valid(&type_size, is_reduction) {
+        return Err(EncodeError::InvalidAtomicOperation(
+            opcode, operation, type_size,
+        ));
+    }
+
+    Ok(())
 }
 
 fn encode_imm8(out: &mut u64, value: u8) {
@@ -85,30 +193,56 @@ fn encode_destination_predicate(out: &mut u64, predicate_register: u8) {
     *out = inst.0;
 }
 
-pub fn encode_ram() -> u64 {
+fn encode_video_register_b(
+    opcode: Opcode,
+    source_b: u16,
+    b_selector: Option<VideoSelector>,
+) -> Result<(u8, VideoSelector), EncodeError> {
+    if source_b > 0xFF {
+        return Err(EncodeError::InvalidVideoRegister(opcode, source_b));
+    }
+
+    b_selector
+        .map(|b_selector| (source_b as u8, b_selector))
+        .ok_or(EncodeError::MissingVideoSelector(opcode))
+}
+
+fn check_video_immediate_b(
+    opcode: Opcode,
+    b_selector: Option<VideoSelector>,
+) -> Result<(), EncodeError> {
+    // NOTE: The immediate form doesn't have any selector for operand B.
+    match b_selector {
+        Some(b_selector) => Err(EncodeError::UnexpectedVideoSelector(opcode, b_selector)),
+        None => Ok(()),
+    }
+}
+
+pub fn encode_ram(target: Target) -> Result<u64, EncodeError> {
     let mut inst = RamInstruction(0);
 
-    encode_opcode(&mut inst.0, Opcode::RAM);
+    encode_opcode(&mut inst.0, target, Opcode::RAM)?;
 
-    inst.0
+    Ok(inst.0)
 }
 
-pub fn encode_sam() -> u64 {
+pub fn encode_sam(target: Target) -> Result<u64, EncodeError> {
     let mut inst = SamInstruction(0);
 
-    encode_opcode(&mut inst.0, Opcode::SAM);
+    encode_opcode(&mut inst.0, target, Opcode::SAM)?;
 
-    inst.0
+    Ok(inst.0)
 }
 
 pub fn encode_ret(
+    target: Target,
     source_predicate_register: u8,
     invert_source_predicate: bool,
     control_code: ControlCode,
-) -> u64 {
+) -> Result<u64, EncodeError> {
     let mut inst = RetInstruction(0);
 
-    encode_opcode(&mut inst.0, Opcode::RET);
+    encode_opcode(&mut inst.0, target, Opcode::RET)?;
     encode_source_predicate(
         &mut inst.0,
         source_predicate_register,
@@ -117,18 +251,19 @@ pub fn encode_ret(
 
     inst.set_cc_flags(control_code);
 
-    inst.0
+    Ok(inst.0)
 }
 
 pub fn encode_exit(
+    target: Target,
     source_predicate_register: u8,
     invert_source_predicate: bool,
     control_code: ControlCode,
     keep_refcount: bool,
-) -> u64 {
+) -> Result<u64, EncodeError> {
     let mut inst = ExitInstruction(0);
 
-    encode_opcode(&mut inst.0, Opcode::EXIT);
+    encode_opcode(&mut inst.0, target, Opcode::EXIT)?;
     encode_source_predicate(
         &mut inst.0,
         source_predicate_register,
@@ -138,19 +273,148 @@ pub fn encode_exit(
     inst.set_cc_flags(control_code);
     inst.set_keep_refcount(keep_refcount);
 
-    inst.0
+    Ok(inst.0)
+}
+
+fn encode_branch_offset(offset: i32) -> i32 {
+    debug_assert!(offset % 8 == 0);
+    debug_assert!((-(1 << 23)..(1 << 23)).contains(&offset));
+
+    offset
+}
+
+/// Encode a relative branch.
+///
+/// The offset is relative to the address of the next instruction, in bytes.
+pub fn encode_bra(
+    target: Target,
+    source_predicate_register: u8,
+    invert_source_predicate: bool,
+    control_code: ControlCode,
+    offset: i32,
+) -> Result<u64, EncodeError> {
+    let mut inst = BraInstruction(0);
+
+    encode_opcode(&mut inst.0, target, Opcode::BRA)?;
+    encode_source_predicate(
+        &mut inst.0,
+        source_predicate_register,
+        invert_source_predicate,
+    );
+
+    inst.set_cc_flags(control_code);
+    inst.set_offset(encode_branch_offset(offset));
+
+    Ok(inst.0)
+}
+
+fn encode_flow_push(target: Target, opcode: Opcode, offset: i32) -> Result<u64, EncodeError> {
+    let mut inst = FlowPushInstruction(0);
+
+    encode_opcode(&mut inst.0, target, opcode)?;
+
+    inst.set_offset(encode_branch_offset(offset));
+
+    Ok(inst.0)
+}
+
+fn encode_flow_pop(
+    target: Target,
+    opcode: Opcode,
+    source_predicate_register: u8,
+    invert_source_predicate: bool,
+    control_code: ControlCode,
+) -> Result<u64, EncodeError> {
+    let mut inst = RetInstruction(0);
+
+    encode_opcode(&mut inst.0, target, opcode)?;
+    encode_source_predicate(
+        &mut inst.0,
+        source_predicate_register,
+        invert_source_predicate,
+    );
+
+    inst.set_cc_flags(control_code);
+
+    Ok(inst.0)
+}
+
+/// Push the reconvergence point of a divergent region, reached by Opcode::SYNC.
+pub fn encode_ssy(target: Target, offset: i32) -> Result<u64, EncodeError> {
+    encode_flow_push(target, Opcode::SSY, offset)
+}
+
+pub fn encode_sync(
+    target: Target,
+    source_predicate_register: u8,
+    invert_source_predicate: bool,
+    control_code: ControlCode,
+) -> Result<u64, EncodeError> {
+    encode_flow_pop(
+        target,
+        Opcode::SYNC,
+        source_predicate_register,
+        invert_source_predicate,
+        control_code,
+    )
+}
+
+/// Push the exit point of a loop, reached by Opcode::BRK.
+pub fn encode_pbk(target: Target, offset: i32) -> Result<u64, EncodeError> {
+    encode_flow_push(target, Opcode::PBK, offset)
+}
+
+pub fn encode_brk(
+    target: Target,
+    source_predicate_register: u8,
+    invert_source_predicate: bool,
+    control_code: ControlCode,
+) -> Result<u64, EncodeError> {
+    encode_flow_pop(
+        target,
+        Opcode::BRK,
+        source_predicate_register,
+        invert_source_predicate,
+        control_code,
+    )
+}
+
+/// Push the next iteration point of a loop, reached by Opcode::CONT.
+pub fn encode_pcnt(target: Target, offset: i32) -> Result<u64, EncodeError> {
+    encode_flow_push(target, Opcode::PCNT, offset)
+}
+
+pub fn encode_cont(
+    target: Target,
+    source_predicate_register: u8,
+    invert_source_predicate: bool,
+    control_code: ControlCode,
+) -> Result<u64, EncodeError> {
+    encode_flow_pop(
+        target,
+        Opcode::CONT,
+        source_predicate_register,
+        invert_source_predicate,
+        control_code,
+    )
+}
+
+/// Push the return address of a subroutine, reached by Opcode::RET.
+pub fn encode_pret(target: Target, offset: i32) -> Result<u64, EncodeError> {
+    encode_flow_push(target, Opcode::PRET, offset)
 }
 
 pub fn encode_nop(
+    target: Target,
     trigger: bool,
     source_predicate_register: u8,
     invert_source_predicate: bool,
     value: u16,
     control_code: ControlCode,
-) -> u64 {
+) -> Result<u64, EncodeError> {
     let mut inst = NopInstruction(0);
 
-    encode_opcode(&mut inst.0, Opcode::NOP);
+    encode_opcode(&mut inst.0, target, Opcode::NOP)?;
     encode_imm16(&mut inst.0, value);
     encode_source_predicate(
         &mut inst.0,
@@ -161,35 +425,36 @@ pub fn encode_nop(
     inst.set_trigger(trigger);
     inst.set_cc_flags(control_code);
 
-    inst.0
+    Ok(inst.0)
 }
 
-pub fn encode_get_lmembase(register: u8) -> u64 {
+pub fn encode_get_lmembase(target: Target, register: u8) -> Result<u64, EncodeError> {
     let mut inst = GetLMEMBASEInstruction(0);
 
-    encode_opcode(&mut inst.0, Opcode::GETLMEMBASE);
+    encode_opcode(&mut inst.0, target, Opcode::GETLMEMBASE)?;
     encode_operand0(&mut inst.0, register);
 
-    inst.0
+    Ok(inst.0)
 }
 
-pub fn encode_ide(value: u16, disabe: bool) -> u64 {
+pub fn encode_ide(target: Target, value: u16, disable: bool) -> Result<u64, EncodeError> {
     let mut inst = IdeInstruction(0);
 
-    encode_opcode(&mut inst.0, Opcode::IDE);
+    encode_opcode(&mut inst.0, target, Opcode::IDE)?;
     encode_imm16(&mut inst.0, value);
-    inst.set_disabe(disabe);
+    inst.set_disable(disable);
 
-    inst.0
+    Ok(inst.0)
 }
 
 pub fn encode_kil(
+    target: Target,
     source_predicate_register: u8,
     invert_source_predicate: bool,
     control_code: ControlCode,
-) -> u64 {
+) -> Result<u64, EncodeError> {
     let mut inst = KilInstruction(0);
-    encode_opcode(&mut inst.0, Opcode::KIL);
+    encode_opcode(&mut inst.0, target, Opcode::KIL)?;
     encode_source_predicate(
         &mut inst.0,
         source_predicate_register,
@@ -198,19 +463,20 @@ pub fn encode_kil(
 
     inst.set_cc_flags(control_code);
 
-    inst.0
+    Ok(inst.0)
 }
 
-pub fn encode_set_lmembase(register: u8) -> u64 {
+pub fn encode_set_lmembase(target: Target, register: u8) -> Result<u64, EncodeError> {
     let mut inst = SetLMEMBASEInstruction(0);
 
-    encode_opcode(&mut inst.0, Opcode::SETLMEMBASE);
+    encode_opcode(&mut inst.0, target, Opcode::SETLMEMBASE)?;
     encode_operand1(&mut inst.0, register);
 
-    inst.0
+    Ok(inst.0)
 }
 
 pub fn encode_al2p(
+    target: Target,
     source_predicate_register: u8,
     invert_source_predicate: bool,
     destination_predicate_register: u8,
@@ -219,12 +485,12 @@ pub fn encode_al2p(
     o_flag: bool,
     mode: AtributeLoadMode,
     load_offset: i16,
-) -> u64 {
+) -> Result<u64, EncodeError> {
     debug_assert!(destination_predicate_register < 8);
 
     let mut inst = Al2pInstruction(0);
 
-    encode_opcode(&mut inst.0, Opcode::AL2P);
+    encode_opcode(&mut inst.0, target, Opcode::AL2P)?;
     encode_source_predicate(
         &mut inst.0,
         source_predicate_register,
@@ -239,10 +505,11 @@ pub fn encode_al2p(
     inst.set_mode(mode);
     inst.set_load_offset(load_offset);
 
-    inst.0
+    Ok(inst.0)
 }
 
 pub fn encode_ald(
+    target: Target,
     source_predicate_register: u8,
     invert_source_predicate: bool,
     destionation_register: u8,
@@ -252,10 +519,10 @@ pub fn encode_ald(
     o_flag: bool,
     mode: AtributeLoadMode,
     load_offset: i16,
-) -> u64 {
+) -> Result<u64, EncodeError> {
     let mut inst = AldInstruction(0);
 
-    encode_opcode(&mut inst.0, Opcode::ALD);
+    encode_opcode(&mut inst.0, target, Opcode::ALD)?;
     encode_source_predicate(
         &mut inst.0,
         source_predicate_register,
@@ -270,10 +537,11 @@ pub fn encode_ald(
     inst.set_mode(mode);
     inst.set_load_offset(load_offset);
 
-    inst.0
+    Ok(inst.0)
 }
 
 pub fn encode_ast(
+    target: Target,
     source_predicate_register: u8,
     invert_source_predicate: bool,
     destionation_offset_register: u8,
@@ -282,10 +550,10 @@ pub fn encode_ast(
     no_physical_flag: bool,
     mode: AtributeLoadMode,
     load_offset: i16,
-) -> u64 {
+) -> Result<u64, EncodeError> {
     let mut inst = AstInstruction(0);
 
-    encode_opcode(&mut inst.0, Opcode::AST);
+    encode_opcode(&mut inst.0, target, Opcode::AST)?;
     encode_source_predicate(
         &mut inst.0,
         source_predicate_register,
@@ -294,15 +562,16 @@ pub fn encode_ast(
     encode_operand0(&mut inst.0, destionation_offset_register);
     encode_operand1(&mut inst.0, source_register_a);
 
-    inst.set_source_registerb(source_register_b);
+    inst.set_source_register_b(source_register_b);
     inst.set_no_physical_flag(no_physical_flag);
     inst.set_mode(mode);
     inst.set_load_offset(load_offset);
 
-    inst.0
+    Ok(inst.0)
 }
 
 pub fn encode_atoms(
+    target: Target,
     source_predicate_register: u8,
     invert_source_predicate: bool,
     destination_register: u8,
@@ -311,10 +580,10 @@ pub fn encode_atoms(
     register_a_offset: i32,
     type_size: AtomsPrimitiveType,
     operation: AtomsOperation,
-) -> u64 {
+) -> Result<u64, EncodeError> {
     let mut inst = AtomsIntruction(0);
 
-    encode_opcode(&mut inst.0, Opcode::ATOMS);
+    encode_opcode(&mut inst.0, target, Opcode::ATOMS)?;
     encode_source_predicate(
         &mut inst.0,
         source_predicate_register,
@@ -331,10 +600,11 @@ pub fn encode_atoms(
     inst.set_register_a_offset_shr_2(register_a_offset >> 2);
     inst.set_operation(operation);
 
-    inst.0
+    Ok(inst.0)
 }
 
 pub fn encode_atoms_cas(
+    target: Target,
     source_predicate_register: u8,
     invert_source_predicate: bool,
     destination_register: u8,
@@ -343,12 +613,12 @@ pub fn encode_atoms_cas(
     register_a_offset: i32,
     type_size: AtomicCasPrimitiveType,
     operation: AtomsCasOperation,
-) -> u64 {
+) -> Result<u64, EncodeError> {
     debug_assert!(source_register_b != 0);
 
     let mut inst = AtomsCasIntruction(0);
 
-    encode_opcode(&mut inst.0, Opcode::ATOMS_CAS);
+    encode_opcode(&mut inst.0, target, Opcode::ATOMS_CAS)?;
     encode_source_predicate(
         &mut inst.0,
         source_predicate_register,
@@ -356,17 +626,18 @@ pub fn encode_atoms_cas(
     );
     encode_operand0(&mut inst.0, destination_register);
     encode_operand1(&mut inst.0, source_register_a);
-    encode_operand2(&mut inst.0, source_register_b - 1);
 
+    inst.set_source_register_b(source_register_b);
     inst.set_type_size(type_size);
 
     inst.set_register_a_offset_shr_2(register_a_offset >> 2);
     inst.set_operation(operation);
 
-    inst.0
+    Ok(inst.0)
 }
 
 pub fn encode_atom(
+    target: Target,
     source_predicate_register: u8,
     invert_source_predicate: bool,
     destination_register: u8,
@@ -376,10 +647,11 @@ pub fn encode_atom(
     type_size: AtomPrimitiveType,
     operation: AtomOperation,
     e_flag: bool,
-) -> u64 {
+) -> Result<u64, EncodeError> {
     let mut inst = AtomInstruction(0);
 
-    encode_opcode(&mut inst.0, Opcode::ATOM);
+    encode_opcode(&mut inst.0, target, Opcode::ATOM)?;
+    encode_atomic_operation(target, Opcode::ATOM, operation, type_size)?;
     encode_source_predicate(
         &mut inst.0,
         source_predicate_register,
@@ -395,10 +667,43 @@ pub fn encode_atom(
     inst.set_operation(operation);
     inst.set_e_flag(e_flag);
 
-    inst.0
+    Ok(inst.0)
+}
+
+pub fn encode_red(
+    target: Target,
+    source_predicate_register: u8,
+    invert_source_predicate: bool,
+    source_register_a: u8,
+    source_register_b: u8,
+    register_a_offset: i32,
+    type_size: AtomPrimitiveType,
+    operation: AtomOperation,
+    e_flag: bool,
+) -> Result<u64, EncodeError> {
+    let mut inst = RedInstruction(0);
+
+    encode_opcode(&mut inst.0, target, Opcode::RED)?;
+    encode_atomic_operation(target, Opcode::RED, operation, type_size)?;
+    encode_source_predicate(
+        &mut inst.0,
+        source_predicate_register,
+        invert_source_predicate,
+    );
+    encode_operand0(&mut inst.0, source_register_b);
+    encode_operand1(&mut inst.0, source_register_a);
+
+    inst.set_type_size(type_size);
+
+    inst.set_register_a_offset(register_a_offset);
+    inst.set_operation(operation);
+    inst.set_e_flag(e_flag);
+
+    Ok(inst.0)
 }
 
 pub fn encode_atom_cas(
+    target: Target,
     source_predicate_register: u8,
     invert_source_predicate: bool,
     destination_register: u8,
@@ -407,10 +712,10 @@ pub fn encode_atom_cas(
     register_a_offset: i32,
     type_size: AtomicCasPrimitiveType,
     e_flag: bool,
-) -> u64 {
+) -> Result<u64, EncodeError> {
     let mut inst = AtomCasInstruction(0);
 
-    encode_opcode(&mut inst.0, Opcode::ATOM_CAS);
+    encode_opcode(&mut inst.0, target, Opcode::ATOM_CAS)?;
     encode_source_predicate(
         &mut inst.0,
         source_predicate_register,
@@ -425,20 +730,21 @@ pub fn encode_atom_cas(
     inst.set_register_a_offset(register_a_offset);
     inst.set_e_flag(e_flag);
 
-    inst.0
+    Ok(inst.0)
 }
 
 pub fn encode_b2r(
+    target: Target,
     source_predicate_register: u8,
     invert_source_predicate: bool,
     destination_register: u8,
     destination_predicate_register: u8,
     operation: B2ROperation,
     value: u8,
-) -> u64 {
+) -> Result<u64, EncodeError> {
     let mut inst = B2RInstruction(0);
 
-    encode_opcode(&mut inst.0, Opcode::B2R);
+    encode_opcode(&mut inst.0, target, Opcode::B2R)?;
     encode_source_predicate(
         &mut inst.0,
         source_predicate_register,
@@ -449,5 +755,1526 @@ pub fn encode_b2r(
     encode_destination_predicate(&mut inst.0, destination_predicate_register);
 
     inst.set_operation(operation);
-    inst.0
-}
\ No newline at end of file
+
+    Ok(inst.0)
+}
+
+pub fn encode_bar(
+    target: Target,
+    source_predicate_register: u8,
+    invert_source_predicate: bool,
+    mode: BarMode,
+    reduction: BarReductionOperation,
+    barrier: u8,
+    barrier_immediate: bool,
+    thread_count: u16,
+    thread_count_immediate: bool,
+    reduction_predicate_register: u8,
+    invert_reduction_predicate: bool,
+) -> Result<u64, EncodeError> {
+    debug_assert!(!barrier_immediate || barrier < 16);
+    debug_assert!(thread_count_immediate || thread_count <= 0xFF);
+    debug_assert!(thread_count < 0x1000);
+    debug_assert!(reduction_predicate_register < 8);
+
+    let mut inst = BarInstruction(0);
+
+    encode_opcode(&mut inst.0, target, Opcode::BAR)?;
+    encode_source_predicate(
+        &mut inst.0,
+        source_predicate_register,
+        invert_source_predicate,
+    );
+
+    inst.set_mode(mode);
+    inst.set_reduction(reduction);
+    inst.set_barrier(barrier);
+    inst.set_barrier_immediate_flag(barrier_immediate);
+    inst.set_thread_count(thread_count);
+    inst.set_thread_count_immediate_flag(thread_count_immediate);
+    inst.set_reduction_predicate_register(reduction_predicate_register);
+    inst.set_invert_reduction_predicate(invert_reduction_predicate);
+
+    Ok(inst.0)
+}
+
+pub fn encode_membar(
+    target: Target,
+    source_predicate_register: u8,
+    invert_source_predicate: bool,
+    level: MembarLevel,
+) -> Result<u64, EncodeError> {
+    let mut inst = MembarInstruction(0);
+
+    encode_opcode(&mut inst.0, target, Opcode::MEMBAR)?;
+    encode_source_predicate(
+        &mut inst.0,
+        source_predicate_register,
+        invert_source_predicate,
+    );
+
+    inst.set_level(level);
+
+    Ok(inst.0)
+}
+
+pub fn encode_depbar(
+    target: Target,
+    source_predicate_register: u8,
+    invert_source_predicate: bool,
+    scoreboard: u8,
+    count: u8,
+    le_flag: bool,
+    dependency_mask: u8,
+) -> Result<u64, EncodeError> {
+    debug_assert!(scoreboard < 6);
+    debug_assert!(count < 0x40);
+    debug_assert!(dependency_mask < 0x40);
+
+    let mut inst = DepbarInstruction(0);
+
+    encode_opcode(&mut inst.0, target, Opcode::DEPBAR)?;
+    encode_source_predicate(
+        &mut inst.0,
+        source_predicate_register,
+        invert_source_predicate,
+    );
+
+    inst.set_scoreboard(scoreboard);
+    inst.set_count(count);
+    inst.set_le_flag(le_flag);
+    inst.set_dependency_mask(dependency_mask);
+
+    Ok(inst.0)
+}
+
+pub fn encode_cctl(
+    target: Target,
+    source_predicate_register: u8,
+    invert_source_predicate: bool,
+    source_register: u8,
+    offset: i32,
+    operation: CacheOperation,
+    cache: CacheType,
+) -> Result<u64, EncodeError> {
+    debug_assert!((offset & 0x3) == 0);
+
+    let mut inst = CctlInstruction(0);
+
+    encode_opcode(&mut inst.0, target, Opcode::CCTL)?;
+    encode_source_predicate(
+        &mut inst.0,
+        source_predicate_register,
+        invert_source_predicate,
+    );
+    encode_operand1(&mut inst.0, source_register);
+
+    inst.set_offset_shr_2(offset >> 2);
+    inst.set_operation(operation);
+    inst.set_cache(cache);
+
+    Ok(inst.0)
+}
+
+pub fn encode_cctll(
+    target: Target,
+    source_predicate_register: u8,
+    invert_source_predicate: bool,
+    source_register: u8,
+    offset: i32,
+    operation: CacheOperation,
+) -> Result<u64, EncodeError> {
+    debug_assert!((offset & 0x3) == 0);
+
+    let mut inst = CctllInstruction(0);
+
+    encode_opcode(&mut inst.0, target, Opcode::CCTLL)?;
+    encode_source_predicate(
+        &mut inst.0,
+        source_predicate_register,
+        invert_source_predicate,
+    );
+    encode_operand1(&mut inst.0, source_register);
+
+    inst.set_offset_shr_2(offset >> 2);
+    inst.set_operation(operation);
+
+    Ok(inst.0)
+}
+
+fn encode_texture_component_swizzle(opcode: Opcode, component_mask: u8) -> Result<u8, EncodeError> {
+    // NOTE: More than two components requires destination_register_b to be used.
+    let masks: &[u8] = if component_mask.count_ones() > 2 {
+        &TEXTURE_DUAL_DESTINATION_MASKS
+    } else {
+        &TEXTURE_SINGLE_DESTINATION_MASKS
+    };
+
+    masks
+        .iter()
+        .position(|mask| *mask == component_mask)
+        .map(|swizzle| swizzle as u8)
+        .ok_or(EncodeError::UnsupportedComponentMask(
+            opcode,
+            component_mask,
+        ))
+}
+
+pub fn encode_tex(
+    target: Target,
+    source_predicate_register: u8,
+    invert_source_predicate: bool,
+    destination_register: u8,
+    source_register_a: u8,
+    source_register_b: u8,
+    texture_index: u16,
+    bindless: bool,
+    texture_type: TextureType,
+    lod_mode: TextureLodMode,
+    component_mask: u8,
+    aoffi_flag: bool,
+    dc_flag: bool,
+    nodep_flag: bool,
+    ndv_flag: bool,
+    lc_flag: bool,
+) -> Result<u64, EncodeError> {
+    debug_assert!(component_mask < 0x10);
+    debug_assert!(!bindless || texture_index == 0);
+
+    if bindless {
+        let mut inst = TexBindlessInstruction(0);
+
+        encode_opcode(&mut inst.0, target, Opcode::TEX_B)?;
+        encode_source_predicate(
+            &mut inst.0,
+            source_predicate_register,
+            invert_source_predicate,
+        );
+        encode_operand0(&mut inst.0, destination_register);
+        encode_operand1(&mut inst.0, source_register_a);
+        encode_operand2(&mut inst.0, source_register_b);
+
+        inst.set_texture_type(texture_type);
+        inst.set_lod_mode(lod_mode);
+        inst.set_component_mask(component_mask);
+        inst.set_aoffi_flag(aoffi_flag);
+        inst.set_dc_flag(dc_flag);
+        inst.set_nodep_flag(nodep_flag);
+        inst.set_ndv_flag(ndv_flag);
+        inst.set_lc_flag(lc_flag);
+
+        // TODO: support sparse texture residency.
+        inst.set_sparse_predicate_register(7);
+
+        Ok(inst.0)
+    } else {
+        let mut inst = TexInstruction(0);
+
+        encode_opcode(&mut inst.0, target, Opcode::TEX)?;
+        encode_source_predicate(
+            &mut inst.0,
+            source_predicate_register,
+            invert_source_predicate,
+        );
+        encode_operand0(&mut inst.0, destination_register);
+        encode_operand1(&mut inst.0, source_register_a);
+        encode_operand2(&mut inst.0, source_register_b);
+
+        inst.set_texture_index(texture_index);
+        inst.set_texture_type(texture_type);
+        inst.set_lod_mode(lod_mode);
+        inst.set_component_mask(component_mask);
+        inst.set_aoffi_flag(aoffi_flag);
+        inst.set_dc_flag(dc_flag);
+        inst.set_nodep_flag(nodep_flag);
+        inst.set_ndv_flag(ndv_flag);
+        inst.set_lc_flag(lc_flag);
+
+        // TODO: support sparse texture residency.
+        inst.set_sparse_predicate_register(7);
+
+        Ok(inst.0)
+    }
+}
+
+pub fn encode_texs(
+    target: Target,
+    source_predicate_register: u8,
+    invert_source_predicate: bool,
+    destination_register_a: u8,
+    destination_register_b: u8,
+    source_register_a: u8,
+    source_register_b: u8,
+    texture_index: u16,
+    texture_type: TextureType,
+    lod_mode: TextureLodMode,
+    dc_flag: bool,
+    component_mask: u8,
+    nodep_flag: bool,
+    fp16_flag: bool,
+) -> Result<u64, EncodeError> {
+    let encoding = TEXS_ENCODINGS
+        .iter()
+        .position(|(entry_type, entry_lod, entry_dc)| {
+            *entry_type == texture_type && *entry_lod == lod_mode && *entry_dc == dc_flag
+        })
+        .ok_or(EncodeError::UnsupportedTextureMode(
+            Opcode::TEXS,
+            texture_type,
+            lod_mode,
+        ))?;
+
+    let mut inst = TexsInstruction(0);
+
+    encode_opcode(&mut inst.0, target, Opcode::TEXS)?;
+    encode_source_predicate(
+        &mut inst.0,
+        source_predicate_register,
+        invert_source_predicate,
+    );
+    encode_operand0(&mut inst.0, destination_register_b);
+    encode_operand1(&mut inst.0, source_register_a);
+    encode_operand2(&mut inst.0, source_register_b);
+
+    inst.set_destination_register_a(destination_register_a);
+    inst.set_texture_index(texture_index);
+    inst.set_nodep_flag(nodep_flag);
+    inst.set_component_swizzle(encode_texture_component_swizzle(
+        Opcode::TEXS,
+        component_mask,
+    )?);
+    inst.set_encoding(encoding as u8);
+    inst.set_fp16_flag(fp16_flag);
+
+    Ok(inst.0)
+}
+
+pub fn encode_tld(
+    target: Target,
+    source_predicate_register: u8,
+    invert_source_predicate: bool,
+    destination_register: u8,
+    source_register_a: u8,
+    source_register_b: u8,
+    texture_index: u16,
+    bindless: bool,
+    texture_type: TextureType,
+    lod_mode: TextureLodMode,
+    component_mask: u8,
+    aoffi_flag: bool,
+    ms_flag: bool,
+    nodep_flag: bool,
+) -> Result<u64, EncodeError> {
+    debug_assert!(component_mask < 0x10);
+    debug_assert!(!bindless || texture_index == 0);
+
+    let opcode = if bindless { Opcode::TLD_B } else { Opcode::TLD };
+
+    // NOTE: TLD only has a flag selecting LL over LZ.
+    if lod_mode != TextureLodMode::LZ && lod_mode != TextureLodMode::LL {
+        return Err(EncodeError::UnsupportedTextureMode(
+            opcode,
+            texture_type,
+            lod_mode,
+        ));
+    }
+
+    let mut inst = TldInstruction(0);
+
+    encode_opcode(&mut inst.0, target, opcode)?;
+
+    encode_source_predicate(
+        &mut inst.0,
+        source_predicate_register,
+        invert_source_predicate,
+    );
+    encode_operand0(&mut inst.0, destination_register);
+    encode_operand1(&mut inst.0, source_register_a);
+    encode_operand2(&mut inst.0, source_register_b);
+
+    inst.set_texture_index(texture_index);
+    inst.set_texture_type(texture_type);
+    inst.set_component_mask(component_mask);
+    inst.set_aoffi_flag(aoffi_flag);
+    inst.set_ms_flag(ms_flag);
+    inst.set_nodep_flag(nodep_flag);
+    inst.set_ll_flag(lod_mode == TextureLodMode::LL);
+
+    Ok(inst.0)
+}
+
+pub fn encode_tlds(
+    target: Target,
+    source_predicate_register: u8,
+    invert_source_predicate: bool,
+    destination_register_a: u8,
+    destination_register_b: u8,
+    source_register_a: u8,
+    source_register_b: u8,
+    texture_index: u16,
+    texture_type: TextureType,
+    lod_mode: TextureLodMode,
+    aoffi_flag: bool,
+    ms_flag: bool,
+    component_mask: u8,
+    nodep_flag: bool,
+    fp16_flag: bool,
+) -> Result<u64, EncodeError> {
+    let encoding = TLDS_ENCODINGS
+        .iter()
+        .find(|(_, entry_type, entry_lod, entry_aoffi, entry_ms)| {
+            *entry_type == texture_type
+                && *entry_lod == lod_mode
+                && *entry_aoffi == aoffi_flag
+                && *entry_ms == ms_flag
+        })
+        .ok_or(EncodeError::UnsupportedTextureMode(
+            Opcode::TLDS,
+            texture_type,
+            lod_mode,
+        ))?;
+
+    let mut inst = TexsInstruction(0);
+
+    encode_opcode(&mut inst.0, target, Opcode::TLDS)?;
+    encode_source_predicate(
+        &mut inst.0,
+        source_predicate_register,
+        invert_source_predicate,
+    );
+    encode_operand0(&mut inst.0, destination_register_b);
+    encode_operand1(&mut inst.0, source_register_a);
+    encode_operand2(&mut inst.0, source_register_b);
+
+    inst.set_destination_register_a(destination_register_a);
+    inst.set_texture_index(texture_index);
+    inst.set_nodep_flag(nodep_flag);
+    inst.set_component_swizzle(encode_texture_component_swizzle(
+        Opcode::TLDS,
+        component_mask,
+    )?);
+    inst.set_encoding(encoding.0);
+    inst.set_fp16_flag(fp16_flag);
+
+    Ok(inst.0)
+}
+
+pub fn encode_tld4(
+    target: Target,
+    source_predicate_register: u8,
+    invert_source_predicate: bool,
+    destination_register: u8,
+    source_register_a: u8,
+    source_register_b: u8,
+    texture_index: u16,
+    bindless: bool,
+    texture_type: TextureType,
+    component: TextureComponent,
+    component_mask: u8,
+    offset_mode: TextureGatherOffset,
+    dc_flag: bool,
+    nodep_flag: bool,
+    ndv_flag: bool,
+) -> Result<u64, EncodeError> {
+    debug_assert!(component_mask < 0x10);
+    debug_assert!(!bindless || texture_index == 0);
+
+    if bindless {
+        let mut inst = Tld4BindlessInstruction(0);
+
+        encode_opcode(&mut inst.0, target, Opcode::TLD4_B)?;
+        encode_source_predicate(
+            &mut inst.0,
+            source_predicate_register,
+            invert_source_predicate,
+        );
+        encode_operand0(&mut inst.0, destination_register);
+        encode_operand1(&mut inst.0, source_register_a);
+        encode_operand2(&mut inst.0, source_register_b);
+
+        inst.set_texture_type(texture_type);
+        inst.set_component(component);
+        inst.set_component_mask(component_mask);
+        inst.set_offset_mode(offset_mode);
+        inst.set_dc_flag(dc_flag);
+        inst.set_nodep_flag(nodep_flag);
+        inst.set_ndv_flag(ndv_flag);
+
+        Ok(inst.0)
+    } else {
+        let mut inst = Tld4Instruction(0);
+
+        encode_opcode(&mut inst.0, target, Opcode::TLD4)?;
+        encode_source_predicate(
+            &mut inst.0,
+            source_predicate_register,
+            invert_source_predicate,
+        );
+        encode_operand0(&mut inst.0, destination_register);
+        encode_operand1(&mut inst.0, source_register_a);
+        encode_operand2(&mut inst.0, source_register_b);
+
+        inst.set_texture_index(texture_index);
+        inst.set_texture_type(texture_type);
+        inst.set_component(component);
+        inst.set_component_mask(component_mask);
+        inst.set_offset_mode(offset_mode);
+        inst.set_dc_flag(dc_flag);
+        inst.set_nodep_flag(nodep_flag);
+        inst.set_ndv_flag(ndv_flag);
+
+        Ok(inst.0)
+    }
+}
+
+pub fn encode_tld4s(
+    target: Target,
+    source_predicate_register: u8,
+    invert_source_predicate: bool,
+    destination_register_a: u8,
+    destination_register_b: u8,
+    source_register_a: u8,
+    source_register_b: u8,
+    texture_index: u16,
+    component: TextureComponent,
+    aoffi_flag: bool,
+    dc_flag: bool,
+    nodep_flag: bool,
+    fp16_flag: bool,
+) -> Result<u64, EncodeError> {
+    let mut inst = Tld4sInstruction(0);
+
+    encode_opcode(&mut inst.0, target, Opcode::TLD4S)?;
+    encode_source_predicate(
+        &mut inst.0,
+        source_predicate_register,
+        invert_source_predicate,
+    );
+    encode_operand0(&mut inst.0, destination_register_b);
+    encode_operand1(&mut inst.0, source_register_a);
+    encode_operand2(&mut inst.0, source_register_b);
+
+    inst.set_destination_register_a(destination_register_a);
+    inst.set_texture_index(texture_index);
+    inst.set_component(component);
+    inst.set_aoffi_flag(aoffi_flag);
+    inst.set_dc_flag(dc_flag);
+    inst.set_nodep_flag(nodep_flag);
+    inst.set_fp16_flag(fp16_flag);
+
+    Ok(inst.0)
+}
+
+pub fn encode_txq(
+    target: Target,
+    source_predicate_register: u8,
+    invert_source_predicate: bool,
+    destination_register: u8,
+    source_register: u8,
+    texture_index: u16,
+    bindless: bool,
+    query: TextureQuery,
+    component_mask: u8,
+    nodep_flag: bool,
+) -> Result<u64, EncodeError> {
+    debug_assert!(component_mask < 0x10);
+    debug_assert!(!bindless || texture_index == 0);
+
+    let mut inst = TxqInstruction(0);
+
+    if bindless {
+        encode_opcode(&mut inst.0, target, Opcode::TXQ_B)?;
+    } else {
+        encode_opcode(&mut inst.0, target, Opcode::TXQ)?;
+    }
+
+    encode_source_predicate(
+        &mut inst.0,
+        source_predicate_register,
+        invert_source_predicate,
+    );
+    encode_operand0(&mut inst.0, destination_register);
+    encode_operand1(&mut inst.0, source_register);
+
+    inst.set_texture_index(texture_index);
+    inst.set_query(query);
+    inst.set_component_mask(component_mask);
+    inst.set_nodep_flag(nodep_flag);
+
+    Ok(inst.0)
+}
+
+pub fn encode_tmml(
+    target: Target,
+    source_predicate_register: u8,
+    invert_source_predicate: bool,
+    destination_register: u8,
+    source_register_a: u8,
+    source_register_b: u8,
+    texture_index: u16,
+    bindless: bool,
+    texture_type: TextureType,
+    component_mask: u8,
+    ndv_flag: bool,
+    nodep_flag: bool,
+) -> Result<u64, EncodeError> {
+    debug_assert!(component_mask < 0x10);
+    debug_assert!(!bindless || texture_index == 0);
+
+    let mut inst = TmmlInstruction(0);
+
+    if bindless {
+        encode_opcode(&mut inst.0, target, Opcode::TMML_B)?;
+    } else {
+        encode_opcode(&mut inst.0, target, Opcode::TMML)?;
+    }
+
+    encode_source_predicate(
+        &mut inst.0,
+        source_predicate_register,
+        invert_source_predicate,
+    );
+    encode_operand0(&mut inst.0, destination_register);
+    encode_operand1(&mut inst.0, source_register_a);
+    encode_operand2(&mut inst.0, source_register_b);
+
+    inst.set_texture_index(texture_index);
+    inst.set_texture_type(texture_type);
+    inst.set_component_mask(component_mask);
+    inst.set_ndv_flag(ndv_flag);
+    inst.set_nodep_flag(nodep_flag);
+
+    Ok(inst.0)
+}
+
+pub fn encode_txd(
+    target: Target,
+    source_predicate_register: u8,
+    invert_source_predicate: bool,
+    destination_register: u8,
+    source_register_a: u8,
+    source_register_b: u8,
+    texture_index: u16,
+    bindless: bool,
+    texture_type: TextureType,
+    component_mask: u8,
+    aoffi_flag: bool,
+    nodep_flag: bool,
+) -> Result<u64, EncodeError> {
+    debug_assert!(component_mask < 0x10);
+    debug_assert!(!bindless || texture_index == 0);
+
+    let mut inst = TxdInstruction(0);
+
+    if bindless {
+        encode_opcode(&mut inst.0, target, Opcode::TXD_B)?;
+    } else {
+        encode_opcode(&mut inst.0, target, Opcode::TXD)?;
+    }
+
+    encode_source_predicate(
+        &mut inst.0,
+        source_predicate_register,
+        invert_source_predicate,
+    );
+    encode_operand0(&mut inst.0, destination_register);
+    encode_operand1(&mut inst.0, source_register_a);
+    encode_operand2(&mut inst.0, source_register_b);
+
+    inst.set_texture_index(texture_index);
+    inst.set_texture_type(texture_type);
+    inst.set_component_mask(component_mask);
+    inst.set_aoffi_flag(aoffi_flag);
+    inst.set_nodep_flag(nodep_flag);
+
+    Ok(inst.0)
+}
+
+fn encode_surface_load_store(
+    target: Target,
+    opcode: Opcode,
+    source_predicate_register: u8,
+    invert_source_predicate: bool,
+    register: u8,
+    source_register: u8,
+    surface_index: u16,
+    bindless: bool,
+    surface_type: SurfaceType,
+    d_flag: bool,
+    size: SurfaceSize,
+    component_mask: u8,
+    clamp_mode: SurfaceClampMode,
+) -> Result<u64, EncodeError> {
+    debug_assert!(component_mask < 0x10);
+    debug_assert!(!bindless || surface_index <= 0xFF);
+
+    let format = if d_flag { size.into() } else { component_mask };
+
+    if bindless {
+        let mut inst = SuldBindlessInstruction(0);
+
+        encode_opcode(&mut inst.0, target, opcode)?;
+        encode_source_predicate(
+            &mut inst.0,
+            source_predicate_register,
+            invert_source_predicate,
+        );
+        encode_operand0(&mut inst.0, register);
+        encode_operand1(&mut inst.0, source_register);
+        encode_operand3(&mut inst.0, surface_index as u8);
+
+        inst.set_format(format);
+        inst.set_surface_type(surface_type);
+        inst.set_clamp_mode(clamp_mode);
+        inst.set_d_flag(d_flag);
+
+        Ok(inst.0)
+    } else {
+        let mut inst = SuldInstruction(0);
+
+        encode_opcode(&mut inst.0, target, opcode)?;
+        encode_source_predicate(
+            &mut inst.0,
+            source_predicate_register,
+            invert_source_predicate,
+        );
+        encode_operand0(&mut inst.0, register);
+        encode_operand1(&mut inst.0, source_register);
+
+        inst.set_format(format);
+        inst.set_surface_type(surface_type);
+        inst.set_surface_index(surface_index);
+        inst.set_clamp_mode(clamp_mode);
+        inst.set_d_flag(d_flag);
+
+        Ok(inst.0)
+    }
+}
+
+/// Encode a surface load.
+///
+/// When bindless is set, surface_index is the register holding the surface handle.
+pub fn encode_suld(
+    target: Target,
+    source_predicate_register: u8,
+    invert_source_predicate: bool,
+    destination_register: u8,
+    source_register: u8,
+    surface_index: u16,
+    bindless: bool,
+    surface_type: SurfaceType,
+    d_flag: bool,
+    size: SurfaceSize,
+    component_mask: u8,
+    clamp_mode: SurfaceClampMode,
+) -> Result<u64, EncodeError> {
+    let opcode = if bindless {
+        Opcode::SULD_B
+    } else {
+        Opcode::SULD
+    };
+
+    encode_surface_load_store(
+        target,
+        opcode,
+        source_predicate_register,
+        invert_source_predicate,
+        destination_register,
+        source_register,
+        surface_index,
+        bindless,
+        surface_type,
+        d_flag,
+        size,
+        component_mask,
+        clamp_mode,
+    )
+}
+
+/// Encode a surface store.
+///
+/// When bindless is set, surface_index is the register holding the surface handle.
+pub fn encode_sust(
+    target: Target,
+    source_predicate_register: u8,
+    invert_source_predicate: bool,
+    source_register_a: u8,
+    source_register_b: u8,
+    surface_index: u16,
+    bindless: bool,
+    surface_type: SurfaceType,
+    d_flag: bool,
+    size: SurfaceSize,
+    component_mask: u8,
+    clamp_mode: SurfaceClampMode,
+) -> Result<u64, EncodeError> {
+    let opcode = if bindless {
+        Opcode::SUST_B
+    } else {
+        Opcode::SUST
+    };
+
+    encode_surface_load_store(
+        target,
+        opcode,
+        source_predicate_register,
+        invert_source_predicate,
+        source_register_b,
+        source_register_a,
+        surface_index,
+        bindless,
+        surface_type,
+        d_flag,
+        size,
+        component_mask,
+        clamp_mode,
+    )
+}
+
+pub fn encode_suatom(
+    target: Target,
+    source_predicate_register: u8,
+    invert_source_predicate: bool,
+    destination_register: u8,
+    source_register_a: u8,
+    source_register_b: u8,
+    surface_register: u8,
+    surface_type: SurfaceType,
+    type_size: AtomPrimitiveType,
+    operation: AtomOperation,
+    clamp_mode: SurfaceClampMode,
+) -> Result<u64, EncodeError> {
+    let mut inst = SuatomInstruction(0);
+
+    encode_opcode(&mut inst.0, target, Opcode::SUATOM)?;
+    encode_atomic_operation(target, Opcode::SUATOM, operation, type_size)?;
+    encode_source_predicate(
+        &mut inst.0,
+        source_predicate_register,
+        invert_source_predicate,
+    );
+    encode_operand0(&mut inst.0, destination_register);
+    encode_operand1(&mut inst.0, source_register_a);
+    encode_operand2(&mut inst.0, source_register_b);
+    encode_operand3(&mut inst.0, surface_register);
+
+    inst.set_operation(operation);
+    inst.set_surface_type(surface_type);
+    inst.set_type_size(type_size);
+    inst.set_clamp_mode(clamp_mode);
+
+    Ok(inst.0)
+}
+
+pub fn encode_sured(
+    target: Target,
+    source_predicate_register: u8,
+    invert_source_predicate: bool,
+    source_register_a: u8,
+    source_register_b: u8,
+    surface_register: u8,
+    surface_type: SurfaceType,
+    type_size: AtomPrimitiveType,
+    operation: AtomOperation,
+    clamp_mode: SurfaceClampMode,
+) -> Result<u64, EncodeError> {
+    let mut inst = SuredInstruction(0);
+
+    encode_opcode(&mut inst.0, target, Opcode::SURED)?;
+    encode_atomic_operation(target, Opcode::SURED, operation, type_size)?;
+    encode_source_predicate(
+        &mut inst.0,
+        source_predicate_register,
+        invert_source_predicate,
+    );
+    encode_operand0(&mut inst.0, source_register_b);
+    encode_operand1(&mut inst.0, source_register_a);
+    encode_operand3(&mut inst.0, surface_register);
+
+    inst.set_type_size(type_size);
+    inst.set_operation(operation);
+    inst.set_surface_type(surface_type);
+    inst.set_clamp_mode(clamp_mode);
+
+    Ok(inst.0)
+}
+
+pub fn encode_ipa(
+    target: Target,
+    source_predicate_register: u8,
+    invert_source_predicate: bool,
+    destination_register: u8,
+    index_register: u8,
+    multiplier_register: u8,
+    sample_register: u8,
+    attribute_offset: u16,
+    idx_flag: bool,
+    interpolation_mode: IpaInterpolationMode,
+    sample_mode: IpaSampleMode,
+    sat_flag: bool,
+) -> Result<u64, EncodeError> {
+    debug_assert!((attribute_offset & 0x3) == 0);
+    debug_assert!(attribute_offset < 0x400);
+
+    let mut inst = IpaInstruction(0);
+
+    encode_opcode(&mut inst.0, target, Opcode::IPA)?;
+    encode_source_predicate(
+        &mut inst.0,
+        source_predicate_register,
+        invert_source_predicate,
+    );
+    encode_operand0(&mut inst.0, destination_register);
+    encode_operand1(&mut inst.0, index_register);
+    encode_operand2(&mut inst.0, multiplier_register);
+    encode_operand3(&mut inst.0, sample_register);
+
+    inst.set_attribute_shr_2((attribute_offset >> 2) as u8);
+    inst.set_idx_flag(idx_flag);
+    inst.set_interpolation_mode(interpolation_mode);
+    inst.set_sample_mode(sample_mode);
+    inst.set_sat_flag(sat_flag);
+
+    Ok(inst.0)
+}
+
+pub fn encode_pixld(
+    target: Target,
+    source_predicate_register: u8,
+    invert_source_predicate: bool,
+    destination_register: u8,
+    destination_predicate_register: u8,
+    source_register: u8,
+    offset: i8,
+    mode: PixelLoadMode,
+) -> Result<u64, EncodeError> {
+    debug_assert!(destination_predicate_register < 8);
+
+    let mut inst = PixldInstruction(0);
+
+    encode_opcode(&mut inst.0, target, Opcode::PIXLD)?;
+    encode_source_predicate(
+        &mut inst.0,
+        source_predicate_register,
+        invert_source_predicate,
+    );
+    encode_operand0(&mut inst.0, destination_register);
+    encode_operand1(&mut inst.0, source_register);
+    encode_destination_predicate(&mut inst.0, destination_predicate_register);
+
+    inst.set_offset(offset);
+    inst.set_mode(mode);
+
+    Ok(inst.0)
+}
+
+/// Encode a geometry shader output.
+///
+/// When stream_immediate is set, stream is the stream index instead of the register holding it.
+pub fn encode_out(
+    target: Target,
+    source_predicate_register: u8,
+    invert_source_predicate: bool,
+    destination_register: u8,
+    source_register: u8,
+    stream: u8,
+    stream_immediate: bool,
+    mode: OutMode,
+) -> Result<u64, EncodeError> {
+    debug_assert!(!stream_immediate || stream < 4);
+
+    if stream_immediate {
+        let mut inst = OutImmInstruction(0);
+
+        encode_opcode(&mut inst.0, target, Opcode::OUT_IMM)?;
+        encode_source_predicate(
+            &mut inst.0,
+            source_predicate_register,
+            invert_source_predicate,
+        );
+        encode_operand0(&mut inst.0, destination_register);
+        encode_operand1(&mut inst.0, source_register);
+
+        inst.set_stream(stream);
+        inst.set_mode(mode);
+
+        Ok(inst.0)
+    } else {
+        let mut inst = OutInstruction(0);
+
+        encode_opcode(&mut inst.0, target, Opcode::OUT)?;
+        encode_source_predicate(
+            &mut inst.0,
+            source_predicate_register,
+            invert_source_predicate,
+        );
+        encode_operand0(&mut inst.0, destination_register);
+        encode_operand1(&mut inst.0, source_register);
+        encode_operand2(&mut inst.0, stream);
+
+        inst.set_mode(mode);
+
+        Ok(inst.0)
+    }
+}
+
+pub fn encode_isberd(
+    target: Target,
+    source_predicate_register: u8,
+    invert_source_predicate: bool,
+    destination_register: u8,
+    source_register: u8,
+    mode: IsbeMode,
+    shift: IsbeShift,
+    skew_flag: bool,
+    o_flag: bool,
+) -> Result<u64, EncodeError> {
+    let mut inst = IsberdInstruction(0);
+
+    encode_opcode(&mut inst.0, target, Opcode::ISBERD)?;
+    encode_source_predicate(
+        &mut inst.0,
+        source_predicate_register,
+        invert_source_predicate,
+    );
+    encode_operand0(&mut inst.0, destination_register);
+    encode_operand1(&mut inst.0, source_register);
+
+    inst.set_mode(mode);
+    inst.set_shift(shift);
+    inst.set_skew_flag(skew_flag);
+    inst.set_o_flag(o_flag);
+
+    Ok(inst.0)
+}
+
+pub fn encode_isbewr(
+    target: Target,
+    source_predicate_register: u8,
+    invert_source_predicate: bool,
+    source_register_a: u8,
+    source_register_b: u8,
+    mode: IsbeMode,
+    shift: IsbeShift,
+) -> Result<u64, EncodeError> {
+    let mut inst = IsbewrInstruction(0);
+
+    encode_opcode(&mut inst.0, target, Opcode::ISBEWR)?;
+    encode_source_predicate(
+        &mut inst.0,
+        source_predicate_register,
+        invert_source_predicate,
+    );
+    encode_operand1(&mut inst.0, source_register_a);
+    encode_operand2(&mut inst.0, source_register_b);
+
+    inst.set_mode(mode);
+    inst.set_shift(shift);
+
+    Ok(inst.0)
+}
+
+fn encode_video(
+    target: Target,
+    opcode: Opcode,
+    source_predicate_register: u8,
+    invert_source_predicate: bool,
+    destination_register: u8,
+    source_register_a: u8,
+    a_selector: VideoSelector,
+    a_signed: bool,
+    source_b: u16,
+    b_selector: Option<VideoSelector>,
+    b_signed: bool,
+    b_immediate: bool,
+    source_register_c: u8,
+    secondary_operation: VideoSecondaryOperation,
+    sat_flag: bool,
+) -> Result<u64, EncodeError> {
+    if b_immediate {
+        check_video_immediate_b(opcode, b_selector)?;
+
+        let mut inst = VideoImmInstruction(0);
+
+        encode_opcode(&mut inst.0, target, opcode)?;
+        encode_source_predicate(
+            &mut inst.0,
+            source_predicate_register,
+            invert_source_predicate,
+        );
+        encode_operand0(&mut inst.0, destination_register);
+        encode_operand1(&mut inst.0, source_register_a);
+        encode_operand3(&mut inst.0, source_register_c);
+
+        inst.set_imm16(source_b);
+        inst.set_a_selector(a_selector);
+        inst.set_a_signed_flag(a_signed);
+        inst.set_b_signed_flag(b_signed);
+        inst.set_secondary_operation(secondary_operation);
+        inst.set_sat_flag(sat_flag);
+
+        Ok(inst.0)
+    } else {
+        let (source_register_b, b_selector) =
+            encode_video_register_b(opcode, source_b, b_selector)?;
+
+        let mut inst = VideoInstruction(0);
+
+        encode_opcode(&mut inst.0, target, opcode)?;
+        encode_source_predicate(
+            &mut inst.0,
+            source_predicate_register,
+            invert_source_predicate,
+        );
+        encode_operand0(&mut inst.0, destination_register);
+        encode_operand1(&mut inst.0, source_register_a);
+        encode_operand2(&mut inst.0, source_register_b);
+        encode_operand3(&mut inst.0, source_register_c);
+
+        inst.set_a_selector(a_selector);
+        inst.set_b_selector(b_selector);
+        inst.set_a_signed_flag(a_signed);
+        inst.set_b_signed_flag(b_signed);
+        inst.set_secondary_operation(secondary_operation);
+        inst.set_sat_flag(sat_flag);
+
+        Ok(inst.0)
+    }
+}
+
+pub fn encode_vadd(
+    target: Target,
+    source_predicate_register: u8,
+    invert_source_predicate: bool,
+    destination_register: u8,
+    source_register_a: u8,
+    a_selector: VideoSelector,
+    a_signed: bool,
+    source_b: u16,
+    b_selector: Option<VideoSelector>,
+    b_signed: bool,
+    b_immediate: bool,
+    source_register_c: u8,
+    secondary_operation: VideoSecondaryOperation,
+    sat_flag: bool,
+) -> Result<u64, EncodeError> {
+    encode_video(
+        target,
+        if b_immediate {
+            Opcode::VADD_IMM
+        } else {
+            Opcode::VADD
+        },
+        source_predicate_register,
+        invert_source_predicate,
+        destination_register,
+        source_register_a,
+        a_selector,
+        a_signed,
+        source_b,
+        b_selector,
+        b_signed,
+        b_immediate,
+        source_register_c,
+        secondary_operation,
+        sat_flag,
+    )
+}
+
+pub fn encode_vabsdiff(
+    target: Target,
+    source_predicate_register: u8,
+    invert_source_predicate: bool,
+    destination_register: u8,
+    source_register_a: u8,
+    a_selector: VideoSelector,
+    a_signed: bool,
+    source_b: u16,
+    b_selector: Option<VideoSelector>,
+    b_signed: bool,
+    b_immediate: bool,
+    source_register_c: u8,
+    secondary_operation: VideoSecondaryOperation,
+    sat_flag: bool,
+) -> Result<u64, EncodeError> {
+    encode_video(
+        target,
+        if b_immediate {
+            Opcode::VABSDIFF_IMM
+        } else {
+            Opcode::VABSDIFF
+        },
+        source_predicate_register,
+        invert_source_predicate,
+        destination_register,
+        source_register_a,
+        a_selector,
+        a_signed,
+        source_b,
+        b_selector,
+        b_signed,
+        b_immediate,
+        source_register_c,
+        secondary_operation,
+        sat_flag,
+    )
+}
+
+pub fn encode_vshl(
+    target: Target,
+    source_predicate_register: u8,
+    invert_source_predicate: bool,
+    destination_register: u8,
+    source_register_a: u8,
+    a_selector: VideoSelector,
+    a_signed: bool,
+    source_b: u16,
+    b_selector: Option<VideoSelector>,
+    b_signed: bool,
+    b_immediate: bool,
+    source_register_c: u8,
+    secondary_operation: VideoSecondaryOperation,
+    sat_flag: bool,
+) -> Result<u64, EncodeError> {
+    encode_video(
+        target,
+        if b_immediate {
+            Opcode::VSHL_IMM
+        } else {
+            Opcode::VSHL
+        },
+        source_predicate_register,
+        invert_source_predicate,
+        destination_register,
+        source_register_a,
+        a_selector,
+        a_signed,
+        source_b,
+        b_selector,
+        b_signed,
+        b_immediate,
+        source_register_c,
+        secondary_operation,
+        sat_flag,
+    )
+}
+
+pub fn encode_vshr(
+    target: Target,
+    source_predicate_register: u8,
+    invert_source_predicate: bool,
+    destination_register: u8,
+    source_register_a: u8,
+    a_selector: VideoSelector,
+    a_signed: bool,
+    source_b: u16,
+    b_selector: Option<VideoSelector>,
+    b_signed: bool,
+    b_immediate: bool,
+    source_register_c: u8,
+    secondary_operation: VideoSecondaryOperation,
+    sat_flag: bool,
+) -> Result<u64, EncodeError> {
+    encode_video(
+        target,
+        if b_immediate {
+            Opcode::VSHR_IMM
+        } else {
+            Opcode::VSHR
+        },
+        source_predicate_register,
+        invert_source_predicate,
+        destination_register,
+        source_register_a,
+        a_selector,
+        a_signed,
+        source_b,
+        b_selector,
+        b_signed,
+        b_immediate,
+        source_register_c,
+        secondary_operation,
+        sat_flag,
+    )
+}
+
+pub fn encode_vmnmx(
+    target: Target,
+    source_predicate_register: u8,
+    invert_source_predicate: bool,
+    destination_register: u8,
+    source_register_a: u8,
+    a_selector: VideoSelector,
+    a_signed: bool,
+    source_b: u16,
+    b_selector: Option<VideoSelector>,
+    b_signed: bool,
+    b_immediate: bool,
+    source_register_c: u8,
+    secondary_operation: VideoSecondaryOperation,
+    sat_flag: bool,
+    mx_flag: bool,
+) -> Result<u64, EncodeError> {
+    if b_immediate {
+        check_video_immediate_b(Opcode::VMNMX_IMM, b_selector)?;
+
+        let mut inst = VmnmxImmInstruction(0);
+
+        encode_opcode(&mut inst.0, target, Opcode::VMNMX_IMM)?;
+        encode_source_predicate(
+            &mut inst.0,
+            source_predicate_register,
+            invert_source_predicate,
+        );
+        encode_operand0(&mut inst.0, destination_register);
+        encode_operand1(&mut inst.0, source_register_a);
+        encode_operand3(&mut inst.0, source_register_c);
+
+        inst.set_imm16(source_b);
+        inst.set_a_selector(a_selector);
+        inst.set_a_signed_flag(a_signed);
+        inst.set_b_signed_flag(b_signed);
+        inst.set_secondary_operation(secondary_operation);
+        inst.set_sat_flag(sat_flag);
+        inst.set_mx_flag(mx_flag);
+
+        Ok(inst.0)
+    } else {
+        let (source_register_b, b_selector) =
+            encode_video_register_b(Opcode::VMNMX, source_b, b_selector)?;
+
+        let mut inst = VmnmxInstruction(0);
+
+        encode_opcode(&mut inst.0, target, Opcode::VMNMX)?;
+        encode_source_predicate(
+            &mut inst.0,
+            source_predicate_register,
+            invert_source_predicate,
+        );
+        encode_operand0(&mut inst.0, destination_register);
+        encode_operand1(&mut inst.0, source_register_a);
+        encode_operand2(&mut inst.0, source_register_b);
+        encode_operand3(&mut inst.0, source_register_c);
+
+        inst.set_a_selector(a_selector);
+        inst.set_b_selector(b_selector);
+        inst.set_a_signed_flag(a_signed);
+        inst.set_b_signed_flag(b_signed);
+        inst.set_secondary_operation(secondary_operation);
+        inst.set_sat_flag(sat_flag);
+        inst.set_mx_flag(mx_flag);
+
+        Ok(inst.0)
+    }
+}
+
+pub fn encode_vmad(
+    target: Target,
+    source_predicate_register: u8,
+    invert_source_predicate: bool,
+    destination_register: u8,
+    source_register_a: u8,
+    a_selector: VideoSelector,
+    a_signed: bool,
+    source_b: u16,
+    b_selector: Option<VideoSelector>,
+    b_signed: bool,
+    b_immediate: bool,
+    source_register_c: u8,
+    scale: VideoScale,
+    sat_flag: bool,
+) -> Result<u64, EncodeError> {
+    if b_immediate {
+        check_video_immediate_b(Opcode::VMAD_IMM, b_selector)?;
+
+        let mut inst = VmadImmInstruction(0);
+
+        encode_opcode(&mut inst.0, target, Opcode::VMAD_IMM)?;
+        encode_source_predicate(
+            &mut inst.0,
+            source_predicate_register,
+            invert_source_predicate,
+        );
+        encode_operand0(&mut inst.0, destination_register);
+        encode_operand1(&mut inst.0, source_register_a);
+        encode_operand3(&mut inst.0, source_register_c);
+
+        inst.set_imm16(source_b);
+        inst.set_a_selector(a_selector);
+        inst.set_a_signed_flag(a_signed);
+        inst.set_b_signed_flag(b_signed);
+        inst.set_scale(scale);
+        inst.set_sat_flag(sat_flag);
+
+        Ok(inst.0)
+    } else {
+        let (source_register_b, b_selector) =
+            encode_video_register_b(Opcode::VMAD, source_b, b_selector)?;
+
+        let mut inst = VmadInstruction(0);
+
+        encode_opcode(&mut inst.0, target, Opcode::VMAD)?;
+        encode_source_predicate(
+            &mut inst.0,
+            source_predicate_register,
+            invert_source_predicate,
+        );
+        encode_operand0(&mut inst.0, destination_register);
+        encode_operand1(&mut inst.0, source_register_a);
+        encode_operand2(&mut inst.0, source_register_b);
+        encode_operand3(&mut inst.0, source_register_c);
+
+        inst.set_a_selector(a_selector);
+        inst.set_b_selector(b_selector);
+        inst.set_a_signed_flag(a_signed);
+        inst.set_b_signed_flag(b_signed);
+        inst.set_scale(scale);
+        inst.set_sat_flag(sat_flag);
+
+        Ok(inst.0)
+    }
+}
+
+pub fn encode_vset(
+    target: Target,
+    source_predicate_register: u8,
+    invert_source_predicate: bool,
+    destination_register: u8,
+    source_register_a: u8,
+    a_selector: VideoSelector,
+    a_signed: bool,
+    source_b: u16,
+    b_selector: Option<VideoSelector>,
+    b_signed: bool,
+    b_immediate: bool,
+    source_register_c: u8,
+    compare_operation: VideoCompareOperation,
+    secondary_operation: VideoSecondaryOperation,
+) -> Result<u64, EncodeError> {
+    if b_immediate {
+        check_video_immediate_b(Opcode::VSET_IMM, b_selector)?;
+
+        let mut inst = VsetImmInstruction(0);
+
+        encode_opcode(&mut inst.0, target, Opcode::VSET_IMM)?;
+        encode_source_predicate(
+            &mut inst.0,
+            source_predicate_register,
+            invert_source_predicate,
+        );
+        encode_operand0(&mut inst.0, destination_register);
+        encode_operand1(&mut inst.0, source_register_a);
+        encode_operand3(&mut inst.0, source_register_c);
+
+        inst.set_imm16(source_b);
+        inst.set_a_selector(a_selector);
+        inst.set_a_signed_flag(a_signed);
+        inst.set_b_signed_flag(b_signed);
+        inst.set_compare_operation(compare_operation);
+        inst.set_secondary_operation(secondary_operation);
+
+        Ok(inst.0)
+    } else {
+        let (source_register_b, b_selector) =
+            encode_video_register_b(Opcode::VSET, source_b, b_selector)?;
+
+        let mut inst = VsetInstruction(0);
+
+        encode_opcode(&mut inst.0, target, Opcode::VSET)?;
+        encode_source_predicate(
+            &mut inst.0,
+            source_predicate_register,
+            invert_source_predicate,
+        );
+        encode_operand0(&mut inst.0, destination_register);
+        encode_operand1(&mut inst.0, source_register_a);
+        encode_operand2(&mut inst.0, source_register_b);
+        encode_operand3(&mut inst.0, source_register_c);
+
+        inst.set_a_selector(a_selector);
+        inst.set_b_selector(b_selector);
+        inst.set_a_signed_flag(a_signed);
+        inst.set_b_signed_flag(b_signed);
+        inst.set_compare_operation(compare_operation);
+        inst.set_secondary_operation(secondary_operation);
+
+        Ok(inst.0)
+    }
+}
+
+pub fn encode_vsetp(
+    target: Target,
+    source_predicate_register: u8,
+    invert_source_predicate: bool,
+    destination_predicate_register_a: u8,
+    destination_predicate_register_b: u8,
+    source_register_a: u8,
+    a_selector: VideoSelector,
+    a_signed: bool,
+    source_b: u16,
+    b_selector: Option<VideoSelector>,
+    b_signed: bool,
+    b_immediate: bool,
+    compare_operation: VideoCompareOperation,
+    predicate_operation: PredicateOperation,
+    combine_predicate_register: u8,
+    invert_combine_predicate: bool,
+) -> Result<u64, EncodeError> {
+    debug_assert!(destination_predicate_register_a < 8);
+    debug_assert!(destination_predicate_register_b < 8);
+    debug_assert!(combine_predicate_register < 8);
+
+    if b_immediate {
+        check_video_immediate_b(Opcode::VSETP_IMM, b_selector)?;
+
+        let mut inst = VsetpImmInstruction(0);
+
+        encode_opcode(&mut inst.0, target, Opcode::VSETP_IMM)?;
+        encode_source_predicate(
+            &mut inst.0,
+            source_predicate_register,
+            invert_source_predicate,
+        );
+        encode_operand1(&mut inst.0, source_register_a);
+
+        inst.set_imm16(source_b);
+        inst.set_a_selector(a_selector);
+        inst.set_a_signed_flag(a_signed);
+        inst.set_b_signed_flag(b_signed);
+        inst.set_destination_predicate_register_a(destination_predicate_register_a);
+        inst.set_destination_predicate_register_b(destination_predicate_register_b);
+        inst.set_compare_operation(compare_operation);
+        inst.set_predicate_operation(predicate_operation);
+        inst.set_combine_predicate_register(combine_predicate_register);
+        inst.set_invert_combine_predicate(invert_combine_predicate);
+
+        Ok(inst.0)
+    } else {
+        let (source_register_b, b_selector) =
+            encode_video_register_b(Opcode::VSETP, source_b, b_selector)?;
+
+        let mut inst = VsetpInstruction(0);
+
+        encode_opcode(&mut inst.0, target, Opcode::VSETP)?;
+        encode_source_predicate(
+            &mut inst.0,
+            source_predicate_register,
+            invert_source_predicate,
+        );
+        encode_operand1(&mut inst.0, source_register_a);
+        encode_operand2(&mut inst.0, source_register_b);
+
+        inst.set_a_selector(a_selector);
+        inst.set_b_selector(b_selector);
+        inst.set_a_signed_flag(a_signed);
+        inst.set_b_signed_flag(b_signed);
+        inst.set_destination_predicate_register_a(destination_predicate_register_a);
+        inst.set_destination_predicate_register_b(destination_predicate_register_b);
+        inst.set_compare_operation(compare_operation);
+        inst.set_predicate_operation(predicate_operation);
+        inst.set_combine_predicate_register(combine_predicate_register);
+        inst.set_invert_combine_predicate(invert_combine_predicate);
+
+        Ok(inst.0)
+    }
+}
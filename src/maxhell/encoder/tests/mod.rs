@@ -0,0 +1,1803 @@
+use super::*;
+use crate::maxhell::util::{FieldDefinition, FieldKind};
+
+const ITERATIONS: usize = 256;
+
+const TARGET: Target = Target::SM62;
+
+/// Xorshift generator, the seed is fixed so failures can be reproduced.
+struct Rng(u64);
+
+impl Rng {
+    fn new() -> Self {
+        Rng(0x2545_f491_4f6c_dd1d)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, bound: u64) -> u64 {
+        self.next() % bound
+    }
+
+    fn flag(&mut self) -> bool {
+        self.next() & 1 != 0
+    }
+
+    fn register(&mut self) -> u8 {
+        self.next() as u8
+    }
+
+    fn predicate(&mut self) -> u8 {
+        self.below(8) as u8
+    }
+
+    /// Pick the value of one of the variants of an enum_with_val.
+    fn variant(&mut self, variants: &[(&str, u64)]) -> u64 {
+        variants[self.below(variants.len() as u64) as usize].1
+    }
+
+    /// Generate a valid value for a field.
+    fn field(&mut self, field: &FieldDefinition) -> u64 {
+        let bits = field.mask() >> field.lsb;
+
+        match field.kind() {
+            FieldKind::Enum => {
+                let variants: Vec<(&str, u64)> = field
+                    .variants
+                    .iter()
+                    .copied()
+                    .filter(|(_, value)| value & !bits == 0)
+                    .collect();
+
+                self.variant(&variants)
+            }
+            _ => self.next() & bits,
+        }
+    }
+}
+
+/// Decode an instruction produced by an encoder, check the raw value of the given fields
+/// and check that encoding the decoded fields reproduces the instruction.
+fn check_encoded(instruction: u64, opcode: Opcode, expected: &[(&str, u64)]) {
+    let decoded = DecodedInstruction::decode(instruction)
+        .unwrap_or_else(|| panic!("{:?}: cannot decode 0x{:016x}", opcode, instruction));
+
+    assert_eq!(decoded.opcode(), opcode, "0x{:016x}", instruction);
+
+    let fields = opcode.fields();
+    let values = decoded.field_values();
+
+    for (name, value) in expected {
+        let index = fields
+            .iter()
+            .position(|field| field.name == *name)
+            .unwrap_or_else(|| panic!("{:?} has no field {}", opcode, name));
+        let field = &fields[index];
+
+        assert_eq!(
+            values[index],
+            value & (field.mask() >> field.lsb),
+            "{:?}: {} of 0x{:016x}",
+            opcode,
+            name,
+            instruction
+        );
+    }
+
+    assert_eq!(opcode.encode(&values), instruction, "{:?}", opcode);
+}
+
+#[test]
+fn every_opcode_round_trips() {
+    let mut rng = Rng::new();
+
+    for &opcode in Opcode::ALL {
+        for _ in 0..ITERATIONS {
+            let values: Vec<u64> = opcode
+                .fields()
+                .iter()
+                .map(|field| rng.field(field))
+                .collect();
+            let instruction = opcode.encode(&values);
+            let decoded = DecodedInstruction::decode(instruction)
+                .unwrap_or_else(|| panic!("{:?}: cannot decode 0x{:016x}", opcode, instruction));
+
+            assert_eq!(decoded.opcode(), opcode, "0x{:016x}", instruction);
+            assert_eq!(decoded.field_values(), values, "{:?}", opcode);
+            assert_eq!(opcode.encode(&decoded.field_values()), instruction);
+        }
+    }
+}
+
+#[test]
+fn exit_round_trips() {
+    let mut rng = Rng::new();
+
+    for _ in 0..ITERATIONS {
+        let predicate = rng.predicate();
+        let invert = rng.flag();
+        let control_code = rng.variant(ControlCode::VARIANTS);
+        let keep_refcount = rng.flag();
+
+        let instruction = encode_exit(
+            TARGET,
+            predicate,
+            invert,
+            ControlCode::from(control_code as u8),
+            keep_refcount,
+        )
+        .unwrap();
+
+        check_encoded(
+            instruction,
+            Opcode::EXIT,
+            &[
+                ("source_predicate_register", predicate as u64),
+                ("invert_source_predicate", invert as u64),
+                ("cc_flags", control_code),
+                ("keep_refcount", keep_refcount as u64),
+            ],
+        );
+    }
+}
+
+#[test]
+fn nop_round_trips() {
+    let mut rng = Rng::new();
+
+    for _ in 0..ITERATIONS {
+        let trigger = rng.flag();
+        let predicate = rng.predicate();
+        let invert = rng.flag();
+        let value = rng.next() as u16;
+        let control_code = rng.variant(ControlCode::VARIANTS);
+
+        let instruction = encode_nop(
+            TARGET,
+            trigger,
+            predicate,
+            invert,
+            value,
+            ControlCode::from(control_code as u8),
+        )
+        .unwrap();
+
+        check_encoded(
+            instruction,
+            Opcode::NOP,
+            &[
+                ("trigger", trigger as u64),
+                ("source_predicate_register", predicate as u64),
+                ("invert_source_predicate", invert as u64),
+                ("imm16", value as u64),
+                ("cc_flags", control_code),
+            ],
+        );
+    }
+}
+
+#[test]
+fn al2p_round_trips() {
+    let mut rng = Rng::new();
+
+    for _ in 0..ITERATIONS {
+        let predicate = rng.predicate();
+        let invert = rng.flag();
+        let destination_predicate = rng.predicate();
+        let destination = rng.register();
+        let source = rng.register();
+        let o_flag = rng.flag();
+        let mode = rng.variant(AtributeLoadMode::VARIANTS);
+        let load_offset = (rng.below(0x800) as i16) - 0x400;
+
+        let instruction = encode_al2p(
+            TARGET,
+            predicate,
+            invert,
+            destination_predicate,
+            destination,
+            source,
+            o_flag,
+            AtributeLoadMode::from(mode as u8),
+            load_offset,
+        )
+        .unwrap();
+
+        check_encoded(
+            instruction,
+            Opcode::AL2P,
+            &[
+                ("source_predicate_register", predicate as u64),
+                ("invert_source_predicate", invert as u64),
+                (
+                    "destination_predicate_register",
+                    destination_predicate as u64,
+                ),
+                ("destination_register", destination as u64),
+                ("source_register", source as u64),
+                ("o_flag", o_flag as u64),
+                ("mode", mode),
+                ("load_offset", load_offset as u64),
+            ],
+        );
+    }
+}
+
+#[test]
+fn atoms_round_trips() {
+    let mut rng = Rng::new();
+
+    for _ in 0..ITERATIONS {
+        let predicate = rng.predicate();
+        let invert = rng.flag();
+        let destination = rng.register();
+        let source_a = rng.register();
+        let source_b = rng.register();
+        let offset = ((rng.below(0x40_0000) as i32) - 0x20_0000) << 2;
+        let type_size = rng.variant(AtomsPrimitiveType::VARIANTS);
+        let operation = rng.variant(AtomsOperation::VARIANTS);
+
+        let instruction = encode_atoms(
+            TARGET,
+            predicate,
+            invert,
+            destination,
+            source_a,
+            source_b,
+            offset,
+            AtomsPrimitiveType::from(type_size as u8),
+            AtomsOperation::from(operation as u8),
+        )
+        .unwrap();
+
+        check_encoded(
+            instruction,
+            Opcode::ATOMS,
+            &[
+                ("source_predicate_register", predicate as u64),
+                ("invert_source_predicate", invert as u64),
+                ("destination_register", destination as u64),
+                ("source_register_a", source_a as u64),
+                ("source_register_b", source_b as u64),
+                ("register_a_offset_shr_2", (offset >> 2) as u64),
+                ("type_size", type_size),
+                ("operation", operation),
+            ],
+        );
+    }
+}
+
+#[test]
+fn atoms_cas_round_trips() {
+    let mut rng = Rng::new();
+
+    for _ in 0..ITERATIONS {
+        let predicate = rng.predicate();
+        let invert = rng.flag();
+        let destination = rng.register();
+        let source_a = rng.register();
+        let source_b = (rng.below(0xFF) + 1) as u8;
+        let offset = ((rng.below(0x40_0000) as i32) - 0x20_0000) << 2;
+        let type_size = rng.variant(AtomicCasPrimitiveType::VARIANTS);
+        let operation = rng.variant(AtomsCasOperation::VARIANTS);
+
+        let instruction = encode_atoms_cas(
+            TARGET,
+            predicate,
+            invert,
+            destination,
+            source_a,
+            source_b,
+            offset,
+            AtomicCasPrimitiveType::from(type_size as u8),
+            AtomsCasOperation::from(operation as u8),
+        )
+        .unwrap();
+
+        check_encoded(
+            instruction,
+            Opcode::ATOMS_CAS,
+            &[
+                ("source_predicate_register", predicate as u64),
+                ("invert_source_predicate", invert as u64),
+                ("destination_register", destination as u64),
+                ("source_register_a", source_a as u64),
+                ("source_register_b", source_b as u64),
+                ("register_a_offset_shr_2", (offset >> 2) as u64),
+                ("type_size", type_size),
+                ("operation", operation),
+            ],
+        );
+
+        // The comparison value is held by the register preceding source_register_b.
+        assert_eq!(
+            AtomsCasIntruction(instruction).compare_register(),
+            source_b - 1
+        );
+    }
+}
+
+/// Pick an operation and a primitive type accepted by Opcode::ATOM or Opcode::RED.
+fn random_atom_operation(rng: &mut Rng, is_reduction: bool) -> (u64, u64) {
+    loop {
+        let operation = rng.variant(AtomOperation::VARIANTS);
+        let type_size = rng.variant(AtomPrimitiveType::VARIANTS);
+
+        if AtomOperation::from(operation as u8)
+            .is_valid(&AtomPrimitiveType::from(type_size as u8), is_reduction)
+        {
+            return (operation, type_size);
+        }
+    }
+}
+
+#[test]
+fn atom_round_trips() {
+    let mut rng = Rng::new();
+
+    for _ in 0..ITERATIONS {
+        let predicate = rng.predicate();
+        let invert = rng.flag();
+        let destination = rng.register();
+        let source_a = rng.register();
+        let source_b = rng.register();
+        let offset = (rng.below(0x10_0000) as i32) - 0x8_0000;
+        let (operation, type_size) = random_atom_operation(&mut rng, false);
+        let e_flag = rng.flag();
+
+        let instruction = encode_atom(
+            TARGET,
+            predicate,
+            invert,
+            destination,
+            source_a,
+            source_b,
+            offset,
+            AtomPrimitiveType::from(type_size as u8),
+            AtomOperation::from(operation as u8),
+            e_flag,
+        )
+        .unwrap();
+
+        check_encoded(
+            instruction,
+            Opcode::ATOM,
+            &[
+                ("source_predicate_register", predicate as u64),
+                ("invert_source_predicate", invert as u64),
+                ("destination_register", destination as u64),
+                ("source_register_a", source_a as u64),
+                ("source_register_b", source_b as u64),
+                ("register_a_offset", offset as u64),
+                ("type_size", type_size),
+                ("operation", operation),
+                ("e_flag", e_flag as u64),
+            ],
+        );
+    }
+}
+
+#[test]
+fn red_round_trips() {
+    let mut rng = Rng::new();
+
+    for _ in 0..ITERATIONS {
+        let predicate = rng.predicate();
+        let invert = rng.flag();
+        let source_a = rng.register();
+        let source_b = rng.register();
+        let offset = (rng.below(0x10_0000) as i32) - 0x8_0000;
+        let (operation, type_size) = random_atom_operation(&mut rng, true);
+        let e_flag = rng.flag();
+
+        let instruction = encode_red(
+            TARGET,
+            predicate,
+            invert,
+            source_a,
+            source_b,
+            offset,
+            AtomPrimitiveType::from(type_size as u8),
+            AtomOperation::from(operation as u8),
+            e_flag,
+        )
+        .unwrap();
+
+        check_encoded(
+            instruction,
+            Opcode::RED,
+            &[
+                ("source_predicate_register", predicate as u64),
+                ("invert_source_predicate", invert as u64),
+                ("source_register_a", source_a as u64),
+                ("source_register_b", source_b as u64),
+                ("register_a_offset", offset as u64),
+                ("type_size", type_size),
+                ("operation", operation),
+                ("e_flag", e_flag as u64),
+            ],
+        );
+    }
+}
+
+#[test]
+fn rejects_invalid_atomic_operations() {
+    assert_eq!(
+        encode_atom(
+            TARGET,
+            7,
+            false,
+            0,
+            1,
+            2,
+            0,
+            AtomPrimitiveType::F32,
+            AtomOperation::MIN,
+            true,
+        ),
+        Err(EncodeError::InvalidAtomicOperation(
+            Opcode::ATOM,
+            AtomOperation::MIN,
+            AtomPrimitiveType::F32
+        ))
+    );
+    assert_eq!(
+        encode_red(
+            TARGET,
+            7,
+            false,
+            1,
+            2,
+            0,
+            AtomPrimitiveType::U32,
+            AtomOperation::EXCH,
+            true,
+        ),
+        Err(EncodeError::InvalidAtomicOperation(
+            Opcode::RED,
+            AtomOperation::EXCH,
+            AtomPrimitiveType::U32
+        ))
+    );
+    assert_eq!(
+        encode_suatom(
+            TARGET,
+            7,
+            false,
+            0,
+            1,
+            2,
+            3,
+            SurfaceType::SURFACE_2D,
+            AtomPrimitiveType::S32,
+            AtomOperation::INC,
+            SurfaceClampMode::IGN,
+        ),
+        Err(EncodeError::InvalidAtomicOperation(
+            Opcode::SUATOM,
+            AtomOperation::INC,
+            AtomPrimitiveType::S32
+        ))
+    );
+    assert_eq!(
+        encode_sured(
+            TARGET,
+            7,
+            false,
+            1,
+            2,
+            3,
+            SurfaceType::SURFACE_2D,
+            AtomPrimitiveType::U64,
+            AtomOperation::SAFE_ADD,
+            SurfaceClampMode::IGN,
+        ),
+        Err(EncodeError::InvalidAtomicOperation(
+            Opcode::SURED,
+            AtomOperation::SAFE_ADD,
+            AtomPrimitiveType::U64
+        ))
+    );
+}
+
+#[test]
+fn rejects_instructions_unsupported_by_target() {
+    let hadd2 = |target| {
+        encode_hadd2(
+            target,
+            7,
+            false,
+            0,
+            1,
+            HalfSwizzle::H1_H0,
+            false,
+            false,
+            2,
+            HalfSwizzle::H1_H0,
+            false,
+            false,
+            HalfMerge::H1_H0,
+            false,
+            false,
+        )
+    };
+
+    assert_eq!(
+        hadd2(Target::SM52),
+        Err(EncodeError::UnsupportedInstruction(
+            Opcode::HADD2,
+            Target::SM52
+        ))
+    );
+    assert!(hadd2(Target::SM53).is_ok());
+
+    let atom = |target, type_size| {
+        encode_atom(
+            target,
+            7,
+            false,
+            0,
+            1,
+            2,
+            0,
+            type_size,
+            AtomOperation::ADD,
+            true,
+        )
+    };
+
+    // Packed half-precision atomics were introduced with Pascal.
+    assert_eq!(
+        atom(Target::SM53, AtomPrimitiveType::F16X2),
+        Err(EncodeError::UnsupportedAtomicType(
+            Opcode::ATOM,
+            AtomPrimitiveType::F16X2,
+            Target::SM53
+        ))
+    );
+    assert!(atom(Target::SM60, AtomPrimitiveType::F16X2).is_ok());
+    assert!(atom(Target::SM50, AtomPrimitiveType::S64).is_ok());
+    assert_eq!(
+        encode_red(
+            Target::SM52,
+            7,
+            false,
+            1,
+            2,
+            0,
+            AtomPrimitiveType::F16X2,
+            AtomOperation::ADD,
+            true,
+        ),
+        Err(EncodeError::UnsupportedAtomicType(
+            Opcode::RED,
+            AtomPrimitiveType::F16X2,
+            Target::SM52
+        ))
+    );
+
+    assert_eq!(
+        encode_atom(
+            Target::SM60,
+            7,
+            false,
+            0,
+            1,
+            2,
+            0,
+            AtomPrimitiveType::F16X2,
+            AtomOperation::MAX,
+            true,
+        ),
+        Err(EncodeError::InvalidAtomicOperation(
+            Opcode::ATOM,
+            AtomOperation::MAX,
+            AtomPrimitiveType::F16X2
+        ))
+    );
+}
+
+#[test]
+fn bar_round_trips() {
+    let mut rng = Rng::new();
+
+    for _ in 0..ITERATIONS {
+        let predicate = rng.predicate();
+        let invert = rng.flag();
+        let mode = rng.variant(BarMode::VARIANTS);
+        let reduction = rng.variant(BarReductionOperation::VARIANTS);
+        let barrier_immediate = rng.flag();
+        let barrier = if barrier_immediate {
+            rng.below(16) as u8
+        } else {
+            rng.register()
+        };
+        let thread_count_immediate = rng.flag();
+        let thread_count = if thread_count_immediate {
+            rng.below(0x1000) as u16
+        } else {
+            rng.register() as u16
+        };
+        let reduction_predicate = rng.predicate();
+        let invert_reduction_predicate = rng.flag();
+
+        let instruction = encode_bar(
+            TARGET,
+            predicate,
+            invert,
+            BarMode::from(mode as u8),
+            BarReductionOperation::from(reduction as u8),
+            barrier,
+            barrier_immediate,
+            thread_count,
+            thread_count_immediate,
+            reduction_predicate,
+            invert_reduction_predicate,
+        )
+        .unwrap();
+
+        check_encoded(
+            instruction,
+            Opcode::BAR,
+            &[
+                ("source_predicate_register", predicate as u64),
+                ("invert_source_predicate", invert as u64),
+                ("mode", mode),
+                ("reduction", reduction),
+                ("barrier", barrier as u64),
+                ("barrier_immediate_flag", barrier_immediate as u64),
+                ("thread_count", thread_count as u64),
+                ("thread_count_immediate_flag", thread_count_immediate as u64),
+                ("reduction_predicate_register", reduction_predicate as u64),
+                (
+                    "invert_reduction_predicate",
+                    invert_reduction_predicate as u64,
+                ),
+            ],
+        );
+    }
+}
+
+#[test]
+fn cctl_round_trips() {
+    let mut rng = Rng::new();
+
+    for _ in 0..ITERATIONS {
+        let predicate = rng.predicate();
+        let invert = rng.flag();
+        let source = rng.register();
+        let offset = ((rng.below(0x4000_0000) as i32) - 0x2000_0000) << 2;
+        let operation = rng.variant(CacheOperation::VARIANTS);
+        let cache = rng.variant(CacheType::VARIANTS);
+
+        let instruction = encode_cctl(
+            TARGET,
+            predicate,
+            invert,
+            source,
+            offset,
+            CacheOperation::from(operation as u8),
+            CacheType::from(cache as u8),
+        )
+        .unwrap();
+
+        check_encoded(
+            instruction,
+            Opcode::CCTL,
+            &[
+                ("source_predicate_register", predicate as u64),
+                ("invert_source_predicate", invert as u64),
+                ("source_register", source as u64),
+                ("offset_shr_2", (offset >> 2) as u64),
+                ("operation", operation),
+                ("cache", cache),
+            ],
+        );
+    }
+}
+
+#[test]
+fn tex_round_trips() {
+    let mut rng = Rng::new();
+
+    for _ in 0..ITERATIONS {
+        let predicate = rng.predicate();
+        let invert = rng.flag();
+        let destination = rng.register();
+        let source_a = rng.register();
+        let source_b = rng.register();
+        let bindless = rng.flag();
+        let texture_index = if bindless {
+            0
+        } else {
+            rng.below(0x2000) as u16
+        };
+        let texture_type = rng.variant(TextureType::VARIANTS);
+        let lod_mode = rng.variant(TextureLodMode::VARIANTS);
+        let component_mask = rng.below(0x10) as u8;
+        let aoffi_flag = rng.flag();
+        let dc_flag = rng.flag();
+        let nodep_flag = rng.flag();
+        let ndv_flag = rng.flag();
+        let lc_flag = rng.flag();
+
+        let instruction = encode_tex(
+            TARGET,
+            predicate,
+            invert,
+            destination,
+            source_a,
+            source_b,
+            texture_index,
+            bindless,
+            TextureType::from(texture_type as u8),
+            TextureLodMode::from(lod_mode as u8),
+            component_mask,
+            aoffi_flag,
+            dc_flag,
+            nodep_flag,
+            ndv_flag,
+            lc_flag,
+        )
+        .unwrap();
+
+        let mut expected = vec![
+            ("source_predicate_register", predicate as u64),
+            ("invert_source_predicate", invert as u64),
+            ("destination_register", destination as u64),
+            ("source_register_a", source_a as u64),
+            ("source_register_b", source_b as u64),
+            ("texture_type", texture_type),
+            ("lod_mode", lod_mode),
+            ("component_mask", component_mask as u64),
+            ("aoffi_flag", aoffi_flag as u64),
+            ("dc_flag", dc_flag as u64),
+            ("nodep_flag", nodep_flag as u64),
+            ("ndv_flag", ndv_flag as u64),
+            ("lc_flag", lc_flag as u64),
+            // PT, sparse residency isn't supported.
+            ("sparse_predicate_register", 7),
+        ];
+
+        if bindless {
+            check_encoded(instruction, Opcode::TEX_B, &expected);
+        } else {
+            expected.push(("texture_index", texture_index as u64));
+
+            check_encoded(instruction, Opcode::TEX, &expected);
+        }
+    }
+}
+
+#[test]
+fn texs_round_trips() {
+    let mut rng = Rng::new();
+
+    for _ in 0..ITERATIONS {
+        let predicate = rng.predicate();
+        let invert = rng.flag();
+        let destination_a = rng.register();
+        let destination_b = rng.register();
+        let source_a = rng.register();
+        let source_b = rng.register();
+        let texture_index = rng.below(0x2000) as u16;
+        let encoding = rng.below(TEXS_ENCODINGS.len() as u64) as usize;
+        let (texture_type, lod_mode, dc_flag) = TEXS_ENCODINGS[encoding];
+        let masks: &[u8] = if rng.flag() {
+            &TEXTURE_DUAL_DESTINATION_MASKS
+        } else {
+            &TEXTURE_SINGLE_DESTINATION_MASKS
+        };
+        let swizzle = rng.below(masks.len() as u64) as usize;
+        let nodep_flag = rng.flag();
+        let fp16_flag = rng.flag();
+
+        let instruction = encode_texs(
+            TARGET,
+            predicate,
+            invert,
+            destination_a,
+            destination_b,
+            source_a,
+            source_b,
+            texture_index,
+            texture_type,
+            lod_mode,
+            dc_flag,
+            masks[swizzle],
+            nodep_flag,
+            fp16_flag,
+        )
+        .unwrap();
+
+        check_encoded(
+            instruction,
+            Opcode::TEXS,
+            &[
+                ("source_predicate_register", predicate as u64),
+                ("invert_source_predicate", invert as u64),
+                ("destination_register_a", destination_a as u64),
+                ("destination_register_b", destination_b as u64),
+                ("source_register_a", source_a as u64),
+                ("source_register_b", source_b as u64),
+                ("texture_index", texture_index as u64),
+                ("component_swizzle", swizzle as u64),
+                ("encoding", encoding as u64),
+                ("nodep_flag", nodep_flag as u64),
+                ("fp16_flag", fp16_flag as u64),
+            ],
+        );
+    }
+}
+
+#[test]
+fn rejects_unsupported_texture_modes() {
+    assert_eq!(
+        encode_texs(
+            TARGET,
+            7,
+            false,
+            0,
+            0xFF,
+            1,
+            2,
+            0,
+            TextureType::TEXTURE_CUBE,
+            TextureLodMode::LZ,
+            true,
+            0x1,
+            false,
+            false,
+        ),
+        Err(EncodeError::UnsupportedTextureMode(
+            Opcode::TEXS,
+            TextureType::TEXTURE_CUBE,
+            TextureLodMode::LZ
+        ))
+    );
+
+    assert_eq!(
+        encode_tlds(
+            TARGET,
+            7,
+            false,
+            0,
+            0xFF,
+            1,
+            2,
+            0,
+            TextureType::TEXTURE_3D,
+            TextureLodMode::LL,
+            false,
+            false,
+            0x1,
+            false,
+            false,
+        ),
+        Err(EncodeError::UnsupportedTextureMode(
+            Opcode::TLDS,
+            TextureType::TEXTURE_3D,
+            TextureLodMode::LL
+        ))
+    );
+
+    assert_eq!(
+        encode_tld(
+            TARGET,
+            7,
+            false,
+            0,
+            1,
+            2,
+            0,
+            false,
+            TextureType::TEXTURE_2D,
+            TextureLodMode::LB,
+            0xF,
+            false,
+            false,
+            false,
+        ),
+        Err(EncodeError::UnsupportedTextureMode(
+            Opcode::TLD,
+            TextureType::TEXTURE_2D,
+            TextureLodMode::LB
+        ))
+    );
+
+    // NOTE: 0x5 skips a component, which the compressed forms cannot express.
+    assert_eq!(
+        encode_texs(
+            TARGET,
+            7,
+            false,
+            0,
+            0xFF,
+            1,
+            2,
+            0,
+            TextureType::TEXTURE_2D,
+            TextureLodMode::NONE,
+            false,
+            0x5,
+            false,
+            false,
+        ),
+        Err(EncodeError::UnsupportedComponentMask(Opcode::TEXS, 0x5))
+    );
+}
+
+#[test]
+fn tld4_round_trips() {
+    let mut rng = Rng::new();
+
+    for _ in 0..ITERATIONS {
+        let predicate = rng.predicate();
+        let invert = rng.flag();
+        let destination = rng.register();
+        let source_a = rng.register();
+        let source_b = rng.register();
+        let bindless = rng.flag();
+        let texture_index = if bindless {
+            0
+        } else {
+            rng.below(0x2000) as u16
+        };
+        let texture_type = rng.variant(TextureType::VARIANTS);
+        let component = rng.variant(TextureComponent::VARIANTS);
+        let component_mask = rng.below(0x10) as u8;
+        let offset_mode = rng.variant(TextureGatherOffset::VARIANTS);
+        let dc_flag = rng.flag();
+        let nodep_flag = rng.flag();
+        let ndv_flag = rng.flag();
+
+        let instruction = encode_tld4(
+            TARGET,
+            predicate,
+            invert,
+            destination,
+            source_a,
+            source_b,
+            texture_index,
+            bindless,
+            TextureType::from(texture_type as u8),
+            TextureComponent::from(component as u8),
+            component_mask,
+            TextureGatherOffset::from(offset_mode as u8),
+            dc_flag,
+            nodep_flag,
+            ndv_flag,
+        )
+        .unwrap();
+
+        let mut expected = vec![
+            ("source_predicate_register", predicate as u64),
+            ("invert_source_predicate", invert as u64),
+            ("destination_register", destination as u64),
+            ("source_register_a", source_a as u64),
+            ("source_register_b", source_b as u64),
+            ("texture_type", texture_type),
+            ("component", component),
+            ("component_mask", component_mask as u64),
+            ("offset_mode", offset_mode),
+            ("dc_flag", dc_flag as u64),
+            ("nodep_flag", nodep_flag as u64),
+            ("ndv_flag", ndv_flag as u64),
+        ];
+
+        if bindless {
+            check_encoded(instruction, Opcode::TLD4_B, &expected);
+        } else {
+            expected.push(("texture_index", texture_index as u64));
+
+            check_encoded(instruction, Opcode::TLD4, &expected);
+        }
+    }
+}
+
+#[test]
+fn suld_sust_round_trips() {
+    let mut rng = Rng::new();
+
+    for _ in 0..ITERATIONS {
+        let predicate = rng.predicate();
+        let invert = rng.flag();
+        let register = rng.register();
+        let source = rng.register();
+        let bindless = rng.flag();
+        let surface_index = if bindless {
+            rng.register() as u16
+        } else {
+            rng.below(0x2000) as u16
+        };
+        let surface_type = rng.variant(SurfaceType::VARIANTS);
+        let d_flag = rng.flag();
+        let size = rng.variant(SurfaceSize::VARIANTS);
+        let component_mask = rng.below(0x10) as u8;
+        let clamp_mode = rng.variant(SurfaceClampMode::VARIANTS);
+        let store = rng.flag();
+
+        let encode = if store { encode_sust } else { encode_suld };
+        let instruction = encode(
+            TARGET,
+            predicate,
+            invert,
+            // NOTE: the register written by SULD and the address of SUST come first.
+            if store { source } else { register },
+            if store { register } else { source },
+            surface_index,
+            bindless,
+            SurfaceType::from(surface_type as u8),
+            d_flag,
+            SurfaceSize::from(size as u8),
+            component_mask,
+            SurfaceClampMode::from(clamp_mode as u8),
+        )
+        .unwrap();
+
+        let mut expected = vec![
+            ("source_predicate_register", predicate as u64),
+            ("invert_source_predicate", invert as u64),
+            ("destination_register", register as u64),
+            ("source_register", source as u64),
+            ("format", if d_flag { size } else { component_mask as u64 }),
+            ("surface_type", surface_type),
+            ("clamp_mode", clamp_mode),
+            ("d_flag", d_flag as u64),
+        ];
+
+        let opcode = match (store, bindless) {
+            (false, false) => Opcode::SULD,
+            (false, true) => Opcode::SULD_B,
+            (true, false) => Opcode::SUST,
+            (true, true) => Opcode::SUST_B,
+        };
+
+        if bindless {
+            expected.push(("surface_register", surface_index as u64));
+        } else {
+            expected.push(("surface_index", surface_index as u64));
+        }
+
+        check_encoded(instruction, opcode, &expected);
+    }
+}
+
+#[test]
+fn suatom_round_trips() {
+    let mut rng = Rng::new();
+
+    for _ in 0..ITERATIONS {
+        let predicate = rng.predicate();
+        let invert = rng.flag();
+        let destination = rng.register();
+        let source_a = rng.register();
+        let source_b = rng.register();
+        let surface = rng.register();
+        let surface_type = rng.variant(SurfaceType::VARIANTS);
+        let (operation, type_size) = random_atom_operation(&mut rng, false);
+        let clamp_mode = rng.variant(SurfaceClampMode::VARIANTS);
+
+        let instruction = encode_suatom(
+            TARGET,
+            predicate,
+            invert,
+            destination,
+            source_a,
+            source_b,
+            surface,
+            SurfaceType::from(surface_type as u8),
+            AtomPrimitiveType::from(type_size as u8),
+            AtomOperation::from(operation as u8),
+            SurfaceClampMode::from(clamp_mode as u8),
+        )
+        .unwrap();
+
+        check_encoded(
+            instruction,
+            Opcode::SUATOM,
+            &[
+                ("source_predicate_register", predicate as u64),
+                ("invert_source_predicate", invert as u64),
+                ("destination_register", destination as u64),
+                ("source_register_a", source_a as u64),
+                ("source_register_b", source_b as u64),
+                ("surface_register", surface as u64),
+                ("surface_type", surface_type),
+                ("type_size", type_size),
+                ("operation", operation),
+                ("clamp_mode", clamp_mode),
+            ],
+        );
+    }
+}
+
+#[test]
+fn ipa_round_trips() {
+    let mut rng = Rng::new();
+
+    for _ in 0..ITERATIONS {
+        let predicate = rng.predicate();
+        let invert = rng.flag();
+        let destination = rng.register();
+        let index = rng.register();
+        let multiplier = rng.register();
+        let sample = rng.register();
+        let attribute_offset = (rng.below(0x100) << 2) as u16;
+        let idx_flag = rng.flag();
+        let interpolation_mode = rng.variant(IpaInterpolationMode::VARIANTS);
+        let sample_mode = rng.variant(IpaSampleMode::VARIANTS);
+        let sat_flag = rng.flag();
+
+        let instruction = encode_ipa(
+            TARGET,
+            predicate,
+            invert,
+            destination,
+            index,
+            multiplier,
+            sample,
+            attribute_offset,
+            idx_flag,
+            IpaInterpolationMode::from(interpolation_mode as u8),
+            IpaSampleMode::from(sample_mode as u8),
+            sat_flag,
+        )
+        .unwrap();
+
+        check_encoded(
+            instruction,
+            Opcode::IPA,
+            &[
+                ("source_predicate_register", predicate as u64),
+                ("invert_source_predicate", invert as u64),
+                ("destination_register", destination as u64),
+                ("source_register_a", index as u64),
+                ("source_register_b", multiplier as u64),
+                ("source_register_c", sample as u64),
+                ("attribute_shr_2", (attribute_offset >> 2) as u64),
+                ("idx_flag", idx_flag as u64),
+                ("interpolation_mode", interpolation_mode),
+                ("sample_mode", sample_mode),
+                ("sat_flag", sat_flag as u64),
+            ],
+        );
+    }
+}
+
+#[test]
+fn out_round_trips() {
+    let mut rng = Rng::new();
+
+    for _ in 0..ITERATIONS {
+        let predicate = rng.predicate();
+        let invert = rng.flag();
+        let destination = rng.register();
+        let source = rng.register();
+        let stream_immediate = rng.flag();
+        let stream = if stream_immediate {
+            rng.below(4) as u8
+        } else {
+            rng.register()
+        };
+        let mode = rng.variant(OutMode::VARIANTS);
+
+        let instruction = encode_out(
+            TARGET,
+            predicate,
+            invert,
+            destination,
+            source,
+            stream,
+            stream_immediate,
+            OutMode::from(mode as u8),
+        )
+        .unwrap();
+
+        let (opcode, stream_field) = if stream_immediate {
+            (Opcode::OUT_IMM, "stream")
+        } else {
+            (Opcode::OUT, "source_register_b")
+        };
+
+        check_encoded(
+            instruction,
+            opcode,
+            &[
+                ("source_predicate_register", predicate as u64),
+                ("invert_source_predicate", invert as u64),
+                ("destination_register", destination as u64),
+                ("source_register_a", source as u64),
+                (stream_field, stream as u64),
+                ("mode", mode),
+            ],
+        );
+    }
+}
+
+#[test]
+fn vmnmx_round_trips() {
+    let mut rng = Rng::new();
+
+    for _ in 0..ITERATIONS {
+        let predicate = rng.predicate();
+        let invert = rng.flag();
+        let destination = rng.register();
+        let source_a = rng.register();
+        let a_selector = rng.variant(VideoSelector::VARIANTS);
+        let a_signed = rng.flag();
+        let b_immediate = rng.flag();
+        let source_b = if b_immediate {
+            rng.next() as u16
+        } else {
+            rng.register() as u16
+        };
+        let b_selector = rng.variant(VideoSelector::VARIANTS);
+        let b_signed = rng.flag();
+        let source_c = rng.register();
+        let secondary_operation = rng.variant(VideoSecondaryOperation::VARIANTS);
+        let sat_flag = rng.flag();
+        let mx_flag = rng.flag();
+
+        let instruction = encode_vmnmx(
+            TARGET,
+            predicate,
+            invert,
+            destination,
+            source_a,
+            VideoSelector::from(a_selector as u8),
+            a_signed,
+            source_b,
+            if b_immediate {
+                None
+            } else {
+                Some(VideoSelector::from(b_selector as u8))
+            },
+            b_signed,
+            b_immediate,
+            source_c,
+            VideoSecondaryOperation::from(secondary_operation as u8),
+            sat_flag,
+            mx_flag,
+        )
+        .unwrap();
+
+        let mut expected = vec![
+            ("source_predicate_register", predicate as u64),
+            ("invert_source_predicate", invert as u64),
+            ("destination_register", destination as u64),
+            ("source_register_a", source_a as u64),
+            ("a_selector", a_selector),
+            ("a_signed_flag", a_signed as u64),
+            ("b_signed_flag", b_signed as u64),
+            ("source_register_c", source_c as u64),
+            ("secondary_operation", secondary_operation),
+            ("sat_flag", sat_flag as u64),
+            ("mx_flag", mx_flag as u64),
+        ];
+
+        if b_immediate {
+            expected.push(("imm16", source_b as u64));
+
+            check_encoded(instruction, Opcode::VMNMX_IMM, &expected);
+        } else {
+            expected.push(("source_register_b", source_b as u64));
+            expected.push(("b_selector", b_selector));
+
+            check_encoded(instruction, Opcode::VMNMX, &expected);
+        }
+    }
+}
+
+/// Operand B of a video instruction, an immediate or a register with a selector.
+fn random_video_b(rng: &mut Rng) -> (bool, u16, u64) {
+    let b_immediate = rng.flag();
+    let source_b = if b_immediate {
+        rng.next() as u16
+    } else {
+        rng.register() as u16
+    };
+
+    (b_immediate, source_b, rng.variant(VideoSelector::VARIANTS))
+}
+
+#[test]
+fn vadd_round_trips() {
+    let mut rng = Rng::new();
+
+    for _ in 0..ITERATIONS {
+        let predicate = rng.predicate();
+        let invert = rng.flag();
+        let destination = rng.register();
+        let source_a = rng.register();
+        let a_selector = rng.variant(VideoSelector::VARIANTS);
+        let a_signed = rng.flag();
+        let (b_immediate, source_b, b_selector) = random_video_b(&mut rng);
+        let b_signed = rng.flag();
+        let source_c = rng.register();
+        let secondary_operation = rng.variant(VideoSecondaryOperation::VARIANTS);
+        let sat_flag = rng.flag();
+
+        let instruction = encode_vadd(
+            TARGET,
+            predicate,
+            invert,
+            destination,
+            source_a,
+            VideoSelector::from(a_selector as u8),
+            a_signed,
+            source_b,
+            if b_immediate {
+                None
+            } else {
+                Some(VideoSelector::from(b_selector as u8))
+            },
+            b_signed,
+            b_immediate,
+            source_c,
+            VideoSecondaryOperation::from(secondary_operation as u8),
+            sat_flag,
+        )
+        .unwrap();
+
+        let mut expected = vec![
+            ("source_predicate_register", predicate as u64),
+            ("invert_source_predicate", invert as u64),
+            ("destination_register", destination as u64),
+            ("source_register_a", source_a as u64),
+            ("a_selector", a_selector),
+            ("a_signed_flag", a_signed as u64),
+            ("b_signed_flag", b_signed as u64),
+            ("source_register_c", source_c as u64),
+            ("secondary_operation", secondary_operation),
+            ("sat_flag", sat_flag as u64),
+        ];
+
+        if b_immediate {
+            expected.push(("imm16", source_b as u64));
+
+            check_encoded(instruction, Opcode::VADD_IMM, &expected);
+        } else {
+            expected.push(("source_register_b", source_b as u64));
+            expected.push(("b_selector", b_selector));
+
+            check_encoded(instruction, Opcode::VADD, &expected);
+        }
+    }
+}
+
+#[test]
+fn rejects_invalid_video_operands() {
+    let vadd = |source_b, b_selector, b_immediate| {
+        encode_vadd(
+            TARGET,
+            7,
+            false,
+            0,
+            1,
+            VideoSelector::B0,
+            false,
+            source_b,
+            b_selector,
+            false,
+            b_immediate,
+            3,
+            VideoSecondaryOperation::NONE,
+            false,
+        )
+    };
+
+    assert_eq!(
+        vadd(0x100, Some(VideoSelector::B0), false),
+        Err(EncodeError::InvalidVideoRegister(Opcode::VADD, 0x100))
+    );
+    assert_eq!(
+        vadd(2, None, false),
+        Err(EncodeError::MissingVideoSelector(Opcode::VADD))
+    );
+    assert_eq!(
+        vadd(0x1234, Some(VideoSelector::H1), true),
+        Err(EncodeError::UnexpectedVideoSelector(
+            Opcode::VADD_IMM,
+            VideoSelector::H1
+        ))
+    );
+    assert!(vadd(0xFF, Some(VideoSelector::W), false).is_ok());
+    assert!(vadd(0x1234, None, true).is_ok());
+
+    assert_eq!(
+        encode_vsetp(
+            TARGET,
+            7,
+            false,
+            0,
+            7,
+            1,
+            VideoSelector::B0,
+            false,
+            0x1234,
+            Some(VideoSelector::B0),
+            false,
+            true,
+            VideoCompareOperation::from(0),
+            PredicateOperation::from(0),
+            7,
+            false,
+        ),
+        Err(EncodeError::UnexpectedVideoSelector(
+            Opcode::VSETP_IMM,
+            VideoSelector::B0
+        ))
+    );
+}
+
+#[test]
+fn vmad_round_trips() {
+    let mut rng = Rng::new();
+
+    for _ in 0..ITERATIONS {
+        let predicate = rng.predicate();
+        let invert = rng.flag();
+        let destination = rng.register();
+        let source_a = rng.register();
+        let a_selector = rng.variant(VideoSelector::VARIANTS);
+        let a_signed = rng.flag();
+        let (b_immediate, source_b, b_selector) = random_video_b(&mut rng);
+        let b_signed = rng.flag();
+        let source_c = rng.register();
+        let scale = rng.variant(VideoScale::VARIANTS);
+        let sat_flag = rng.flag();
+
+        let instruction = encode_vmad(
+            TARGET,
+            predicate,
+            invert,
+            destination,
+            source_a,
+            VideoSelector::from(a_selector as u8),
+            a_signed,
+            source_b,
+            if b_immediate {
+                None
+            } else {
+                Some(VideoSelector::from(b_selector as u8))
+            },
+            b_signed,
+            b_immediate,
+            source_c,
+            VideoScale::from(scale as u8),
+            sat_flag,
+        )
+        .unwrap();
+
+        let mut expected = vec![
+            ("source_predicate_register", predicate as u64),
+            ("invert_source_predicate", invert as u64),
+            ("destination_register", destination as u64),
+            ("source_register_a", source_a as u64),
+            ("a_selector", a_selector),
+            ("a_signed_flag", a_signed as u64),
+            ("b_signed_flag", b_signed as u64),
+            ("source_register_c", source_c as u64),
+            ("scale", scale),
+            ("sat_flag", sat_flag as u64),
+        ];
+
+        if b_immediate {
+            expected.push(("imm16", source_b as u64));
+
+            check_encoded(instruction, Opcode::VMAD_IMM, &expected);
+        } else {
+            expected.push(("source_register_b", source_b as u64));
+            expected.push(("b_selector", b_selector));
+
+            check_encoded(instruction, Opcode::VMAD, &expected);
+        }
+    }
+}
+
+#[test]
+fn vsetp_round_trips() {
+    let mut rng = Rng::new();
+
+    for _ in 0..ITERATIONS {
+        let predicate = rng.predicate();
+        let invert = rng.flag();
+        let destination_a = rng.predicate();
+        let destination_b = rng.predicate();
+        let source_a = rng.register();
+        let a_selector = rng.variant(VideoSelector::VARIANTS);
+        let a_signed = rng.flag();
+        let (b_immediate, source_b, b_selector) = random_video_b(&mut rng);
+        let b_signed = rng.flag();
+        let compare_operation = rng.variant(VideoCompareOperation::VARIANTS);
+        let predicate_operation = rng.variant(PredicateOperation::VARIANTS);
+        let combine_predicate = rng.predicate();
+        let invert_combine_predicate = rng.flag();
+
+        let instruction = encode_vsetp(
+            TARGET,
+            predicate,
+            invert,
+            destination_a,
+            destination_b,
+            source_a,
+            VideoSelector::from(a_selector as u8),
+            a_signed,
+            source_b,
+            if b_immediate {
+                None
+            } else {
+                Some(VideoSelector::from(b_selector as u8))
+            },
+            b_signed,
+            b_immediate,
+            VideoCompareOperation::from(compare_operation as u8),
+            PredicateOperation::from(predicate_operation as u8),
+            combine_predicate,
+            invert_combine_predicate,
+        )
+        .unwrap();
+
+        let mut expected = vec![
+            ("source_predicate_register", predicate as u64),
+            ("invert_source_predicate", invert as u64),
+            ("destination_predicate_register_a", destination_a as u64),
+            ("destination_predicate_register_b", destination_b as u64),
+            ("source_register_a", source_a as u64),
+            ("a_selector", a_selector),
+            ("a_signed_flag", a_signed as u64),
+            ("b_signed_flag", b_signed as u64),
+            ("compare_operation", compare_operation),
+            ("predicate_operation", predicate_operation),
+            ("combine_predicate_register", combine_predicate as u64),
+            ("invert_combine_predicate", invert_combine_predicate as u64),
+        ];
+
+        if b_immediate {
+            expected.push(("imm16", source_b as u64));
+
+            check_encoded(instruction, Opcode::VSETP_IMM, &expected);
+        } else {
+            expected.push(("source_register_b", source_b as u64));
+            expected.push(("b_selector", b_selector));
+
+            check_encoded(instruction, Opcode::VSETP, &expected);
+        }
+    }
+}
+
+/// A pair of half floats that can be encoded as an immediate.
+fn random_half_pair(rng: &mut Rng) -> HalfPair {
+    HalfPair::from_bits(rng.next() as u16 & !0x3F, rng.next() as u16 & !0x3F)
+}
+
+#[test]
+fn hadd2_imm_round_trips() {
+    let mut rng = Rng::new();
+
+    for _ in 0..ITERATIONS {
+        let predicate = rng.predicate();
+        let invert = rng.flag();
+        let destination = rng.register();
+        let source_a = rng.register();
+        let a_swizzle = rng.variant(HalfSwizzle::VARIANTS);
+        let negate_a = rng.flag();
+        let absolute_a = rng.flag();
+        let immediate = random_half_pair(&mut rng);
+        let merge = rng.variant(HalfMerge::VARIANTS);
+        let ftz_flag = rng.flag();
+        let sat_flag = rng.flag();
+
+        let instruction = encode_hadd2_imm(
+            TARGET,
+            predicate,
+            invert,
+            destination,
+            source_a,
+            HalfSwizzle::from(a_swizzle as u8),
+            negate_a,
+            absolute_a,
+            immediate,
+            HalfMerge::from(merge as u8),
+            ftz_flag,
+            sat_flag,
+        )
+        .unwrap();
+
+        // The sign of each half is stored apart from its upper 9 bits.
+        check_encoded(
+            instruction,
+            Opcode::HADD2_IMM,
+            &[
+                ("source_predicate_register", predicate as u64),
+                ("invert_source_predicate", invert as u64),
+                ("destination_register", destination as u64),
+                ("source_register_a", source_a as u64),
+                ("a_swizzle", a_swizzle),
+                ("negate_a", negate_a as u64),
+                ("absolute_a", absolute_a as u64),
+                ("immediate_low", (immediate.low >> 6) as u64),
+                ("negate_immediate_low", (immediate.low >> 15) as u64),
+                ("immediate_high", (immediate.high >> 6) as u64),
+                ("negate_immediate_high", (immediate.high >> 15) as u64),
+                ("merge", merge),
+                ("ftz_flag", ftz_flag as u64),
+                ("sat_flag", sat_flag as u64),
+            ],
+        );
+    }
+}
+
+#[test]
+fn rejects_inexact_half_immediates() {
+    let hadd2_imm = |immediate| {
+        encode_hadd2_imm(
+            TARGET,
+            7,
+            false,
+            0,
+            1,
+            HalfSwizzle::H1_H0,
+            false,
+            false,
+            immediate,
+            HalfMerge::H1_H0,
+            false,
+            false,
+        )
+    };
+
+    // NOTE: 0x3C01 is the half right after 1.0, its lowest mantissa bit cannot be kept.
+    let inexact = HalfPair::from_bits(0x3C00, 0x3C01);
+
+    assert_eq!(
+        hadd2_imm(inexact),
+        Err(EncodeError::InexactHalfImmediate(
+            Opcode::HADD2_IMM,
+            inexact
+        ))
+    );
+    assert!(hadd2_imm(HalfPair::from_bits(0x3C00, 0xBC00)).is_ok());
+}
+
+#[test]
+fn hfma2_round_trips() {
+    let mut rng = Rng::new();
+
+    for _ in 0..ITERATIONS {
+        let predicate = rng.predicate();
+        let invert = rng.flag();
+        let destination = rng.register();
+        let source_a = rng.register();
+        let a_swizzle = rng.variant(HalfSwizzle::VARIANTS);
+        let source_b = rng.register();
+        let b_swizzle = rng.variant(HalfSwizzle::VARIANTS);
+        let negate_b = rng.flag();
+        let source_c = rng.register();
+        let c_swizzle = rng.variant(HalfSwizzle::VARIANTS);
+        let negate_c = rng.flag();
+        let merge = rng.variant(HalfMerge::VARIANTS);
+        let multiply_mode = rng.variant(FloatMultiplyMode::VARIANTS);
+        let sat_flag = rng.flag();
+
+        let instruction = encode_hfma2(
+            TARGET,
+            predicate,
+            invert,
+            destination,
+            source_a,
+            HalfSwizzle::from(a_swizzle as u8),
+            source_b,
+            HalfSwizzle::from(b_swizzle as u8),
+            negate_b,
+            source_c,
+            HalfSwizzle::from(c_swizzle as u8),
+            negate_c,
+            HalfMerge::from(merge as u8),
+            FloatMultiplyMode::from(multiply_mode as u8),
+            sat_flag,
+        )
+        .unwrap();
+
+        check_encoded(
+            instruction,
+            Opcode::HFMA2,
+            &[
+                ("source_predicate_register", predicate as u64),
+                ("invert_source_predicate", invert as u64),
+                ("destination_register", destination as u64),
+                ("source_register_a", source_a as u64),
+                ("a_swizzle", a_swizzle),
+                ("source_register_b", source_b as u64),
+                ("b_swizzle", b_swizzle),
+                ("negate_b", negate_b as u64),
+                ("source_register_c", source_c as u64),
+                ("c_swizzle", c_swizzle),
+                ("negate_c", negate_c as u64),
+                ("merge", merge),
+                ("multiply_mode", multiply_mode),
+                ("sat_flag", sat_flag as u64),
+            ],
+        );
+    }
+}
+
+#[test]
+fn hsetp2_round_trips() {
+    let mut rng = Rng::new();
+
+    for _ in 0..ITERATIONS {
+        let predicate = rng.predicate();
+        let invert = rng.flag();
+        let destination_a = rng.predicate();
+        let destination_b = rng.predicate();
+        let source_a = rng.register();
+        let a_swizzle = rng.variant(HalfSwizzle::VARIANTS);
+        let negate_a = rng.flag();
+        let absolute_a = rng.flag();
+        let source_b = rng.register();
+        let b_swizzle = rng.variant(HalfSwizzle::VARIANTS);
+        let negate_b = rng.flag();
+        let absolute_b = rng.flag();
+        let compare_operation = rng.variant(FloatCompareOperation::VARIANTS);
+        let predicate_operation = rng.variant(PredicateOperation::VARIANTS);
+        let combine_predicate = rng.predicate();
+        let invert_combine_predicate = rng.flag();
+        let h_and_flag = rng.flag();
+        let ftz_flag = rng.flag();
+
+        let instruction = encode_hsetp2(
+            TARGET,
+            predicate,
+            invert,
+            destination_a,
+            destination_b,
+            source_a,
+            HalfSwizzle::from(a_swizzle as u8),
+            negate_a,
+            absolute_a,
+            source_b,
+            HalfSwizzle::from(b_swizzle as u8),
+            negate_b,
+            absolute_b,
+            FloatCompareOperation::from(compare_operation as u8),
+            PredicateOperation::from(predicate_operation as u8),
+            combine_predicate,
+            invert_combine_predicate,
+            h_and_flag,
+            ftz_flag,
+        )
+        .unwrap();
+
+        check_encoded(
+            instruction,
+            Opcode::HSETP2,
+            &[
+                ("source_predicate_register", predicate as u64),
+                ("invert_source_predicate", invert as u64),
+                ("destination_predicate_register_a", destination_a as u64),
+                ("destination_predicate_register_b", destination_b as u64),
+                ("source_register_a", source_a as u64),
+                ("a_swizzle", a_swizzle),
+                ("negate_a", negate_a as u64),
+                ("absolute_a", absolute_a as u64),
+                ("source_register_b", source_b as u64),
+                ("b_swizzle", b_swizzle),
+                ("negate_b", negate_b as u64),
+                ("absolute_b", absolute_b as u64),
+                ("compare_operation", compare_operation),
+                ("predicate_operation", predicate_operation),
+                ("combine_predicate_register", combine_predicate as u64),
+                ("invert_combine_predicate", invert_combine_predicate as u64),
+                ("h_and_flag", h_and_flag as u64),
+                ("ftz_flag", ftz_flag as u64),
+            ],
+        );
+    }
+}
@@ -1,3 +1,13 @@
+pub mod assembler;
+pub mod cubin;
 pub mod definition;
+pub mod disassembler;
+pub mod dump;
+pub mod emitter;
+pub mod emu;
 pub mod encoder;
+pub mod nvn;
+pub mod program;
+pub mod sph;
+pub mod target;
 pub mod util;
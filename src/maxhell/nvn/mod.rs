@@ -0,0 +1,132 @@
+use std::error::Error;
+use std::fmt;
+
+use super::encoder::EncodeError;
+use super::program::{Container, EndOfProgramError, Program, ProgramInfo};
+use super::sph::{ShaderProgramHeader, ShaderType};
+
+#[cfg(test)]
+mod tests;
+
+/// Magic of the container, "DKSH".
+pub const MAGIC: u32 = 0x4853_4b44;
+
+/// Size of the container header.
+pub const HEADER_SIZE: usize = 0x18;
+
+/// Size of the control header of a program.
+pub const PROGRAM_HEADER_SIZE: usize = 0x40;
+
+/// Alignment of the code section and of every program inside of it.
+pub const CODE_ALIGNMENT: usize = 0x100;
+
+/// Padding preceding the SPH of a graphics program.
+///
+/// NOTE: this puts the code following the SPH on 0x80 bytes.
+pub const START_PADDING: usize = 0x30;
+
+/// Error returned when writing a container.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum NvnError {
+    Encode(EncodeError),
+    /// The program at the given index wasn't finalized for Container::Nvn.
+    NotFinalized(usize, EndOfProgramError),
+}
+
+impl fmt::Display for NvnError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NvnError::Encode(error) => write!(f, "{}", error),
+            NvnError::NotFinalized(index, error) => write!(f, "program {}: {}", index, error),
+        }
+    }
+}
+
+impl Error for NvnError {}
+
+impl From<EncodeError> for NvnError {
+    fn from(error: EncodeError) -> Self {
+        NvnError::Encode(error)
+    }
+}
+
+/// The stage of a program, as stored in its control header.
+fn stage(header: Option<&ShaderProgramHeader>) -> u32 {
+    match header.map(|header| header.shader_type) {
+        Some(ShaderType::VERTEX) => 0,
+        Some(ShaderType::TESSELLATION_INIT) => 1,
+        Some(ShaderType::TESSELLATION) => 2,
+        Some(ShaderType::GEOMETRY) => 3,
+        Some(ShaderType::PIXEL) => 4,
+        // Compute programs have no SPH.
+        _ => 5,
+    }
+}
+
+/// Write programs in a container made of a control section followed by a code section.
+///
+/// This is the layout of the deko3d shader files (DKSH) derived from the NVN one:
+/// the control section holds a header and the control header of every program, the code
+/// section holds the programs aligned on CODE_ALIGNMENT. Graphics programs start with
+/// START_PADDING bytes and their SPH.
+///
+/// The programs must have been finalized with the end of program of Container::Nvn.
+pub fn write_nvn(
+    programs: &[(&Program, Option<&ShaderProgramHeader>)],
+) -> Result<Vec<u8>, NvnError> {
+    let control_size =
+        (HEADER_SIZE + programs.len() * PROGRAM_HEADER_SIZE).next_multiple_of(CODE_ALIGNMENT);
+
+    let mut code = Vec::new();
+    let mut program_headers = Vec::new();
+
+    for (index, (program, header)) in programs.iter().enumerate() {
+        program
+            .check_finalized(Container::Nvn)
+            .map_err(|error| NvnError::NotFinalized(index, error))?;
+
+        code.resize(code.len().next_multiple_of(CODE_ALIGNMENT), 0);
+
+        if header.is_some() {
+            code.resize(code.len() + START_PADDING, 0);
+        }
+
+        let entrypoint = code.len() as u32;
+
+        if let Some(header) = header {
+            code.extend_from_slice(&header.to_bytes());
+        }
+
+        code.extend_from_slice(&program.code()?);
+
+        // NOTE: the fields following the register count depend on the stage, we have nothing to set there yet.
+        let mut program_header = [0u32; PROGRAM_HEADER_SIZE / 4];
+
+        program_header[0] = stage(*header);
+        program_header[1] = entrypoint;
+        program_header[2] = ProgramInfo::from_program(program).register_count;
+
+        program_headers.extend(program_header.iter().flat_map(|word| word.to_le_bytes()));
+    }
+
+    code.resize(code.len().next_multiple_of(CODE_ALIGNMENT), 0);
+
+    let mut output = Vec::with_capacity(control_size + code.len());
+
+    for word in &[
+        MAGIC,
+        HEADER_SIZE as u32,
+        control_size as u32,
+        code.len() as u32,
+        HEADER_SIZE as u32,
+        programs.len() as u32,
+    ] {
+        output.extend_from_slice(&word.to_le_bytes());
+    }
+
+    output.extend_from_slice(&program_headers);
+    output.resize(control_size, 0);
+    output.extend_from_slice(&code);
+
+    Ok(output)
+}
@@ -0,0 +1,112 @@
+use std::convert::TryInto;
+
+use super::*;
+use crate::maxhell::definition::ControlCode;
+use crate::maxhell::encoder;
+use crate::maxhell::program::{EndOfProgram, Terminator};
+use crate::maxhell::sph::SPH_WORDS;
+use crate::maxhell::target::Target;
+
+const TARGET: Target = Target::SM53;
+
+const BRA_SELF: u64 = 0xe240_0fff_ff87_000f;
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
+
+fn exit_program() -> Program {
+    let mut program = Program::new(TARGET);
+
+    program.push(encoder::encode_exit(TARGET, 7, false, ControlCode::TRUE, false).unwrap());
+    program
+        .finalize(&Container::Nvn.default_end_of_program())
+        .unwrap();
+
+    program
+}
+
+#[test]
+fn writes_graphics_program() {
+    let program = exit_program();
+    let header = ShaderProgramHeader::from_program(&program, ShaderType::PIXEL);
+
+    let output = write_nvn(&[(&program, Some(&header))]).unwrap();
+
+    assert_eq!(read_u32(&output, 0x0), MAGIC);
+    assert_eq!(read_u32(&output, 0x8), CODE_ALIGNMENT as u32);
+    assert_eq!(read_u32(&output, 0xc), 0x100);
+    assert_eq!(read_u32(&output, 0x14), 1);
+
+    // Stage and entrypoint of the program.
+    assert_eq!(read_u32(&output, HEADER_SIZE), 4);
+    assert_eq!(read_u32(&output, HEADER_SIZE + 4), START_PADDING as u32);
+
+    let code = &output[CODE_ALIGNMENT..];
+
+    assert!(code[..START_PADDING].iter().all(|byte| *byte == 0));
+    assert_eq!(
+        code[START_PADDING..START_PADDING + SPH_WORDS * 4],
+        *header.to_bytes()
+    );
+
+    // The code follows the SPH at 0x80, EXIT is followed by BRA to itself.
+    assert_eq!(read_u64(code, 0x88), program.instructions()[0]);
+    assert_eq!(read_u64(code, 0x90), BRA_SELF);
+}
+
+#[test]
+fn aligns_compute_programs() {
+    let program = exit_program();
+
+    let output = write_nvn(&[(&program, None), (&program, None)]).unwrap();
+    let code = &output[CODE_ALIGNMENT..];
+
+    assert_eq!(read_u32(&output, 0xc), 2 * CODE_ALIGNMENT as u32);
+
+    for (index, entrypoint) in [0, CODE_ALIGNMENT].iter().enumerate() {
+        let program_header = HEADER_SIZE + index * PROGRAM_HEADER_SIZE;
+
+        assert_eq!(read_u32(&output, program_header), 5);
+        assert_eq!(read_u32(&output, program_header + 4), *entrypoint as u32);
+        assert_eq!(read_u64(code, entrypoint + 0x10), BRA_SELF);
+    }
+}
+
+#[test]
+fn rejects_unfinalized_programs() {
+    let finalized = exit_program();
+
+    let mut unterminated = Program::new(TARGET);
+    unterminated.push(encoder::encode_exit(TARGET, 7, false, ControlCode::TRUE, false).unwrap());
+
+    assert_eq!(
+        write_nvn(&[(&finalized, None), (&unterminated, None)]),
+        Err(NvnError::NotFinalized(
+            1,
+            EndOfProgramError::MissingSelfBranch(Container::Nvn)
+        ))
+    );
+
+    // Finalized for a smaller alignment.
+    let mut unaligned = Program::new(TARGET);
+    unaligned.push(encoder::encode_exit(TARGET, 7, false, ControlCode::TRUE, false).unwrap());
+    unaligned
+        .finalize(&EndOfProgram {
+            terminator: Terminator::SelfBranch,
+            alignment: 0x20,
+        })
+        .unwrap();
+
+    assert_eq!(
+        write_nvn(&[(&unaligned, None)]),
+        Err(NvnError::NotFinalized(
+            0,
+            EndOfProgramError::InsufficientAlignment(Container::Nvn, 0x80)
+        ))
+    );
+}
@@ -0,0 +1,378 @@
+use std::fmt;
+
+use super::Program;
+use crate::maxhell::definition::*;
+use crate::maxhell::util::FieldKind;
+
+const RZ: u8 = 0xFF;
+const PT: u8 = 7;
+
+/// Number of named barriers available to a CTA.
+pub const MAX_BARRIERS: u32 = 16;
+
+/// The resources used by a program, as needed to launch it.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct ProgramInfo {
+    /// The highest register index used plus one, RZ isn't counted.
+    pub register_count: u32,
+    /// Mask of the predicates used, PT isn't counted.
+    pub predicates: u8,
+    /// Mask of the barriers selected by an immediate.
+    pub barriers: u16,
+    /// A barrier is selected by a register, any barrier can be used.
+    pub uses_indirect_barrier: bool,
+    pub uses_shared_memory: bool,
+    pub uses_local_memory: bool,
+}
+
+impl ProgramInfo {
+    pub fn from_program(program: &Program) -> Self {
+        let mut info = ProgramInfo::default();
+
+        for instruction in program.instructions() {
+            let instruction = match DecodedInstruction::decode(*instruction) {
+                Some(instruction) => instruction,
+                None => continue,
+            };
+
+            let fields = instruction.opcode().fields();
+
+            for (field, value) in fields.iter().zip(instruction.field_values()) {
+                match field.kind() {
+                    FieldKind::Register => info.use_registers(value as u8, 1),
+                    FieldKind::Predicate if value != u64::from(PT) => {
+                        info.predicates |= 1 << value;
+                    }
+                    _ => {}
+                }
+            }
+
+            match instruction {
+                // Attribute accesses use a vector of registers.
+                DecodedInstruction::ALD(inst) => info.use_registers(
+                    inst.destination_register(),
+                    u32::from(u8::from(inst.mode())) + 1,
+                ),
+                DecodedInstruction::AST(inst) => info.use_registers(
+                    inst.destination_register(),
+                    u32::from(u8::from(inst.mode())) + 1,
+                ),
+                DecodedInstruction::ATOM(inst) => {
+                    let width = atom_width(inst.type_size());
+
+                    info.use_registers(inst.destination_register(), width);
+                    info.use_registers(inst.source_register_b(), width);
+                    info.use_address(inst.source_register_a(), inst.e_flag());
+                }
+                // The comparison value is followed by the stored value.
+                DecodedInstruction::ATOM_CAS(inst) => {
+                    let width = cas_width(inst.type_size());
+
+                    info.use_registers(inst.destination_register(), width);
+                    info.use_registers(inst.source_register_b(), width * 2);
+                    info.use_address(inst.source_register_a(), inst.e_flag());
+                }
+                DecodedInstruction::RED(inst) => {
+                    info.use_registers(inst.source_register_b(), atom_width(inst.type_size()));
+                    info.use_address(inst.source_register_a(), inst.e_flag());
+                }
+                DecodedInstruction::ATOMS(inst) => {
+                    let width = match inst.type_size() {
+                        AtomsPrimitiveType::U64 | AtomsPrimitiveType::S64 => 2,
+                        _ => 1,
+                    };
+
+                    info.use_registers(inst.destination_register(), width);
+                    info.use_registers(inst.source_register_b(), width);
+                    info.uses_shared_memory = true;
+                }
+                DecodedInstruction::ATOMS_CAS(inst) => {
+                    let width = cas_width(inst.type_size());
+
+                    info.use_registers(inst.destination_register(), width);
+                    info.use_registers(inst.compare_register(), width * 2);
+                    info.uses_shared_memory = true;
+                }
+                DecodedInstruction::SUATOM(inst) => {
+                    let width = atom_width(inst.type_size());
+
+                    info.use_registers(inst.destination_register(), width);
+                    info.use_registers(inst.source_register_b(), width);
+                }
+                DecodedInstruction::SURED(inst) => {
+                    info.use_registers(inst.source_register_b(), atom_width(inst.type_size()));
+                }
+                // Texture instructions write a register per selected component.
+                //
+                // NOTE: source_register_a holds the array index followed by the coordinates,
+                // source_register_b the bindless handle followed by the other parameters.
+                DecodedInstruction::TEX(inst) => {
+                    info.use_components(inst.destination_register(), inst.component_mask());
+                    info.use_registers(
+                        inst.source_register_a(),
+                        texture_coordinates(inst.texture_type()),
+                    );
+                    info.use_registers(
+                        inst.source_register_b(),
+                        lod_width(inst.lod_mode())
+                            + u32::from(inst.aoffi_flag())
+                            + u32::from(inst.dc_flag()),
+                    );
+                }
+                DecodedInstruction::TEX_B(inst) => {
+                    info.use_components(inst.destination_register(), inst.component_mask());
+                    info.use_registers(
+                        inst.source_register_a(),
+                        texture_coordinates(inst.texture_type()),
+                    );
+                    info.use_registers(
+                        inst.source_register_b(),
+                        1 + lod_width(inst.lod_mode())
+                            + u32::from(inst.aoffi_flag())
+                            + u32::from(inst.dc_flag()),
+                    );
+                }
+                DecodedInstruction::TLD(inst) => {
+                    info.use_components(inst.destination_register(), inst.component_mask());
+                    info.use_registers(
+                        inst.source_register_a(),
+                        texture_coordinates(inst.texture_type()),
+                    );
+                    info.use_registers(
+                        inst.source_register_b(),
+                        u32::from(inst.ll_flag())
+                            + u32::from(inst.aoffi_flag())
+                            + u32::from(inst.ms_flag()),
+                    );
+                }
+                DecodedInstruction::TLD_B(inst) => {
+                    info.use_components(inst.destination_register(), inst.component_mask());
+                    info.use_registers(
+                        inst.source_register_a(),
+                        texture_coordinates(inst.texture_type()),
+                    );
+                    info.use_registers(
+                        inst.source_register_b(),
+                        1 + u32::from(inst.ll_flag())
+                            + u32::from(inst.aoffi_flag())
+                            + u32::from(inst.ms_flag()),
+                    );
+                }
+                DecodedInstruction::TLD4(inst) => {
+                    info.use_components(inst.destination_register(), inst.component_mask());
+                    info.use_registers(
+                        inst.source_register_a(),
+                        texture_coordinates(inst.texture_type()),
+                    );
+                    info.use_registers(
+                        inst.source_register_b(),
+                        gather_offset_width(inst.offset_mode()) + u32::from(inst.dc_flag()),
+                    );
+                }
+                DecodedInstruction::TLD4_B(inst) => {
+                    info.use_components(inst.destination_register(), inst.component_mask());
+                    info.use_registers(
+                        inst.source_register_a(),
+                        texture_coordinates(inst.texture_type()),
+                    );
+                    info.use_registers(
+                        inst.source_register_b(),
+                        1 + gather_offset_width(inst.offset_mode()) + u32::from(inst.dc_flag()),
+                    );
+                }
+                DecodedInstruction::TMML(inst) => {
+                    info.use_components(inst.destination_register(), inst.component_mask());
+                    info.use_registers(
+                        inst.source_register_a(),
+                        texture_coordinates(inst.texture_type()),
+                    );
+                }
+                DecodedInstruction::TMML_B(inst) => {
+                    info.use_components(inst.destination_register(), inst.component_mask());
+                    info.use_registers(
+                        inst.source_register_a(),
+                        texture_coordinates(inst.texture_type()),
+                    );
+                    info.use_registers(inst.source_register_b(), 1);
+                }
+                // The derivatives along X and Y of every coordinate follow the handle.
+                DecodedInstruction::TXD(inst) => {
+                    info.use_components(inst.destination_register(), inst.component_mask());
+                    info.use_registers(
+                        inst.source_register_a(),
+                        texture_coordinates(inst.texture_type()),
+                    );
+                    info.use_registers(
+                        inst.source_register_b(),
+                        2 * texture_dimensions(inst.texture_type()) + u32::from(inst.aoffi_flag()),
+                    );
+                }
+                DecodedInstruction::TXD_B(inst) => {
+                    info.use_components(inst.destination_register(), inst.component_mask());
+                    info.use_registers(
+                        inst.source_register_a(),
+                        texture_coordinates(inst.texture_type()),
+                    );
+                    info.use_registers(
+                        inst.source_register_b(),
+                        1 + 2 * texture_dimensions(inst.texture_type())
+                            + u32::from(inst.aoffi_flag()),
+                    );
+                }
+                DecodedInstruction::TXQ(inst) | DecodedInstruction::TXQ_B(inst) => {
+                    info.use_components(inst.destination_register(), inst.component_mask())
+                }
+                // The scalar forms split up to four components between two register pairs.
+                DecodedInstruction::TEXS(inst) | DecodedInstruction::TLDS(inst) => {
+                    info.use_registers(inst.destination_register_a(), 2);
+                    info.use_registers(inst.destination_register_b(), 2);
+                }
+                DecodedInstruction::TLD4S(inst) => {
+                    info.use_registers(inst.destination_register_a(), 2);
+                    info.use_registers(inst.destination_register_b(), 2);
+                }
+                DecodedInstruction::SULD(inst) | DecodedInstruction::SUST(inst) => info
+                    .use_registers(
+                        inst.destination_register(),
+                        surface_width(inst.d_flag(), inst.format()),
+                    ),
+                DecodedInstruction::SULD_B(inst) | DecodedInstruction::SUST_B(inst) => info
+                    .use_registers(
+                        inst.destination_register(),
+                        surface_width(inst.d_flag(), inst.format()),
+                    ),
+                DecodedInstruction::BAR(inst) => {
+                    if inst.barrier_immediate_flag() {
+                        info.barriers |= 1 << (u32::from(inst.barrier()) % MAX_BARRIERS);
+                    } else {
+                        info.use_registers(inst.barrier(), 1);
+                        info.uses_indirect_barrier = true;
+                    }
+
+                    if !inst.thread_count_immediate_flag() {
+                        info.use_registers(inst.thread_count() as u8, 1);
+                    }
+                }
+                DecodedInstruction::CCTLL(_)
+                | DecodedInstruction::GETLMEMBASE(_)
+                | DecodedInstruction::SETLMEMBASE(_) => info.uses_local_memory = true,
+                _ => {}
+            }
+        }
+
+        info
+    }
+
+    /// Mark the consecutive registers of an operand as used, RZ is ignored.
+    fn use_registers(&mut self, first: u8, count: u32) {
+        if first != RZ && count != 0 {
+            self.register_count = self.register_count.max(u32::from(first) + count);
+        }
+    }
+
+    fn use_components(&mut self, first: u8, component_mask: u8) {
+        self.use_registers(first, (component_mask & 0xF).count_ones());
+    }
+
+    /// Addresses are held by a register pair with the E flag.
+    fn use_address(&mut self, register: u8, e_flag: bool) {
+        self.use_registers(register, if e_flag { 2 } else { 1 });
+    }
+
+    /// Number of barriers to allocate, the highest barrier used plus one.
+    ///
+    /// NOTE: the barrier is unknown when it comes from a register, assume the worst.
+    pub fn barrier_count(&self) -> u32 {
+        if self.uses_indirect_barrier {
+            MAX_BARRIERS
+        } else {
+            u16::BITS - self.barriers.leading_zeros()
+        }
+    }
+}
+
+/// Number of registers holding a value of an atomic operation.
+fn atom_width(type_size: AtomPrimitiveType) -> u32 {
+    match type_size {
+        AtomPrimitiveType::U64 | AtomPrimitiveType::S64 => 2,
+        _ => 1,
+    }
+}
+
+fn cas_width(type_size: AtomicCasPrimitiveType) -> u32 {
+    match type_size {
+        AtomicCasPrimitiveType::U64 => 2,
+        _ => 1,
+    }
+}
+
+/// Number of coordinates of a texture, cube maps are sampled with a direction.
+fn texture_dimensions(texture_type: TextureType) -> u32 {
+    match texture_type {
+        TextureType::TEXTURE_1D | TextureType::TEXTURE_1D_ARRAY => 1,
+        TextureType::TEXTURE_2D | TextureType::TEXTURE_2D_ARRAY => 2,
+        _ => 3,
+    }
+}
+
+/// Number of registers holding the coordinates of a texture instruction, the array index included.
+fn texture_coordinates(texture_type: TextureType) -> u32 {
+    let array = match texture_type {
+        TextureType::TEXTURE_1D_ARRAY
+        | TextureType::TEXTURE_2D_ARRAY
+        | TextureType::TEXTURE_3D_ARRAY
+        | TextureType::TEXTURE_CUBE_ARRAY => 1,
+        _ => 0,
+    };
+
+    array + texture_dimensions(texture_type)
+}
+
+/// Number of registers holding the LOD or the bias of a texture instruction.
+fn lod_width(lod_mode: TextureLodMode) -> u32 {
+    match lod_mode {
+        TextureLodMode::NONE | TextureLodMode::LZ => 0,
+        _ => 1,
+    }
+}
+
+/// Number of registers holding the offsets of a gather, PTP gives one per texel.
+fn gather_offset_width(offset_mode: TextureGatherOffset) -> u32 {
+    match offset_mode {
+        TextureGatherOffset::NONE => 0,
+        TextureGatherOffset::AOFFI => 1,
+        _ => 2,
+    }
+}
+
+/// Number of registers loaded or stored by a surface instruction.
+fn surface_width(d_flag: bool, format: u8) -> u32 {
+    if !d_flag {
+        return u32::from(format & 0xF).count_ones();
+    }
+
+    match SurfaceSize::from(format) {
+        SurfaceSize::B64 => 2,
+        SurfaceSize::B128 => 4,
+        _ => 1,
+    }
+}
+
+impl fmt::Display for ProgramInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Registers: {}", self.register_count)?;
+
+        write!(f, "Predicates:")?;
+
+        for predicate in 0..PT {
+            if self.predicates & (1 << predicate) != 0 {
+                write!(f, " P{}", predicate)?;
+            }
+        }
+
+        writeln!(f)?;
+        writeln!(f, "Barriers: {}", self.barrier_count())?;
+        writeln!(f, "Shared memory: {}", self.uses_shared_memory)?;
+        write!(f, "Local memory: {}", self.uses_local_memory)
+    }
+}
@@ -0,0 +1,278 @@
+use std::convert::TryInto;
+use std::error::Error;
+use std::fmt;
+
+use super::definition::{ControlCode, Opcode};
+use super::encoder::{self, EncodeError};
+use super::target::Target;
+
+mod info;
+#[cfg(test)]
+mod tests;
+
+pub use info::*;
+
+/// Number of instructions following each scheduling control word.
+const INSTRUCTIONS_PER_GROUP: usize = 3;
+
+/// Size of a group made of a scheduling control word and its instructions, in bytes.
+pub const GROUP_SIZE: u64 = 0x20;
+
+/// Address of an instruction in the shader binary.
+///
+/// A scheduling control word precedes every group of three instructions.
+pub fn instruction_address(index: usize) -> u64 {
+    let group = (index / INSTRUCTIONS_PER_GROUP) as u64;
+    let slot = (index % INSTRUCTIONS_PER_GROUP) as u64;
+
+    group * GROUP_SIZE + (slot + 1) * 8
+}
+
+/// Number of instructions once padded so that the code size is a multiple of the alignment.
+pub fn aligned_instruction_count(count: usize, alignment: u64) -> usize {
+    debug_assert!(alignment.is_multiple_of(GROUP_SIZE));
+
+    count.next_multiple_of((alignment / GROUP_SIZE) as usize * INSTRUCTIONS_PER_GROUP)
+}
+
+/// Index of the instruction at an address of the shader binary.
+///
+/// Returns None if the address is misaligned or points to a scheduling control word.
+pub fn instruction_index(address: u64) -> Option<usize> {
+    let slot = (address % GROUP_SIZE) / 8;
+
+    if !address.is_multiple_of(8) || slot == 0 {
+        return None;
+    }
+
+    Some((address / GROUP_SIZE) as usize * INSTRUCTIONS_PER_GROUP + slot as usize - 1)
+}
+
+/// The sequence appended after the last instruction of a program.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Terminator {
+    Nothing,
+    /// A BRA to itself, catching warps running past the end of the program.
+    SelfBranch,
+}
+
+/// How the end of a program is laid out.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct EndOfProgram {
+    pub terminator: Terminator,
+    /// The code is padded with NOPs to a multiple of this size, in bytes.
+    pub alignment: u64,
+}
+
+/// The containers a program can be written to.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Container {
+    /// The code, optionally preceded by a SPH.
+    Raw,
+    Cubin,
+    Nvn,
+}
+
+/// Error returned when an end of program cannot be used with a container.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum EndOfProgramError {
+    /// The alignment isn't a power of two multiple of GROUP_SIZE.
+    InvalidAlignment(u64),
+    /// The container requires a larger alignment.
+    InsufficientAlignment(Container, u64),
+    /// The container requires the program to end with a BRA to itself.
+    MissingSelfBranch(Container),
+}
+
+impl fmt::Display for EndOfProgramError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EndOfProgramError::InvalidAlignment(alignment) => write!(
+                f,
+                "invalid alignment 0x{:x}, expected a power of two multiple of 0x{:x}",
+                alignment, GROUP_SIZE
+            ),
+            EndOfProgramError::InsufficientAlignment(container, alignment) => write!(
+                f,
+                "{:?} requires an alignment of at least 0x{:x}",
+                container, alignment
+            ),
+            EndOfProgramError::MissingSelfBranch(container) => {
+                write!(
+                    f,
+                    "{:?} requires programs to end with a BRA to itself",
+                    container
+                )
+            }
+        }
+    }
+}
+
+impl Error for EndOfProgramError {}
+
+impl Container {
+    /// The end of program used when none is specified.
+    ///
+    /// NOTE: nvcc and the NVN toolchains end programs with a BRA to itself and pad them to 0x80 bytes.
+    pub fn default_end_of_program(self) -> EndOfProgram {
+        match self {
+            Container::Raw => EndOfProgram {
+                terminator: Terminator::Nothing,
+                alignment: GROUP_SIZE,
+            },
+            Container::Cubin | Container::Nvn => EndOfProgram {
+                terminator: Terminator::SelfBranch,
+                alignment: 0x80,
+            },
+        }
+    }
+
+    /// Check if an end of program can be used with the container.
+    pub fn validate(self, end: &EndOfProgram) -> Result<(), EndOfProgramError> {
+        if !end.alignment.is_power_of_two() || end.alignment < GROUP_SIZE {
+            return Err(EndOfProgramError::InvalidAlignment(end.alignment));
+        }
+
+        if self == Container::Nvn {
+            let required = self.default_end_of_program();
+
+            if end.terminator != Terminator::SelfBranch {
+                return Err(EndOfProgramError::MissingSelfBranch(self));
+            }
+
+            if end.alignment < required.alignment {
+                return Err(EndOfProgramError::InsufficientAlignment(
+                    self,
+                    required.alignment,
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// An assembled program for a given target.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Program {
+    target: Target,
+    instructions: Vec<u64>,
+}
+
+impl Program {
+    pub fn new(target: Target) -> Self {
+        Program {
+            target,
+            instructions: Vec::new(),
+        }
+    }
+
+    /// Create a program from binary code, scheduling control words are dropped.
+    ///
+    /// NOTE: the NOPs padding the code are kept, trailing bytes not making a whole instruction are ignored.
+    pub fn from_code(target: Target, code: &[u8]) -> Self {
+        let instructions = code
+            .chunks_exact(8)
+            .enumerate()
+            .filter(|(index, _)| index % (INSTRUCTIONS_PER_GROUP + 1) != 0)
+            .map(|(_, bytes)| u64::from_le_bytes(bytes.try_into().unwrap()))
+            .collect();
+
+        Program {
+            target,
+            instructions,
+        }
+    }
+
+    pub fn target(&self) -> Target {
+        self.target
+    }
+
+    pub fn push(&mut self, instruction: u64) {
+        self.instructions.push(instruction);
+    }
+
+    pub fn instructions(&self) -> &[u64] {
+        &self.instructions
+    }
+
+    /// Append the terminator and pad the program to the alignment of the end of program.
+    pub fn finalize(&mut self, end: &EndOfProgram) -> Result<(), EncodeError> {
+        if end.terminator == Terminator::SelfBranch {
+            // NOTE: the offset is relative to the following instruction.
+            let branch = encoder::encode_bra(self.target, 7, false, ControlCode::TRUE, -8)?;
+
+            self.instructions.push(branch);
+        }
+
+        self.align(end.alignment)
+    }
+
+    /// Check if the program was finalized with the end of program of the container.
+    pub fn check_finalized(&self, container: Container) -> Result<(), EndOfProgramError> {
+        let end = container.default_end_of_program();
+
+        if end.terminator == Terminator::SelfBranch {
+            let branch = encoder::encode_bra(self.target, 7, false, ControlCode::TRUE, -8).ok();
+            let last = self
+                .instructions
+                .iter()
+                .rev()
+                .find(|instruction| Opcode::decode(**instruction) != Some(Opcode::NOP));
+
+            if last.copied() != branch {
+                return Err(EndOfProgramError::MissingSelfBranch(container));
+            }
+        }
+
+        if !self.code_size().is_multiple_of(end.alignment) {
+            return Err(EndOfProgramError::InsufficientAlignment(
+                container,
+                end.alignment,
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Pad the program with NOPs until the code size is a multiple of the alignment.
+    ///
+    /// NOTE: the alignment is a multiple of the group size, the next instruction starts a group.
+    pub fn align(&mut self, alignment: u64) -> Result<(), EncodeError> {
+        let nop = encoder::encode_nop(self.target, false, 7, false, 0, ControlCode::TRUE)?;
+        let count = aligned_instruction_count(self.instructions.len(), alignment);
+
+        self.instructions.resize(count, nop);
+
+        Ok(())
+    }
+
+    /// Size of the binary code of the program, see code.
+    pub fn code_size(&self) -> u64 {
+        self.instructions.len().div_ceil(INSTRUCTIONS_PER_GROUP) as u64 * GROUP_SIZE
+    }
+
+    /// The binary code of the program, scheduling control words included.
+    ///
+    /// The code is padded with NOPs to a multiple of 0x20 bytes.
+    pub fn code(&self) -> Result<Vec<u8>, EncodeError> {
+        // TODO: proper scheduling
+        let stub_sched_control = 0x1f8000fc0007e0u64;
+
+        let nop = encoder::encode_nop(self.target, false, 7, false, 0, ControlCode::TRUE)?;
+        let group_count = self.instructions.len().div_ceil(INSTRUCTIONS_PER_GROUP);
+        let mut code = Vec::with_capacity(group_count * GROUP_SIZE as usize);
+
+        for index in 0..group_count * INSTRUCTIONS_PER_GROUP {
+            if index % INSTRUCTIONS_PER_GROUP == 0 {
+                code.extend_from_slice(&stub_sched_control.to_le_bytes());
+            }
+
+            let instruction = self.instructions.get(index).copied().unwrap_or(nop);
+
+            code.extend_from_slice(&instruction.to_le_bytes());
+        }
+
+        Ok(code)
+    }
+}
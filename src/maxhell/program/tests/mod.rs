@@ -0,0 +1,448 @@
+use super::*;
+use crate::maxhell::definition::*;
+
+const TARGET: Target = Target::SM50;
+
+const BRA_SELF: u64 = 0xe240_0fff_ff87_000f;
+
+fn exit() -> u64 {
+    encoder::encode_exit(TARGET, 7, false, ControlCode::TRUE, false).unwrap()
+}
+
+#[test]
+fn addresses_skip_scheduling_words() {
+    assert_eq!(instruction_address(0), 0x8);
+    assert_eq!(instruction_address(3), 0x28);
+    assert_eq!(instruction_index(0x28), Some(3));
+    assert_eq!(instruction_index(0x20), None);
+}
+
+#[test]
+fn finalizes_programs() {
+    let mut program = Program::new(TARGET);
+    program.push(exit());
+
+    program
+        .finalize(&Container::Cubin.default_end_of_program())
+        .unwrap();
+
+    assert_eq!(program.instructions().len(), 12);
+    assert_eq!(program.instructions()[1], BRA_SELF);
+    assert_eq!(program.code().unwrap().len(), 0x80);
+
+    let mut program = Program::new(TARGET);
+    program.push(exit());
+
+    program
+        .finalize(&Container::Raw.default_end_of_program())
+        .unwrap();
+
+    assert_eq!(program.instructions().len(), 3);
+}
+
+#[test]
+fn validates_end_of_program() {
+    let end = |terminator, alignment| EndOfProgram {
+        terminator,
+        alignment,
+    };
+
+    assert_eq!(
+        Container::Raw.validate(&end(Terminator::Nothing, 0x20)),
+        Ok(())
+    );
+    assert_eq!(
+        Container::Raw.validate(&end(Terminator::Nothing, 0x30)),
+        Err(EndOfProgramError::InvalidAlignment(0x30))
+    );
+    assert_eq!(
+        Container::Nvn.validate(&end(Terminator::Nothing, 0x100)),
+        Err(EndOfProgramError::MissingSelfBranch(Container::Nvn))
+    );
+    assert_eq!(
+        Container::Nvn.validate(&end(Terminator::SelfBranch, 0x40)),
+        Err(EndOfProgramError::InsufficientAlignment(
+            Container::Nvn,
+            0x80
+        ))
+    );
+    assert_eq!(
+        Container::Cubin.validate(&end(Terminator::SelfBranch, 0x100)),
+        Ok(())
+    );
+}
+
+#[test]
+fn counts_barriers() {
+    let bar = |barrier, immediate| {
+        encoder::encode_bar(
+            TARGET,
+            7,
+            false,
+            BarMode::SYNC,
+            BarReductionOperation::POPC,
+            barrier,
+            immediate,
+            0,
+            false,
+            7,
+            false,
+        )
+        .unwrap()
+    };
+    let barrier_count = |instructions: &[u64]| {
+        let mut program = Program::new(TARGET);
+
+        for instruction in instructions {
+            program.push(*instruction);
+        }
+
+        ProgramInfo::from_program(&program).barrier_count()
+    };
+
+    assert_eq!(barrier_count(&[exit()]), 0);
+    assert_eq!(barrier_count(&[bar(3, true), bar(1, true), exit()]), 4);
+    assert_eq!(barrier_count(&[bar(2, false), exit()]), 16);
+}
+
+#[test]
+fn collects_program_info() {
+    let mut program = Program::new(TARGET);
+
+    program.push(
+        encoder::encode_atoms(
+            TARGET,
+            2,
+            true,
+            4,
+            1,
+            0xFF,
+            0x10,
+            AtomsPrimitiveType::S32,
+            AtomsOperation::ADD,
+        )
+        .unwrap(),
+    );
+    program.push(
+        encoder::encode_ald(
+            TARGET,
+            7,
+            false,
+            8,
+            0xFF,
+            0xFF,
+            false,
+            false,
+            AtributeLoadMode::M96,
+            0x80,
+        )
+        .unwrap(),
+    );
+    program.push(exit());
+
+    let info = ProgramInfo::from_program(&program);
+
+    assert_eq!(info.register_count, 11);
+    assert_eq!(info.predicates, 1 << 2);
+    assert!(info.uses_shared_memory);
+    assert!(!info.uses_local_memory);
+    assert_eq!(
+        info.to_string(),
+        "Registers: 11\nPredicates: P2\nBarriers: 0\nShared memory: true\nLocal memory: false"
+    );
+
+    program.push(encoder::encode_get_lmembase(TARGET, 42).unwrap());
+
+    let info = ProgramInfo::from_program(&program);
+
+    assert_eq!(info.register_count, 43);
+    assert!(info.uses_local_memory);
+}
+
+#[test]
+fn counts_wide_operands() {
+    let register_count = |instruction: u64| {
+        let mut program = Program::new(TARGET);
+
+        program.push(instruction);
+
+        ProgramInfo::from_program(&program).register_count
+    };
+
+    // 64-bit values and addresses are held by register pairs.
+    let atom = encoder::encode_atom(
+        TARGET,
+        7,
+        false,
+        4,
+        6,
+        2,
+        0,
+        AtomPrimitiveType::U64,
+        AtomOperation::ADD,
+        true,
+    )
+    .unwrap();
+    assert_eq!(register_count(atom), 8);
+
+    let red = encoder::encode_red(
+        TARGET,
+        7,
+        false,
+        2,
+        8,
+        0,
+        AtomPrimitiveType::S64,
+        AtomOperation::MIN,
+        false,
+    )
+    .unwrap();
+    assert_eq!(register_count(red), 10);
+
+    // The stored value follows the comparison value.
+    let atoms_cas = encoder::encode_atoms_cas(
+        TARGET,
+        7,
+        false,
+        0,
+        1,
+        9,
+        0,
+        AtomicCasPrimitiveType::U32,
+        AtomsCasOperation::CAS,
+    )
+    .unwrap();
+    assert_eq!(register_count(atoms_cas), 10);
+
+    let tex = encoder::encode_tex(
+        TARGET,
+        7,
+        false,
+        12,
+        0,
+        0xFF,
+        0,
+        false,
+        TextureType::TEXTURE_2D,
+        TextureLodMode::NONE,
+        0b1011,
+        false,
+        false,
+        false,
+        false,
+        false,
+    )
+    .unwrap();
+    assert_eq!(register_count(tex), 15);
+
+    let suld = encoder::encode_suld(
+        TARGET,
+        7,
+        false,
+        20,
+        0,
+        0,
+        false,
+        SurfaceType::SURFACE_2D,
+        true,
+        SurfaceSize::B128,
+        0,
+        SurfaceClampMode::IGN,
+    )
+    .unwrap();
+    assert_eq!(register_count(suld), 24);
+
+    let sust = encoder::encode_sust(
+        TARGET,
+        7,
+        false,
+        0,
+        16,
+        0,
+        false,
+        SurfaceType::SURFACE_2D,
+        false,
+        SurfaceSize::U8,
+        0b0011,
+        SurfaceClampMode::IGN,
+    )
+    .unwrap();
+    assert_eq!(register_count(sust), 18);
+
+    // The barrier and the thread count can be given by registers.
+    let bar = encoder::encode_bar(
+        TARGET,
+        7,
+        false,
+        BarMode::SYNC,
+        BarReductionOperation::POPC,
+        30,
+        false,
+        40,
+        false,
+        7,
+        false,
+    )
+    .unwrap();
+    assert_eq!(register_count(bar), 41);
+}
+
+#[test]
+fn counts_texture_operands() {
+    let register_count = |instruction: u64| {
+        let mut program = Program::new(TARGET);
+
+        program.push(instruction);
+
+        ProgramInfo::from_program(&program).register_count
+    };
+
+    // The array index precedes the coordinates, the LOD, offsets and reference follow.
+    let tex = |source_register_a, source_register_b, texture_type, lod_mode, aoffi, dc| {
+        encoder::encode_tex(
+            TARGET,
+            7,
+            false,
+            0,
+            source_register_a,
+            source_register_b,
+            0,
+            false,
+            texture_type,
+            lod_mode,
+            0x1,
+            aoffi,
+            dc,
+            false,
+            false,
+            false,
+        )
+        .unwrap()
+    };
+
+    assert_eq!(
+        register_count(tex(
+            20,
+            0xFF,
+            TextureType::TEXTURE_2D_ARRAY,
+            TextureLodMode::LZ,
+            false,
+            false
+        )),
+        23
+    );
+    assert_eq!(
+        register_count(tex(
+            4,
+            30,
+            TextureType::TEXTURE_2D,
+            TextureLodMode::LL,
+            true,
+            true
+        )),
+        33
+    );
+    assert_eq!(
+        register_count(tex(
+            40,
+            0xFF,
+            TextureType::TEXTURE_CUBE_ARRAY,
+            TextureLodMode::NONE,
+            false,
+            false
+        )),
+        44
+    );
+
+    // The bindless handle comes first.
+    let tld = encoder::encode_tld(
+        TARGET,
+        7,
+        false,
+        0,
+        2,
+        10,
+        0,
+        true,
+        TextureType::TEXTURE_2D,
+        TextureLodMode::LL,
+        0x1,
+        true,
+        true,
+        false,
+    )
+    .unwrap();
+    assert_eq!(register_count(tld), 14);
+
+    let tld4 = encoder::encode_tld4(
+        TARGET,
+        7,
+        false,
+        0,
+        2,
+        10,
+        0,
+        false,
+        TextureType::TEXTURE_2D,
+        TextureComponent::from(0),
+        0x1,
+        TextureGatherOffset::PTP,
+        true,
+        false,
+        false,
+    )
+    .unwrap();
+    assert_eq!(register_count(tld4), 13);
+
+    let tmml = encoder::encode_tmml(
+        TARGET,
+        7,
+        false,
+        0,
+        6,
+        0xFF,
+        0,
+        false,
+        TextureType::TEXTURE_3D,
+        0x3,
+        false,
+        false,
+    )
+    .unwrap();
+    assert_eq!(register_count(tmml), 9);
+
+    // Every coordinate has a derivative along X and along Y.
+    let txd = encoder::encode_txd(
+        TARGET,
+        7,
+        false,
+        0,
+        2,
+        10,
+        0,
+        false,
+        TextureType::TEXTURE_2D,
+        0x1,
+        false,
+        false,
+    )
+    .unwrap();
+    assert_eq!(register_count(txd), 14);
+}
+
+#[test]
+fn ignores_immediate_streams() {
+    let register_count = |stream_immediate| {
+        let mut program = Program::new(TARGET);
+
+        program.push(
+            encoder::encode_out(TARGET, 7, false, 0, 0, 3, stream_immediate, OutMode::EMIT)
+                .unwrap(),
+        );
+
+        ProgramInfo::from_program(&program).register_count
+    };
+
+    assert_eq!(register_count(true), 1);
+    assert_eq!(register_count(false), 4);
+}
@@ -0,0 +1,393 @@
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+use bitfield::BitRange;
+
+use super::definition::*;
+use super::program::Program;
+use crate::enum_with_val;
+
+#[cfg(test)]
+mod tests;
+
+/// Size of the header in 32-bit words.
+pub const SPH_WORDS: usize = 20;
+
+/// Number of 32-bit attribute words described by the header, from address 0x0 to 0x3bc.
+pub const ATTRIBUTE_WORDS: usize = 240;
+
+const RZ: u8 = 0xFF;
+
+// NOTE: bit offsets of the attribute maps in the header.
+const VTG_IMAP_START: usize = 160;
+const VTG_OMAP_START: usize = 400;
+const PS_IMAP_SYSTEM_VALUES_AB_START: usize = 160;
+const PS_IMAP_GENERIC_VECTOR_START: usize = 192;
+const PS_IMAP_COLOR_START: usize = 448;
+const PS_IMAP_SYSTEM_VALUES_C_START: usize = 464;
+const PS_IMAP_FIXED_FNC_TEXTURE_START: usize = 480;
+
+enum_with_val! {
+    #[derive(PartialEq, Eq, Clone, Copy)]
+    pub struct ShaderType(u8) {
+        VERTEX = 1,
+        TESSELLATION_INIT = 2,
+        TESSELLATION = 3,
+        GEOMETRY = 4,
+        PIXEL = 5,
+    }
+}
+
+enum_with_val! {
+    #[derive(PartialEq, Eq, Clone, Copy)]
+    pub struct OutputTopology(u8) {
+        POINTLIST = 1,
+        LINESTRIP = 6,
+        TRIANGLESTRIP = 7,
+    }
+}
+
+enum_with_val! {
+    /// How a pixel shader input is interpolated, VTG stages only check if it's used.
+    #[derive(PartialEq, Eq, Clone, Copy)]
+    pub struct PixelImap(u8) {
+        UNUSED = 0,
+        CONSTANT = 1,
+        PERSPECTIVE = 2,
+        SCREEN_LINEAR = 3,
+    }
+}
+
+impl From<IpaInterpolationMode> for PixelImap {
+    fn from(mode: IpaInterpolationMode) -> Self {
+        match mode {
+            IpaInterpolationMode::MULTIPLY => PixelImap::PERSPECTIVE,
+            IpaInterpolationMode::CONSTANT => PixelImap::CONSTANT,
+            _ => PixelImap::SCREEN_LINEAR,
+        }
+    }
+}
+
+/// Error returned when parsing an unknown shader stage name.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ParseShaderTypeError(String);
+
+impl fmt::Display for ParseShaderTypeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Unknown shader stage \"{}\"", self.0)
+    }
+}
+
+impl Error for ParseShaderTypeError {}
+
+impl FromStr for ShaderType {
+    type Err = ParseShaderTypeError;
+
+    /// Parse a shader stage name, GL and NVIDIA names are both accepted.
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "vertex" | "vp" => Ok(ShaderType::VERTEX),
+            "tessellation-init" | "tess-control" | "tcp" => Ok(ShaderType::TESSELLATION_INIT),
+            "tessellation" | "tess-eval" | "tep" => Ok(ShaderType::TESSELLATION),
+            "geometry" | "gp" => Ok(ShaderType::GEOMETRY),
+            "pixel" | "fragment" | "fp" => Ok(ShaderType::PIXEL),
+            _ => Err(ParseShaderTypeError(String::from(value))),
+        }
+    }
+}
+
+bitfield! {
+    /// Raw layout of the header, bit n is the bit n % 32 of the word n / 32.
+    pub struct ShaderProgramHeaderData([u32]);
+    impl Debug;
+
+    pub u8, sph_type, set_sph_type: 4, 0;
+    pub u8, version, set_version: 9, 5;
+    pub u8, from into ShaderType, shader_type, set_shader_type: 13, 10;
+    pub mrt_enable, set_mrt_enable: 14;
+    pub kills_pixels, set_kills_pixels: 15;
+    pub does_global_store, set_does_global_store: 16;
+    pub u8, sass_version, set_sass_version: 20, 17;
+    pub does_load_or_store, set_does_load_or_store: 26;
+    pub does_fp64, set_does_fp64: 27;
+    pub u8, stream_out_mask, set_stream_out_mask: 31, 28;
+    pub u32, local_memory_low_size, set_local_memory_low_size: 55, 32;
+    pub u8, per_patch_attribute_count, set_per_patch_attribute_count: 63, 56;
+    pub u32, local_memory_high_size, set_local_memory_high_size: 87, 64;
+    pub u8, threads_per_input_primitive, set_threads_per_input_primitive: 95, 88;
+    pub u32, local_memory_crs_size, set_local_memory_crs_size: 119, 96;
+    pub u8, from into OutputTopology, output_topology, set_output_topology: 123, 120;
+    pub u16, max_output_vertex_count, set_max_output_vertex_count: 139, 128;
+    pub u8, store_req_start, set_store_req_start: 147, 140;
+    pub u8, store_req_end, set_store_req_end: 159, 152;
+
+    // NOTE: only used by pixel shaders.
+    pub u32, omap_target, set_omap_target: 607, 576;
+    pub omap_sample_mask, set_omap_sample_mask: 608;
+    pub omap_depth, set_omap_depth: 609;
+}
+
+/// Attribute words used by a shader, indexed by attribute address.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AttributeMap([PixelImap; ATTRIBUTE_WORDS]);
+
+impl Default for AttributeMap {
+    fn default() -> Self {
+        AttributeMap([PixelImap::UNUSED; ATTRIBUTE_WORDS])
+    }
+}
+
+impl AttributeMap {
+    /// Interpolation of the attribute word at an address, addresses past the map are unused.
+    pub fn get(&self, address: u16) -> PixelImap {
+        self.0
+            .get(address as usize / 4)
+            .copied()
+            .unwrap_or(PixelImap::UNUSED)
+    }
+
+    pub fn is_used(&self, address: u16) -> bool {
+        self.get(address) != PixelImap::UNUSED
+    }
+
+    /// Mark the attribute word at an address as used, addresses past the map are ignored.
+    pub fn set(&mut self, address: u16, mode: PixelImap) {
+        debug_assert!(address.is_multiple_of(4));
+
+        if let Some(entry) = self.0.get_mut(address as usize / 4) {
+            *entry = mode;
+        }
+    }
+
+    /// Mark consecutive attribute words starting at an address as used.
+    pub fn set_range(&mut self, address: u16, count: u16, mode: PixelImap) {
+        for index in 0..count {
+            self.set(address.wrapping_add(index * 4), mode);
+        }
+    }
+
+    // NOTE: indirect accesses can reach any generic attribute.
+    fn set_generic_vectors(&mut self, mode: PixelImap) {
+        self.set_range(0x80, 0x80, mode);
+    }
+
+    fn used_words(&self) -> impl Iterator<Item = (usize, PixelImap)> + '_ {
+        self.0
+            .iter()
+            .copied()
+            .enumerate()
+            .filter(|(_, mode)| *mode != PixelImap::UNUSED)
+    }
+}
+
+/// Shader Program Header preceding the code of graphics shaders.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShaderProgramHeader {
+    pub shader_type: ShaderType,
+    pub kills_pixels: bool,
+    pub does_global_store: bool,
+    pub does_load_or_store: bool,
+    /// Local memory per thread, in bytes.
+    pub local_memory_size: u32,
+    /// Local memory used to spill the reconvergence stack, in bytes.
+    pub crs_size: u32,
+    /// Only used by geometry shaders.
+    pub output_topology: OutputTopology,
+    /// Only used by geometry shaders.
+    pub max_output_vertex_count: u16,
+    pub threads_per_input_primitive: u8,
+    pub inputs: AttributeMap,
+    /// Only used by VTG stages.
+    pub outputs: AttributeMap,
+    /// Attribute words of the outputs read back by the shader, as an inclusive range.
+    pub output_reads: Option<(u8, u8)>,
+    /// Components of the render targets written by a pixel shader, 4 bits per target.
+    pub omap_targets: u32,
+    pub omap_sample_mask: bool,
+    pub omap_depth: bool,
+}
+
+impl ShaderProgramHeader {
+    pub fn new(shader_type: ShaderType) -> Self {
+        ShaderProgramHeader {
+            shader_type,
+            kills_pixels: false,
+            does_global_store: false,
+            does_load_or_store: false,
+            local_memory_size: 0,
+            crs_size: 0,
+            output_topology: OutputTopology::TRIANGLESTRIP,
+            max_output_vertex_count: 0,
+            threads_per_input_primitive: 1,
+            inputs: AttributeMap::default(),
+            outputs: AttributeMap::default(),
+            output_reads: None,
+            omap_targets: 0,
+            omap_sample_mask: false,
+            omap_depth: false,
+        }
+    }
+
+    /// Derive a header from the instructions of a program.
+    ///
+    /// The render targets written by a pixel shader cannot be deduced from its code, the
+    /// four components of the first one are assumed to be written.
+    pub fn from_program(program: &Program, shader_type: ShaderType) -> Self {
+        let mut header = ShaderProgramHeader::new(shader_type);
+
+        if shader_type == ShaderType::PIXEL {
+            header.omap_targets = 0xF;
+        }
+
+        for instruction in program.instructions() {
+            let instruction = match DecodedInstruction::decode(*instruction) {
+                Some(instruction) => instruction,
+                None => continue,
+            };
+
+            match instruction {
+                DecodedInstruction::KIL(_) => header.kills_pixels = true,
+                DecodedInstruction::ALD(inst) => {
+                    let address = inst.load_offset() as u16;
+                    let count = u16::from(u8::from(inst.mode())) + 1;
+
+                    if inst.o_flag() {
+                        header.add_output_read(address, count);
+                    } else {
+                        header
+                            .inputs
+                            .set_range(address, count, PixelImap::PERSPECTIVE);
+
+                        if inst.source_offset_register() != RZ {
+                            header.inputs.set_generic_vectors(PixelImap::PERSPECTIVE);
+                        }
+                    }
+                }
+                DecodedInstruction::AST(inst) => {
+                    let address = inst.load_offset() as u16;
+                    let count = u16::from(u8::from(inst.mode())) + 1;
+
+                    header
+                        .outputs
+                        .set_range(address, count, PixelImap::PERSPECTIVE);
+
+                    if inst.source_offset_register() != RZ {
+                        header.outputs.set_generic_vectors(PixelImap::PERSPECTIVE);
+                    }
+                }
+                DecodedInstruction::IPA(inst) => {
+                    let mode = PixelImap::from(inst.interpolation_mode());
+
+                    header
+                        .inputs
+                        .set(u16::from(inst.attribute_shr_2()) << 2, mode);
+
+                    if inst.idx_flag() {
+                        header.inputs.set_generic_vectors(mode);
+                    }
+                }
+                DecodedInstruction::ATOM(_)
+                | DecodedInstruction::ATOM_CAS(_)
+                | DecodedInstruction::RED(_)
+                | DecodedInstruction::SUST(_)
+                | DecodedInstruction::SUST_B(_)
+                | DecodedInstruction::SUATOM(_)
+                | DecodedInstruction::SURED(_) => {
+                    header.does_global_store = true;
+                    header.does_load_or_store = true;
+                }
+                DecodedInstruction::SULD(_) | DecodedInstruction::SULD_B(_) => {
+                    header.does_load_or_store = true;
+                }
+                _ => {}
+            }
+        }
+
+        header
+    }
+
+    fn add_output_read(&mut self, address: u16, count: u16) {
+        let start = (address / 4).min(0xFF) as u8;
+        let end = ((address / 4) + count - 1).min(0xFF) as u8;
+
+        self.output_reads = Some(match self.output_reads {
+            Some((min, max)) => (min.min(start), max.max(end)),
+            None => (start, end),
+        });
+    }
+
+    /// Encode the header to its raw layout.
+    pub fn encode(&self) -> [u32; SPH_WORDS] {
+        let mut data = ShaderProgramHeaderData([0; SPH_WORDS]);
+        let is_pixel = self.shader_type == ShaderType::PIXEL;
+
+        data.set_sph_type(if is_pixel { 2 } else { 1 });
+        data.set_version(3);
+        data.set_shader_type(self.shader_type);
+        data.set_mrt_enable(self.omap_targets & !0xF != 0);
+        data.set_kills_pixels(self.kills_pixels);
+        data.set_does_global_store(self.does_global_store);
+        data.set_sass_version(1);
+        data.set_does_load_or_store(self.does_load_or_store);
+        data.set_local_memory_low_size(self.local_memory_size);
+        data.set_local_memory_crs_size(self.crs_size);
+        data.set_threads_per_input_primitive(self.threads_per_input_primitive);
+
+        if self.shader_type == ShaderType::GEOMETRY {
+            data.set_output_topology(self.output_topology);
+            data.set_max_output_vertex_count(self.max_output_vertex_count);
+        }
+
+        if is_pixel {
+            self.encode_pixel_maps(&mut data);
+        } else {
+            let (start, end) = self.output_reads.unwrap_or((0xFF, 0));
+
+            data.set_store_req_start(start);
+            data.set_store_req_end(end);
+
+            for (index, _) in self.inputs.used_words() {
+                data.set_bit_range(VTG_IMAP_START + index, VTG_IMAP_START + index, 1u8);
+            }
+
+            for (index, _) in self.outputs.used_words() {
+                data.set_bit_range(VTG_OMAP_START + index, VTG_OMAP_START + index, 1u8);
+            }
+        }
+
+        data.0
+    }
+
+    fn encode_pixel_maps(&self, data: &mut ShaderProgramHeaderData<[u32; SPH_WORDS]>) {
+        for (index, mode) in self.inputs.used_words() {
+            let address = index * 4;
+
+            // NOTE: system values only have a bit, others have their interpolation mode.
+            let (start, width) = match address {
+                0x000..=0x07c => (PS_IMAP_SYSTEM_VALUES_AB_START + index, 1),
+                0x080..=0x27c => (PS_IMAP_GENERIC_VECTOR_START + (index - 0x20) * 2, 2),
+                0x280..=0x29c => (PS_IMAP_COLOR_START + (index - 0xa0) * 2, 2),
+                0x2c0..=0x2fc => (PS_IMAP_SYSTEM_VALUES_C_START + (index - 0xb0), 1),
+                0x300..=0x39c => (PS_IMAP_FIXED_FNC_TEXTURE_START + (index - 0xc0) * 2, 2),
+                // TODO: figure out how back colors are declared.
+                _ => continue,
+            };
+
+            let value = if width == 1 { 1 } else { u8::from(mode) };
+
+            data.set_bit_range(start + width - 1, start, value);
+        }
+
+        data.set_omap_target(self.omap_targets);
+        data.set_omap_sample_mask(self.omap_sample_mask);
+        data.set_omap_depth(self.omap_depth);
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.encode()
+            .iter()
+            .flat_map(|word| word.to_le_bytes())
+            .collect()
+    }
+}
@@ -0,0 +1,147 @@
+use super::*;
+use crate::maxhell::encoder;
+use crate::maxhell::target::Target;
+
+const TARGET: Target = Target::SM50;
+
+fn program(instructions: &[u64]) -> Program {
+    let mut program = Program::new(TARGET);
+
+    for instruction in instructions {
+        program.push(*instruction);
+    }
+
+    program
+}
+
+fn exit() -> u64 {
+    encoder::encode_exit(TARGET, 7, false, ControlCode::TRUE, false).unwrap()
+}
+
+#[test]
+fn vertex_shader_maps_attributes() {
+    let program = program(&[
+        encoder::encode_ald(
+            TARGET,
+            7,
+            false,
+            0,
+            RZ,
+            RZ,
+            false,
+            false,
+            AtributeLoadMode::M64,
+            0x80,
+        )
+        .unwrap(),
+        encoder::encode_ast(
+            TARGET,
+            7,
+            false,
+            0,
+            RZ,
+            RZ,
+            false,
+            AtributeLoadMode::M128,
+            0x70,
+        )
+        .unwrap(),
+        exit(),
+    ]);
+
+    let header = ShaderProgramHeader::from_program(&program, ShaderType::VERTEX);
+    let words = header.encode();
+
+    assert!(header.inputs.is_used(0x84));
+    assert!(!header.inputs.is_used(0x88));
+    assert!(header.outputs.is_used(0x7c));
+
+    assert_eq!(words[0], 0x20061 | (1 << 10));
+    // StoreReqStart is 0xff when no output is read.
+    assert_eq!(words[4], 0xff << 12);
+    assert_eq!(words[6], 0x3);
+    // Outputs start at bit 400, the position is at 0x70.
+    assert_eq!(words[13], 0xf << 12);
+}
+
+#[test]
+fn pixel_shader_maps_interpolation_and_kil() {
+    let program = program(&[
+        encoder::encode_ipa(
+            TARGET,
+            7,
+            false,
+            0,
+            RZ,
+            RZ,
+            RZ,
+            0x84,
+            false,
+            IpaInterpolationMode::MULTIPLY,
+            IpaSampleMode::DEFAULT,
+            false,
+        )
+        .unwrap(),
+        encoder::encode_ipa(
+            TARGET,
+            7,
+            false,
+            1,
+            RZ,
+            RZ,
+            RZ,
+            0x90,
+            false,
+            IpaInterpolationMode::CONSTANT,
+            IpaSampleMode::DEFAULT,
+            false,
+        )
+        .unwrap(),
+        encoder::encode_kil(TARGET, 0, false, ControlCode::TRUE).unwrap(),
+        exit(),
+    ]);
+
+    let header = ShaderProgramHeader::from_program(&program, ShaderType::PIXEL);
+    let words = header.encode();
+
+    assert!(header.kills_pixels);
+    assert_eq!(header.inputs.get(0x84), PixelImap::PERSPECTIVE);
+    assert_eq!(header.inputs.get(0x90), PixelImap::CONSTANT);
+
+    assert_eq!(words[0], 0x20062 | (5 << 10) | 0x8000);
+    assert_eq!(words[6], (2 << 2) | (1 << 8));
+    assert_eq!(words[18], 0xf);
+    assert_eq!(header.to_bytes().len(), SPH_WORDS * 4);
+}
+
+#[test]
+fn output_reads_are_tracked() {
+    let program = program(&[
+        encoder::encode_ald(
+            TARGET,
+            7,
+            false,
+            0,
+            RZ,
+            RZ,
+            false,
+            true,
+            AtributeLoadMode::M128,
+            0x80,
+        )
+        .unwrap(),
+        exit(),
+    ]);
+
+    let header = ShaderProgramHeader::from_program(&program, ShaderType::TESSELLATION_INIT);
+
+    assert_eq!(header.output_reads, Some((0x20, 0x23)));
+    assert_eq!(header.encode()[4], (0x20 << 12) | (0x23 << 24));
+}
+
+#[test]
+fn parses_stage_names() {
+    assert_eq!("fragment".parse::<ShaderType>(), Ok(ShaderType::PIXEL));
+    assert_eq!("Vertex".parse::<ShaderType>(), Ok(ShaderType::VERTEX));
+    assert!("compute".parse::<ShaderType>().is_err());
+}
@@ -0,0 +1,115 @@
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+use super::definition::{AtomPrimitiveType, Opcode};
+
+/// The GPU architecture targeted by a program.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Default)]
+pub enum Target {
+    #[default]
+    SM50,
+    SM52,
+    SM53,
+    SM60,
+    SM61,
+    SM62,
+}
+
+impl Target {
+    /// The SM version number of the target, 53 for sm_53.
+    pub fn sm_version(self) -> u32 {
+        match self {
+            Target::SM50 => 50,
+            Target::SM52 => 52,
+            Target::SM53 => 53,
+            Target::SM60 => 60,
+            Target::SM61 => 61,
+            Target::SM62 => 62,
+        }
+    }
+
+    /// Lookup a target by its SM version number.
+    pub fn from_sm_version(sm_version: u32) -> Option<Target> {
+        match sm_version {
+            50 => Some(Target::SM50),
+            52 => Some(Target::SM52),
+            53 => Some(Target::SM53),
+            60 => Some(Target::SM60),
+            61 => Some(Target::SM61),
+            62 => Some(Target::SM62),
+            _ => None,
+        }
+    }
+
+    /// Check if an instruction is available on this target.
+    pub fn supports(self, opcode: Opcode) -> bool {
+        match opcode {
+            // Packed half-precision floats were introduced with the Tegra X1.
+            Opcode::HADD2
+            | Opcode::HADD2_IMM
+            | Opcode::HMUL2
+            | Opcode::HMUL2_IMM
+            | Opcode::HFMA2
+            | Opcode::HFMA2_IMM
+            | Opcode::HSETP2
+            | Opcode::HSETP2_IMM
+            | Opcode::HSET2
+            | Opcode::HSET2_IMM => self >= Target::SM53,
+            _ => true,
+        }
+    }
+
+    /// Check if atomics on the primitive type are available on this target.
+    pub fn supports_atomic_type(self, type_size: AtomPrimitiveType) -> bool {
+        match type_size {
+            // Packed half-precision atomics were introduced with Pascal.
+            AtomPrimitiveType::F16X2 => self >= Target::SM60,
+            _ => true,
+        }
+    }
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Target::SM50 => "sm_50",
+            Target::SM52 => "sm_52",
+            Target::SM53 => "sm_53",
+            Target::SM60 => "sm_60",
+            Target::SM61 => "sm_61",
+            Target::SM62 => "sm_62",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+/// Error returned when parsing an unknown target name.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ParseTargetError(String);
+
+impl fmt::Display for ParseTargetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Unknown target \"{}\"", self.0)
+    }
+}
+
+impl Error for ParseTargetError {}
+
+impl FromStr for Target {
+    type Err = ParseTargetError;
+
+    /// Parse a target name, both "sm_53" and "SM53" are accepted.
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().replace('_', "").as_str() {
+            "sm50" => Ok(Target::SM50),
+            "sm52" => Ok(Target::SM52),
+            "sm53" => Ok(Target::SM53),
+            "sm60" => Ok(Target::SM60),
+            "sm61" => Ok(Target::SM61),
+            "sm62" => Ok(Target::SM62),
+            _ => Err(ParseTargetError(String::from(value))),
+        }
+    }
+}
@@ -14,6 +14,9 @@ macro_rules! enum_with_val {
         $vis struct $ident($innervis $ty);
         impl $ident {
             $($(#[$varmeta])* $vis const $variant: $ident = $ident($num);)*
+
+            /// Names and values of the variants, in declaration order.
+            $vis const VARIANTS: &'static [(&'static str, u64)] = &[$((stringify!($variant), $num as u64)),*];
         }
 
         impl ::core::fmt::Debug for $ident {
@@ -39,3 +42,427 @@ macro_rules! enum_with_val {
         }
     }
 }
+
+/// Describes a field of an instruction layout declared with instruction_layout.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct FieldDefinition {
+    pub name: &'static str,
+    pub msb: u32,
+    pub lsb: u32,
+    pub signed: bool,
+    /// Names and values of the variants when the field holds an enum_with_val.
+    pub variants: &'static [(&'static str, u64)],
+    /// Subtracted from the value before storing it in the instruction.
+    pub bias: u64,
+}
+
+/// How the value of a field should be interpreted when printing or parsing it.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum FieldKind {
+    Register,
+    Predicate,
+    Flag,
+    Enum,
+    Immediate,
+}
+
+impl FieldDefinition {
+    pub const fn new(
+        name: &'static str,
+        msb: u32,
+        lsb: u32,
+        signed: bool,
+        variants: &'static [(&'static str, u64)],
+    ) -> Self {
+        FieldDefinition {
+            name,
+            msb,
+            lsb,
+            signed,
+            variants,
+            bias: 0,
+        }
+    }
+
+    pub const fn with_bias(self, bias: u64) -> Self {
+        FieldDefinition { bias, ..self }
+    }
+
+    pub const fn width(&self) -> u32 {
+        self.msb - self.lsb + 1
+    }
+
+    /// The bits covered by the field inside of an instruction.
+    pub const fn mask(&self) -> u64 {
+        (u64::MAX >> (64 - self.width())) << self.lsb
+    }
+
+    /// Extract the value of the field from an instruction.
+    pub fn extract(&self, instruction: u64) -> u64 {
+        (((instruction & self.mask()) >> self.lsb).wrapping_add(self.bias))
+            & (self.mask() >> self.lsb)
+    }
+
+    /// Replace the field inside of an instruction, extra bits of value are discarded.
+    pub fn insert(&self, instruction: u64, value: u64) -> u64 {
+        (instruction & !self.mask()) | ((value.wrapping_sub(self.bias) << self.lsb) & self.mask())
+    }
+
+    /// Sign extend a raw value of the field if the field is signed.
+    pub fn sign_extend(&self, value: u64) -> i64 {
+        if self.signed {
+            let shift = 64 - self.width();
+
+            ((value << shift) as i64) >> shift
+        } else {
+            value as i64
+        }
+    }
+
+    pub fn kind(&self) -> FieldKind {
+        // NOTE: the kind of a field is deduced from the naming convention of the layouts.
+        let base_name = self
+            .name
+            .strip_suffix("_a")
+            .or_else(|| self.name.strip_suffix("_b"))
+            .or_else(|| self.name.strip_suffix("_c"))
+            .unwrap_or(self.name);
+
+        if !self.variants.is_empty() {
+            FieldKind::Enum
+        } else if base_name.ends_with("predicate_register") {
+            FieldKind::Predicate
+        } else if base_name.ends_with("register") {
+            FieldKind::Register
+        } else if self.width() == 1 {
+            FieldKind::Flag
+        } else {
+            FieldKind::Immediate
+        }
+    }
+
+    /// Lookup the name of a value when the field holds an enum_with_val.
+    pub fn variant_name(&self, value: u64) -> Option<&'static str> {
+        self.variants
+            .iter()
+            .find(|(_, variant_value)| *variant_value == value)
+            .map(|(name, _)| *name)
+    }
+
+    /// Lookup the value of a variant when the field holds an enum_with_val.
+    pub fn variant_value(&self, name: &str) -> Option<u64> {
+        self.variants
+            .iter()
+            .find(|(variant_name, _)| *variant_name == name)
+            .map(|(_, value)| *value)
+    }
+}
+
+/// The bits covered by a set of fields.
+pub const fn fields_mask(fields: &[FieldDefinition]) -> u64 {
+    let mut mask = 0;
+    let mut i = 0;
+
+    while i < fields.len() {
+        mask |= fields[i].mask();
+        i += 1;
+    }
+
+    mask
+}
+
+/// Check that no instruction can be matched by two of the given opcode values and masks.
+pub const fn opcodes_are_unambiguous(opcodes: &[(u64, u64)]) -> bool {
+    let mut i = 0;
+
+    while i < opcodes.len() {
+        let mut j = i + 1;
+
+        while j < opcodes.len() {
+            let (value_a, mask_a) = opcodes[i];
+            let (value_b, mask_b) = opcodes[j];
+
+            if (value_a ^ value_b) & mask_a & mask_b == 0 {
+                return false;
+            }
+
+            j += 1;
+        }
+
+        i += 1;
+    }
+
+    true
+}
+
+/// Types that can be used by the fields of an instruction layout.
+pub trait FieldType {
+    const SIGNED: bool;
+}
+
+macro_rules! impl_field_type {
+    ($($ty:ty => $signed:expr),*) => {
+        $(impl FieldType for $ty {
+            const SIGNED: bool = $signed;
+        })*
+    };
+}
+
+impl_field_type!(
+    bool => false,
+    u8 => false,
+    u16 => false,
+    u32 => false,
+    u64 => false,
+    i8 => true,
+    i16 => true,
+    i32 => true,
+    i64 => true
+);
+
+/// Declares the layout of an instruction.
+///
+/// Fields use the bitfield syntax and the layout is turned into a bitfield struct
+/// exposing the description of every field as FIELDS. `source_predicate;` is a
+/// shorthand for the predicate guarding the instruction.
+///
+/// `pub ty, getter, setter: msb, lsb, bias n => raw_getter, raw_setter;` declares a field
+/// holding its value minus n, the raw accessors give the bits as stored.
+#[macro_export]
+macro_rules! instruction_layout {
+    ($(#[$meta:meta])* pub struct $name:ident { $($fields:tt)* }) => {
+        $crate::instruction_layout!(@munch [$(#[$meta])* $name] [] [] [] $($fields)*);
+    };
+    (@munch [$(#[$meta:meta])* $name:ident] [$($bitfields:tt)*] [$($definitions:expr,)*] [$($methods:tt)*]) => {
+        bitfield! {
+            $(#[$meta])*
+            #[derive(Clone, Copy, PartialEq, Eq)]
+            pub struct $name(u64);
+            impl Debug;
+
+            $($bitfields)*
+        }
+
+        impl $name {
+            pub const FIELDS: &'static [$crate::maxhell::util::FieldDefinition] = &[$($definitions,)*];
+
+            $($methods)*
+        }
+    };
+    (@munch $header:tt [$($bitfields:tt)*] [$($definitions:expr,)*] [$($methods:tt)*]
+        source_predicate; $($rest:tt)*) => {
+        $crate::instruction_layout!(@munch $header [$($bitfields)*] [$($definitions,)*] [$($methods)*]
+            pub u8, source_predicate_register, set_source_predicate_register: 18, 16;
+            pub invert_source_predicate, set_invert_source_predicate: 19;
+            $($rest)*);
+    };
+    (@munch $header:tt [$($bitfields:tt)*] [$($definitions:expr,)*] [$($methods:tt)*]
+        pub $ty:ty, from into $into:ty, $getter:ident, $setter:ident: $msb:expr, $lsb:expr; $($rest:tt)*) => {
+        $crate::instruction_layout!(@munch $header
+            [$($bitfields)* pub $ty, from into $into, $getter, $setter: $msb, $lsb;]
+            [$($definitions,)* $crate::maxhell::util::FieldDefinition::new(
+                stringify!($getter),
+                $msb,
+                $lsb,
+                <$ty as $crate::maxhell::util::FieldType>::SIGNED,
+                <$into>::VARIANTS,
+            ),]
+            [$($methods)*]
+            $($rest)*);
+    };
+    (@munch $header:tt [$($bitfields:tt)*] [$($definitions:expr,)*] [$($methods:tt)*]
+        pub $ty:ty, $getter:ident, $setter:ident: $msb:expr, $lsb:expr, bias $bias:expr =>
+        $raw_getter:ident, $raw_setter:ident; $($rest:tt)*) => {
+        $crate::instruction_layout!(@munch $header
+            [$($bitfields)* pub $ty, $raw_getter, $raw_setter: $msb, $lsb;]
+            [$($definitions,)* $crate::maxhell::util::FieldDefinition::new(
+                stringify!($getter),
+                $msb,
+                $lsb,
+                <$ty as $crate::maxhell::util::FieldType>::SIGNED,
+                &[],
+            ).with_bias($bias),]
+            [$($methods)*
+                pub fn $getter(&self) -> $ty {
+                    self.$raw_getter().wrapping_add($bias)
+                }
+
+                pub fn $setter(&mut self, value: $ty) {
+                    self.$raw_setter(value.wrapping_sub($bias))
+                }
+            ]
+            $($rest)*);
+    };
+    (@munch $header:tt [$($bitfields:tt)*] [$($definitions:expr,)*] [$($methods:tt)*]
+        pub $ty:ty, $getter:ident, $setter:ident: $msb:expr, $lsb:expr; $($rest:tt)*) => {
+        $crate::instruction_layout!(@munch $header
+            [$($bitfields)* pub $ty, $getter, $setter: $msb, $lsb;]
+            [$($definitions,)* $crate::maxhell::util::FieldDefinition::new(
+                stringify!($getter),
+                $msb,
+                $lsb,
+                <$ty as $crate::maxhell::util::FieldType>::SIGNED,
+                &[],
+            ),]
+            [$($methods)*]
+            $($rest)*);
+    };
+    (@munch $header:tt [$($bitfields:tt)*] [$($definitions:expr,)*] [$($methods:tt)*]
+        pub $ty:ty, $getter:ident, $setter:ident: $bit:expr; $($rest:tt)*) => {
+        $crate::instruction_layout!(@munch $header
+            [$($bitfields)* pub $ty, $getter, $setter: $bit;]
+            [$($definitions,)* $crate::maxhell::util::FieldDefinition::new(
+                stringify!($getter),
+                $bit,
+                $bit,
+                false,
+                &[],
+            ),]
+            [$($methods)*]
+            $($rest)*);
+    };
+    (@munch $header:tt [$($bitfields:tt)*] [$($definitions:expr,)*] [$($methods:tt)*]
+        pub $getter:ident, $setter:ident: $bit:expr; $($rest:tt)*) => {
+        $crate::instruction_layout!(@munch $header
+            [$($bitfields)* pub $getter, $setter: $bit;]
+            [$($definitions,)* $crate::maxhell::util::FieldDefinition::new(
+                stringify!($getter),
+                $bit,
+                $bit,
+                false,
+                &[],
+            ),]
+            [$($methods)*]
+            $($rest)*);
+    };
+}
+
+/// Declares the opcode table.
+///
+/// Every entry maps an opcode to its layout, the value of its opcode bits and the mask
+/// of those bits. The mask may not cover any field of the layout, this is checked at
+/// compile time along with the absence of ambiguities between opcodes. This generates
+/// the Opcode enum, the generic field encoder and the DecodedInstruction decoder.
+#[macro_export]
+macro_rules! opcodes {
+    ($($opcode:ident => $layout:ident($value:expr, $mask:expr)),* $(,)*) => {
+        #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+        #[allow(non_camel_case_types)]
+        pub enum Opcode {
+            $($opcode,)*
+        }
+
+        const _: () = {
+            $({
+                let value: u64 = $value;
+                let mask: u64 = $mask;
+
+                assert!(
+                    $crate::maxhell::util::fields_mask($layout::FIELDS) & mask == 0,
+                    concat!("fields of ", stringify!($layout), " overlap the opcode bits of ", stringify!($opcode))
+                );
+                assert!(
+                    value & !mask == 0,
+                    concat!("value of ", stringify!($opcode), " is not covered by its mask")
+                );
+            })*
+
+            assert!(
+                $crate::maxhell::util::opcodes_are_unambiguous(&[$(($value, $mask),)*]),
+                "some opcodes can match the same instruction"
+            );
+        };
+
+        impl Opcode {
+            /// Every opcode, in the order used when decoding.
+            pub const ALL: &'static [Opcode] = &[$(Opcode::$opcode,)*];
+
+            pub fn name(self) -> &'static str {
+                match self {
+                    $(Opcode::$opcode => stringify!($opcode),)*
+                }
+            }
+
+            /// The value of the opcode bits.
+            pub fn value(self) -> u64 {
+                match self {
+                    $(Opcode::$opcode => $value,)*
+                }
+            }
+
+            /// The bits of an instruction holding the opcode.
+            pub fn mask(self) -> u64 {
+                match self {
+                    $(Opcode::$opcode => $mask,)*
+                }
+            }
+
+            pub fn fields(self) -> &'static [$crate::maxhell::util::FieldDefinition] {
+                match self {
+                    $(Opcode::$opcode => $layout::FIELDS,)*
+                }
+            }
+
+            /// Lookup an opcode by name.
+            pub fn from_name(name: &str) -> Option<Opcode> {
+                Opcode::ALL.iter().copied().find(|opcode| opcode.name() == name)
+            }
+
+            /// Find the opcode of an instruction.
+            pub fn decode(instruction: u64) -> Option<Opcode> {
+                Opcode::ALL
+                    .iter()
+                    .copied()
+                    .find(|opcode| instruction & opcode.mask() == opcode.value())
+            }
+
+            /// Encode an instruction from the raw values of its fields, in the order of Opcode::fields.
+            pub fn encode(self, values: &[u64]) -> u64 {
+                debug_assert_eq!(values.len(), self.fields().len());
+
+                self.fields()
+                    .iter()
+                    .zip(values)
+                    .fold(self.value(), |instruction, (field, value)| field.insert(instruction, *value))
+            }
+        }
+
+        /// An instruction decoded to its layout.
+        #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+        #[allow(non_camel_case_types)]
+        pub enum DecodedInstruction {
+            $($opcode($layout),)*
+        }
+
+        impl DecodedInstruction {
+            pub fn decode(instruction: u64) -> Option<DecodedInstruction> {
+                Opcode::decode(instruction).map(|opcode| match opcode {
+                    $(Opcode::$opcode => DecodedInstruction::$opcode($layout(instruction)),)*
+                })
+            }
+
+            pub fn opcode(&self) -> Opcode {
+                match self {
+                    $(DecodedInstruction::$opcode(_) => Opcode::$opcode,)*
+                }
+            }
+
+            pub fn raw(&self) -> u64 {
+                match self {
+                    $(DecodedInstruction::$opcode(inst) => inst.0,)*
+                }
+            }
+
+            /// The raw values of the fields, in the order of Opcode::fields.
+            pub fn field_values(&self) -> Vec<u64> {
+                let raw = self.raw();
+
+                self.opcode()
+                    .fields()
+                    .iter()
+                    .map(|field| field.extract(raw))
+                    .collect()
+            }
+        }
+    };
+}
@@ -0,0 +1,58 @@
+#!/usr/bin/env bash
+# Regenerate the listing column of golden files with `nvdisasm -hex`.
+#
+# Usage: capture.sh <target>.golden...
+set -euo pipefail
+
+NVDISASM=${NVDISASM:-nvdisasm}
+
+# NOTE: Maxwell code groups three instructions behind a scheduling control word, the
+# instruction is disassembled at offset 0x8 of a group padded with NOPs.
+CONTROL=001ffc00fd4007ef
+NOP=50b0000000070f00
+
+binary=$(mktemp)
+trap 'rm -f "$binary"' EXIT
+
+# Write a 64-bit word given in hexadecimal, in little-endian order.
+word() {
+    local hex=$1
+    local i
+
+    for ((i = 14; i >= 0; i -= 2)); do
+        printf "\\x${hex:i:2}"
+    done
+}
+
+for golden in "$@"; do
+    # sm_50.golden is disassembled as SM50.
+    target=$(basename "$golden" .golden)
+    target=${target//_/}
+    target=${target^^}
+
+    while IFS= read -r line; do
+        if [[ -z ${line//[[:space:]]/} || $line == \#* ]]; then
+            printf '%s\n' "$line"
+            continue
+        fi
+
+        IFS='|' read -r assembly encoding _ <<< "$line"
+        assembly=$(sed 's/[[:space:]]*$//' <<< "$assembly")
+        encoding=$(tr -d '[:space:]' <<< "$encoding")
+
+        { word "$CONTROL"; word "${encoding#0x}"; word "$NOP"; word "$NOP"; } > "$binary"
+
+        listing=$("$NVDISASM" --binary "$target" -hex "$binary" \
+            | grep '/\*0008\*/' \
+            | sed -e 's@^[[:space:]]*/\*0008\*/[[:space:]]*@@' -e 's/[[:space:]]\+/ /g' -e 's/ $//')
+
+        if [[ -z $listing ]]; then
+            echo "$golden: nvdisasm printed nothing for $encoding" >&2
+            exit 1
+        fi
+
+        printf '%s | %s | %s\n' "$assembly" "$encoding" "$listing"
+    done < "$golden" > "$golden.new"
+
+    mv "$golden.new" "$golden"
+done